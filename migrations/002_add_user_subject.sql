@@ -0,0 +1,4 @@
+-- Record who owns each user: the subject (sub claim) of the caller who created it
+ALTER TABLE users ADD COLUMN IF NOT EXISTS subject VARCHAR(255);
+
+CREATE INDEX IF NOT EXISTS idx_users_subject ON users(subject);
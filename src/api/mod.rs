@@ -12,9 +12,19 @@ use axum::Router;
 use crate::core::router::AppState;
 use std::sync::Arc;
 
-/// Configure all API routes
+/// Configure all API routes that can be served without authentication
 pub fn configure() -> Router<Arc<AppState>> {
     Router::new()
         // User management endpoints
         .merge(users::configure())
 }
+
+/// Configure all API routes that need an authenticated caller
+///
+/// Mount these behind an auth layer; their handlers reject requests without
+/// a caller with 401.
+pub fn configure_protected() -> Router<Arc<AppState>> {
+    Router::new()
+        // User management endpoints
+        .merge(users::configure_protected())
+}
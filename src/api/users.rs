@@ -6,8 +6,8 @@ use axum::debug_handler;
 use axum::{
     Router,
     extract::{Json, Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     routing::{delete, get, post, put},
 };
 use serde::{Deserialize, Serialize};
@@ -16,8 +16,8 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::{
-    core::router::AppState,
-    repository::models::UserRole,
+    core::{auth::Principal, router::AppState, utils::conditional},
+    repository::{Repository, User, models::UserRole},
     services::{
         IUserService,
         error::ServiceError,
@@ -122,16 +122,83 @@ pub struct UpdateUserRequest {
     pub role: Option<String>,
 }
 
-/// Configure user routes
+/// Configure the user routes that only read users
 pub fn configure() -> Router<Arc<AppState>> {
     Router::new()
         .route("/users", get(get_all_users))
-        .route("/users", post(create_user))
         .route("/users/{id}", get(get_user))
+}
+
+/// Configure the user routes that create, change or delete users
+///
+/// Mount these behind an auth layer when auth is enabled: their handlers then
+/// need an authenticated [`Principal`] and check who may change what. With auth
+/// disabled they serve every caller.
+pub fn configure_protected() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/users", post(create_user))
         .route("/users/{id}", put(update_user))
         .route("/users/{id}", delete(delete_user))
 }
 
+/// The caller changing users, which is only optional while auth is disabled
+fn require_caller(
+    principal: Option<Principal>,
+    state: &AppState,
+) -> Result<Option<Principal>, (StatusCode, String)> {
+    if principal.is_none() && state.config.auth.enabled {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            "Authentication required".to_string(),
+        ));
+    }
+    Ok(principal)
+}
+
+/// Whether `principal` has one of the configured admin roles
+fn is_admin(principal: &Principal, state: &AppState) -> bool {
+    principal.has_any_role(&state.config.auth.entra.admin_roles)
+}
+
+/// Reject callers that are neither an admin nor the owner of `user`
+///
+/// A user is owned by the caller whose subject created it.
+fn require_owner_or_admin(
+    principal: Option<&Principal>,
+    state: &AppState,
+    user: &User,
+) -> Result<(), (StatusCode, String)> {
+    let Some(principal) = principal else {
+        return Ok(());
+    };
+    let is_owner = user
+        .subject
+        .as_deref()
+        .is_some_and(|subject| principal.is(subject));
+    if is_owner || is_admin(principal, state) {
+        return Ok(());
+    }
+    Err((
+        StatusCode::FORBIDDEN,
+        "Only administrators can change other users".to_string(),
+    ))
+}
+
+/// Reject role changes by callers without an admin role
+fn require_admin_for_role(
+    principal: Option<&Principal>,
+    state: &AppState,
+    role: Option<&String>,
+) -> Result<(), (StatusCode, String)> {
+    match principal {
+        Some(principal) if role.is_some() && !is_admin(principal, state) => Err((
+            StatusCode::FORBIDDEN,
+            "Only administrators can change a user's role".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
 /// Map service errors to HTTP status codes
 fn map_service_error(err: ServiceError) -> (StatusCode, String) {
     match err {
@@ -140,6 +207,10 @@ fn map_service_error(err: ServiceError) -> (StatusCode, String) {
             (StatusCode::CONFLICT, "Username already exists".to_string())
         }
         ServiceError::EmailExists => (StatusCode::CONFLICT, "Email already exists".to_string()),
+        ServiceError::UserModified => (
+            StatusCode::PRECONDITION_FAILED,
+            "User has been modified since it was retrieved".to_string(),
+        ),
         ServiceError::Validation(msg) => (StatusCode::BAD_REQUEST, msg),
         _ => (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -191,11 +262,15 @@ async fn get_all_users(
 }
 
 /// Get a user by ID
+///
+/// Responds with `ETag` and `Last-Modified` validators, and with 304 when the
+/// request's `If-None-Match` or `If-Modified-Since` shows the client is current.
 #[debug_handler]
 async fn get_user(
     State(state): State<Arc<AppState>>,
     Path(id_str): Path<String>,
-) -> Result<Json<UserResponse>, (StatusCode, String)> {
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, String)> {
     // Parse UUID from string
     let id = match Uuid::parse_str(&id_str) {
         Ok(uuid) => uuid,
@@ -212,17 +287,28 @@ async fn get_user(
         .map_err(map_service_error)?;
 
     // Map user to response format
+    let last_modified = user.updated_at;
     let response = UserResponse::from(user);
 
-    Ok(Json(response))
+    Ok(conditional::conditional_json(
+        &headers,
+        &response,
+        Some(last_modified),
+    ))
 }
 
 /// Create a new user
+///
+/// The caller owns the new user. Only callers with an admin role may give it a role.
 #[debug_handler]
 async fn create_user(
     State(state): State<Arc<AppState>>,
+    principal: Option<Principal>,
     Json(request): Json<CreateUserRequest>,
 ) -> Result<(StatusCode, Json<UserResponse>), (StatusCode, String)> {
+    let principal = require_caller(principal, &state)?;
+    require_admin_for_role(principal.as_ref(), &state, request.role.as_ref())?;
+
     // Get user service from app state
     let user_service = get_user_service(state)?;
 
@@ -248,6 +334,7 @@ async fn create_user(
         email: request.email,
         full_name: request.full_name,
         role,
+        subject: principal.map(|p| p.subject),
     };
 
     // Create user via service
@@ -263,12 +350,22 @@ async fn create_user(
 }
 
 /// Update a user
+///
+/// When an `If-Match` header is sent, the update only applies to the version
+/// of the user with that `ETag`. It is rejected with 412 if the user has changed,
+/// including by a concurrent update, so edits are not silently overwritten.
+/// Callers may only update the users they created unless they have an admin
+/// role, and only admins may change a user's role.
 #[debug_handler]
 async fn update_user(
     State(state): State<Arc<AppState>>,
     Path(id_str): Path<String>,
+    principal: Option<Principal>,
+    headers: HeaderMap,
     Json(request): Json<UpdateUserRequest>,
-) -> Result<Json<UserResponse>, (StatusCode, String)> {
+) -> Result<Response, (StatusCode, String)> {
+    let principal = require_caller(principal, &state)?;
+
     // Parse UUID from string
     let id = match Uuid::parse_str(&id_str) {
         Ok(uuid) => uuid,
@@ -276,7 +373,15 @@ async fn update_user(
     };
 
     // Get user service from app state
-    let user_service = get_user_service(state)?;
+    let user_service = get_user_service(state.clone())?;
+
+    // Callers may not edit other users, or grant anyone a new role, unless they are admins
+    let current = user_service
+        .get_user_by_id(id)
+        .await
+        .map_err(map_service_error)?;
+    require_owner_or_admin(principal.as_ref(), &state, &current)?;
+    require_admin_for_role(principal.as_ref(), &state, request.role.as_ref())?;
 
     // Map role string to enum if provided
     let role = match request.role {
@@ -302,24 +407,49 @@ async fn update_user(
         role,
     };
 
-    // Update user via service
-    let user = user_service
-        .update_user(id, update_dto)
-        .await
-        .map_err(map_service_error)?;
+    let user = if headers.contains_key(axum::http::header::IF_MATCH) {
+        // Check the client is editing the current version of the user, then
+        // update only that version so a concurrent update cannot slip in between
+        let etag =
+            conditional::strong_etag(&UserResponse::from(current.clone())).unwrap_or_default();
 
-    // Map user to response format
+        if conditional::if_match(&headers, &etag) == Some(false) {
+            return Err(map_service_error(ServiceError::UserModified));
+        }
+
+        user_service
+            .update_user_if_unmodified(update_dto, &current)
+            .await
+            .map_err(map_service_error)?
+    } else {
+        user_service
+            .update_user(id, update_dto)
+            .await
+            .map_err(map_service_error)?
+    };
+
+    // Map user to response format, returning the new validators
+    let last_modified = user.updated_at;
     let response = UserResponse::from(user);
+    let etag = conditional::strong_etag(&response);
 
-    Ok(Json(response))
+    let mut response = Json(response).into_response();
+    conditional::set_validators(response.headers_mut(), etag.as_deref(), Some(last_modified));
+
+    Ok(response)
 }
 
 /// Delete a user
+///
+/// Callers may only delete the users they created unless they have an admin role.
 #[debug_handler]
 async fn delete_user(
     Path(id): Path<String>,
     State(state): State<Arc<AppState>>,
+    principal: Option<Principal>,
 ) -> Result<StatusCode, (StatusCode, String)> {
+    let principal = require_caller(principal, &state)?;
+
     // Parse UUID from string
     let user_id = match Uuid::parse_str(&id) {
         Ok(uuid) => uuid,
@@ -327,7 +457,20 @@ async fn delete_user(
     };
 
     // Get user service
-    let user_service = get_user_service(state)?;
+    let user_service = get_user_service(state.clone())?;
+
+    // Only the owner or an admin may delete the user
+    let user = user_service
+        .get_user_by_id(user_id)
+        .await
+        .map_err(|e| match e {
+            ServiceError::UserNotFound => (
+                StatusCode::NOT_FOUND,
+                format!("User with ID {} not found", user_id),
+            ),
+            e => map_service_error(e),
+        })?;
+    require_owner_or_admin(principal.as_ref(), &state, &user)?;
 
     // Delete user
     match user_service.delete_user(user_id).await {
@@ -356,11 +499,24 @@ fn get_user_service(state: Arc<AppState>) -> Result<Arc<dyn IUserService>, (Stat
         }
     };
 
-    // Create user repository
-    let user_repo = Arc::new(crate::repository::UserRepository::new(db_pool));
-
-    // Create user service
-    let user_service = Arc::new(crate::services::UserService::new(user_repo));
+    // Use the cached repository if configured, so lookups skip the database
+    match &state.user_repository {
+        Some(user_repo) => Ok(user_service(user_repo.clone(), &state)),
+        None => Ok(user_service(
+            Arc::new(crate::repository::UserRepository::new(db_pool)),
+            &state,
+        )),
+    }
+}
 
-    Ok(user_service)
+/// Create a user service, broadcasting cache evictions to other replicas if configured
+fn user_service<R>(user_repo: Arc<R>, state: &AppState) -> Arc<dyn IUserService>
+where
+    R: Repository<User, Uuid> + 'static,
+{
+    let mut user_service = crate::services::UserService::new(user_repo);
+    if let Some(bus) = &state.cache_invalidation {
+        user_service = user_service.with_invalidation(bus.clone());
+    }
+    Arc::new(user_service)
 }
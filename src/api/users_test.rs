@@ -12,9 +12,11 @@ use tower::ServiceExt;
 use uuid::Uuid;
 
 use crate::{
-    api::users::{CreateUserRequest, UpdateUserRequest, UserResponse, configure},
+    api::users::{
+        CreateUserRequest, UpdateUserRequest, UserResponse, configure, configure_protected,
+    },
     core::router::AppState,
-    repository::models::UserRole,
+    repository::{User, models::UserRole},
     services::{IUserService, MockUserService, error::ServiceError},
 };
 
@@ -55,3 +57,199 @@ async fn test_delete_user() {
 async fn test_error_handling() {
     // Test implementation to be fixed in a future update
 }
+
+/// A users router backed by a mock database holding `users`
+fn protected_users_app(users: Vec<User>, auth_enabled: bool) -> Router {
+    use crate::config::AppConfig;
+    use crate::core::config::app_config::DatabaseConfig;
+    use crate::core::database::{PgPool, connection::MockDatabaseConnection};
+    use crate::utils::api_resource::ApiResourceRegistry;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use std::time::SystemTime;
+
+    let database = MockDatabaseConnection::new(&DatabaseConfig::default());
+    for user in users {
+        database.add_user(user);
+    }
+
+    let mut config = AppConfig::default();
+    config.auth.enabled = auth_enabled;
+    config.auth.entra.admin_roles = vec!["admin".to_string()];
+    let state = Arc::new(AppState {
+        client: reqwest::Client::new(),
+        config,
+        start_time: SystemTime::now(),
+        cache_registry: None,
+        cache_provider: None,
+        metrics_handle: PrometheusBuilder::new().build_recorder().handle(),
+        token_client: None,
+        resource_registry: ApiResourceRegistry::new(),
+        db_pool: Some(Arc::new(Box::new(database) as Box<dyn PgPool>)),
+        cache_invalidation: None,
+        user_repository: None,
+    });
+    configure_protected().with_state(state)
+}
+
+/// A `method` request for `uri`, sent by `caller` (subject and roles) if any
+fn users_request(
+    method: &str,
+    uri: &str,
+    body: serde_json::Value,
+    caller: Option<(&str, Vec<&str>)>,
+) -> Request<Body> {
+    use crate::core::auth::middleware::EntraClaims;
+
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    if let Some((subject, roles)) = caller {
+        request.extensions_mut().insert(EntraClaims {
+            sub: subject.to_string(),
+            aud: "api://backend".to_string(),
+            iss: "issuer".to_string(),
+            exp: 0,
+            nbf: 0,
+            iat: 0,
+            roles: roles.into_iter().map(String::from).collect(),
+            appid: None,
+            app_id_uri: None,
+            scp: None,
+            tid: None,
+        });
+    }
+    request
+}
+
+/// A `PUT /users/{id}` request, sent by `caller` (subject and roles) if any
+fn update_request(
+    id: Uuid,
+    body: serde_json::Value,
+    caller: Option<(&str, Vec<&str>)>,
+) -> Request<Body> {
+    users_request("PUT", &format!("/users/{}", id), body, caller)
+}
+
+/// Alice's user, created by the caller with subject `alice-sub`
+fn alice() -> User {
+    let mut user = User::new(
+        "alice".to_string(),
+        "alice@example.com".to_string(),
+        None,
+        UserRole::User,
+    );
+    user.subject = Some("alice-sub".to_string());
+    user
+}
+
+#[tokio::test]
+async fn test_anonymous_callers_cannot_update_users() {
+    let user = alice();
+    let app = protected_users_app(vec![user.clone()], true);
+
+    let request = update_request(user.id, json!({ "is_active": false }), None);
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_anyone_may_change_users_when_auth_is_disabled() {
+    let user = alice();
+    let app = protected_users_app(vec![user.clone()], false);
+
+    let request = update_request(user.id, json!({ "role": "admin" }), None);
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = users_request("DELETE", &format!("/users/{}", user.id), json!({}), None);
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn test_users_only_update_themselves() {
+    let user = alice();
+    let app = protected_users_app(vec![user.clone()], true);
+    let owner = "alice-sub";
+
+    // Another non-admin may not touch the user
+    let request = update_request(
+        user.id,
+        json!({ "email": "mallory@example.com" }),
+        Some(("someone-else", vec!["user"])),
+    );
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // The owner may change their own details, but not their role
+    let request = update_request(
+        user.id,
+        json!({ "role": "admin" }),
+        Some((owner, vec!["user"])),
+    );
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let request = update_request(
+        user.id,
+        json!({ "email": "alice@example.org" }),
+        Some((owner, vec!["user"])),
+    );
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated: UserResponse = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(updated.email, "alice@example.org");
+
+    // Admins may change anyone, including their role
+    let request = update_request(
+        user.id,
+        json!({ "role": "admin" }),
+        Some(("an-admin", vec!["admin"])),
+    );
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated: UserResponse = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(updated.role, UserRole::Admin.to_string());
+}
+
+#[tokio::test]
+async fn test_creators_own_the_users_they_create() {
+    let app = protected_users_app(Vec::new(), true);
+
+    let request = users_request(
+        "POST",
+        "/users",
+        json!({ "username": "bob", "email": "bob@example.com" }),
+        Some(("bob-sub", vec!["user"])),
+    );
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: UserResponse = serde_json::from_slice(&body_bytes(response).await).unwrap();
+
+    // The user's own ID is not their subject, so it does not make them the owner
+    let request = update_request(
+        created.id,
+        json!({ "full_name": "Mallory" }),
+        Some((created.id.to_string().as_str(), vec!["user"])),
+    );
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let request = users_request(
+        "DELETE",
+        &format!("/users/{}", created.id),
+        json!({}),
+        Some(("bob-sub", vec!["user"])),
+    );
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+}
+
+async fn body_bytes(response: Response) -> Bytes {
+    body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap()
+}
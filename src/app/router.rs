@@ -1,71 +1,102 @@
 use axum::{
     Json,
-    extract::{Path, State},
+    extract::{OriginalUri, Path, Request, State},
+    http::Uri,
     routing::{Router, get, post},
 };
 use std::net::SocketAddr;
 use std::sync::Arc;
+use tower::{ServiceExt, util::MapRequest};
 use tracing::info;
 
 use crate::{
     api,
     core::auth::EntraAuthLayer,
-    core::router::{AppState, create_core_app_router, init_app_state},
+    core::error::AppError,
+    core::router::{AppState, create_core_app_router, init_app_state, shutdown_app_state},
     handlers::examples::pet,
 };
 
+/// Former route group prefixes, still served as aliases of `/protected`
+const PROTECTED_ALIASES: [&str; 2] = ["/read", "/full"];
+
 /// Create custom user routes that can be modified by developers
-fn create_user_routes(state: Arc<AppState>) -> Router {
+fn create_user_routes(state: Arc<AppState>) -> Result<Router, AppError> {
     // Define whether auth is enabled
     let auth_enabled = state.config.auth.enabled;
 
-    // Create auth middleware for different access levels
-    let readonly_auth = EntraAuthLayer::from_app_config_require_read_only_role(&state.config);
-    let fullaccess_auth = EntraAuthLayer::from_app_config_require_full_access_role(&state.config);
-
     // 1. PUBLIC ROUTES - available without authentication
     let public_routes = Router::new()
+        .route("/pet", get(pet::fetch_pets_handler))
         .route("/pet/{id}", get(pet::fetch_pet_handler))
         // Add more public routes here
         .route("/hello", get(|| async { "Hello, World!" }))
-        // Include API routes
+        // Include API routes that only read
         .merge(api::configure());
 
-    // 2. READ-ONLY ROUTES - requires basic authentication
-    let readonly_routes = Router::new()
+    // 2. PROTECTED ROUTES - require a valid token; what each caller may do on each
+    //    path and method is decided by the policies under `auth.policies`, on top of
+    //    reads needing a read-only role and writes a full access role
+    let policy_routes = Router::new()
+        .route("/pet", get(pet::fetch_pets_handler))
         .route("/pet/{id}", get(pet::fetch_pet_handler))
-        // Add more read-only routes here
+        .merge(pet::pet_write_routes(&state))
+        // Add more protected routes here
         ;
+    let protected_routes = Router::new().nest("/protected", policy_routes);
 
-    // 3. FULL ACCESS ROUTES - requires full access role
-    let fullaccess_routes = Router::new()
-        .route("/pet/{id}", get(pet::fetch_pet_handler))
-        // Add more full access routes here
-        ;
+    // API routes that change data; their handlers also check who may change what
+    let api_write_routes = api::configure_protected();
 
-    // Apply authentication layers if enabled
-    let (readonly_routes, fullaccess_routes) = if auth_enabled {
+    // Apply the authentication layer if enabled; without it the API write routes
+    // serve every caller
+    let (protected_routes, api_write_routes) = if auth_enabled {
+        let auth = EntraAuthLayer::from_app_config(&state.config)?;
         (
-            readonly_routes.layer(readonly_auth),
-            fullaccess_routes.layer(fullaccess_auth),
+            protected_routes.layer(auth.clone()),
+            api_write_routes.layer(auth),
         )
     } else {
-        // No auth enabled
-        (readonly_routes, fullaccess_routes)
+        (protected_routes, api_write_routes)
     };
 
     // Combine user-defined routes
-    Router::new()
+    let mut router = Router::new()
         .merge(public_routes)
-        .nest("/read", readonly_routes)
-        .nest("/full", fullaccess_routes)
-        .with_state(state)
+        .merge(protected_routes.clone())
+        .merge(api_write_routes);
+    let protected_routes = protected_routes.with_state(state.clone());
+    for alias in PROTECTED_ALIASES {
+        router = router.nest_service(alias, protected_alias(protected_routes.clone()));
+    }
+    Ok(router.with_state(state))
+}
+
+/// Serve `protected_routes` for requests under one of [`PROTECTED_ALIASES`]
+///
+/// `nest_service` strips the alias; the request is then moved under `/protected`,
+/// `OriginalUri` included, so it is routed, authenticated and checked against the
+/// policies exactly as if it had been sent there.
+fn protected_alias(protected_routes: Router) -> MapRequest<Router, fn(Request) -> Request> {
+    protected_routes.map_request(under_protected as fn(Request) -> Request)
+}
+
+/// Move `req`, with its alias prefix already stripped, under `/protected`
+fn under_protected(mut req: Request) -> Request {
+    let path_and_query = req.uri().path_and_query().map_or("/", |pq| pq.as_str());
+    if let Ok(uri) = format!("/protected{}", path_and_query).parse::<Uri>() {
+        req.extensions_mut().insert(OriginalUri(uri.clone()));
+        *req.uri_mut() = uri;
+    }
+    req
 }
 
 /// Create the application router by combining core routes with user routes
-pub fn create_router(state: Arc<AppState>) -> Router {
+///
+/// Fails if the authorization policies are malformed.
+pub fn create_router(state: Arc<AppState>) -> Result<Router, AppError> {
     // Get user-defined routes
-    let user_routes = create_user_routes(state.clone());
+    let user_routes = create_user_routes(state.clone())?;
 
     // Create the core app router with user routes
     create_core_app_router(state, user_routes)
@@ -75,12 +106,130 @@ pub fn create_router(state: Arc<AppState>) -> Router {
 ///
 /// Note: The Router returned here has type Router<Arc<AppState>>. When passing to the server
 /// in main.rs, it needs to be used with the appropriate serving method.
-pub async fn init() -> (Router, SocketAddr) {
+pub async fn init() -> Result<(Router, SocketAddr), AppError> {
+    let (app, _state, addr) = init_with_state().await?;
+    Ok((app, addr))
+}
+
+/// Initialize the application, also returning the state for use at shutdown
+pub async fn init_with_state() -> Result<(Router, Arc<AppState>, SocketAddr), AppError> {
     // Initialize app state and get server address
     let (state, addr) = init_app_state().await;
 
     // Create router with app state
-    let app = create_router(state);
+    let app = create_router(state.clone())?;
+
+    Ok((app, state, addr))
+}
+
+/// Shut the application down after the server has stopped
+pub async fn shutdown(state: &AppState) {
+    shutdown_app_state(state).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::auth::test_support::{
+        MockOidcProvider, expires_in_an_hour, rsa_jwk, sign_rs256,
+    };
+    use crate::core::config::app_config::{AppConfig, AuthProvider, OidcIssuerConfig};
+    use crate::utils::api_resource::ApiResourceRegistry;
+    use axum::{
+        body::{self, Body},
+        http::StatusCode,
+    };
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use serde_json::{Value, json};
+    use std::time::SystemTime;
+
+    #[tokio::test]
+    async fn test_former_route_groups_are_aliases_of_protected() {
+        let provider = MockOidcProvider::start("realms/aliases", vec![rsa_jwk()]).await;
+        let issuer = provider.issuer("realms/aliases");
+
+        let mut config = AppConfig::default();
+        config.auth.provider = AuthProvider::Oidc;
+        config.auth.oidc.issuers = vec![OidcIssuerConfig {
+            issuer: issuer.clone(),
+            audience: "backend".to_string(),
+            discovery_url: None,
+            roles_claim: "roles".to_string(),
+            scopes_claim: "scope".to_string(),
+        }];
+        config.auth.entra.read_only_roles = vec!["read".to_string()];
+        config.auth.entra.full_access_roles = vec!["full".to_string()];
+        let state = Arc::new(AppState {
+            client: reqwest::Client::new(),
+            config,
+            start_time: SystemTime::now(),
+            cache_registry: None,
+            cache_provider: None,
+            metrics_handle: PrometheusBuilder::new().build_recorder().handle(),
+            token_client: None,
+            resource_registry: ApiResourceRegistry::new(),
+            db_pool: None,
+            cache_invalidation: None,
+            user_repository: None,
+        });
+        let app = create_user_routes(state).unwrap();
+
+        let request = |method: &str, uri: &str, roles: Option<Vec<&str>>| {
+            let mut builder = Request::builder().method(method).uri(uri);
+            if let Some(roles) = roles {
+                let token = sign_rs256(&json!({
+                    "iss": issuer,
+                    "sub": "user-1",
+                    "aud": "backend",
+                    "exp": expires_in_an_hour(),
+                    "roles": roles,
+                }));
+                builder = builder.header("Authorization", format!("Bearer {}", token));
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+        let denied_by = |response: axum::response::Response| async move {
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+            let body = body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()["message"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
+
+        // Aliases need a token like /protected does
+        let response = app
+            .clone()
+            .oneshot(request("GET", "/read/pet/1", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // Reads need a read-only role and writes a full access role, on every prefix
+        for uri in ["/protected/pet/1", "/read/pet/1", "/full/pet/1"] {
+            let response = app
+                .clone()
+                .oneshot(request("GET", uri, Some(vec!["full"])))
+                .await
+                .unwrap();
+            assert!(
+                denied_by(response).await.contains("protected-read"),
+                "{}",
+                uri
+            );
 
-    (app, addr)
+            let response = app
+                .clone()
+                .oneshot(request("DELETE", uri, Some(vec!["read"])))
+                .await
+                .unwrap();
+            assert!(
+                denied_by(response).await.contains("protected-write"),
+                "{}",
+                uri
+            );
+        }
+    }
 }
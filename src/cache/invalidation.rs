@@ -0,0 +1,569 @@
+//! Cross-replica cache invalidation
+//!
+//! Every replica keeps its own in-process [`CacheRegistry`], so a write handled
+//! by one replica leaves stale entries on the others. An [`InvalidationBus`]
+//! evicts the written key locally and broadcasts it over an
+//! [`InvalidationTransport`] (Redis pub/sub or Postgres LISTEN/NOTIFY); every
+//! subscribed replica then evicts the same key from its own registry.
+
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use super::providers::redis::{RedisCacheProvider, RedisConfig};
+use crate::config::{AppConfig, InvalidationBackend};
+use crate::core::cache::CacheRegistry;
+
+/// A key evicted by a write on one replica
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InvalidationMessage {
+    pub resource_type: String,
+    pub key: String,
+    /// Whether the whole `resource_type` cache was cleared, rather than just `key`
+    #[serde(default)]
+    pub all: bool,
+    /// ID of the publishing bus, so a replica can skip its own messages
+    pub origin: String,
+}
+
+/// Broadcast channel shared by every replica
+///
+/// Messages are opaque strings; delivery is best effort and messages sent while
+/// a subscriber is disconnected are lost.
+#[async_trait]
+pub trait InvalidationTransport: Send + Sync + 'static {
+    /// Short name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Send `payload` to every subscriber
+    async fn publish(&self, payload: &str) -> Result<(), String>;
+
+    /// Start receiving payloads
+    ///
+    /// The stream ends, or yields an error, when the subscription is lost.
+    async fn subscribe(&self) -> Result<BoxStream<'static, Result<String, String>>, String>;
+}
+
+/// Redis pub/sub transport, using the `cache.redis` connection settings
+pub struct RedisInvalidationTransport {
+    provider: RedisCacheProvider,
+    channel: String,
+}
+
+impl RedisInvalidationTransport {
+    pub fn new(config: RedisConfig, channel: impl Into<String>) -> Self {
+        Self {
+            provider: RedisCacheProvider::new(config),
+            channel: channel.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl InvalidationTransport for RedisInvalidationTransport {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    async fn publish(&self, payload: &str) -> Result<(), String> {
+        self.provider.publish(&self.channel, payload).await?;
+        Ok(())
+    }
+
+    async fn subscribe(&self) -> Result<BoxStream<'static, Result<String, String>>, String> {
+        let subscription = self.provider.subscribe(&self.channel).await?;
+        Ok(stream::unfold(subscription, |mut subscription| async move {
+            let message = subscription.next_message().await;
+            Some((message, subscription))
+        })
+        .boxed())
+    }
+}
+
+/// Postgres LISTEN/NOTIFY transport, using the `database.url` connection
+pub struct PostgresInvalidationTransport {
+    url: String,
+    channel: String,
+    pool: sqlx::PgPool,
+}
+
+impl PostgresInvalidationTransport {
+    /// Create the transport; connections are opened on first use
+    pub fn new(url: impl Into<String>, channel: impl Into<String>) -> Result<Self, String> {
+        let url = url.into();
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(2)
+            .connect_lazy(&url)
+            .map_err(|e| format!("Invalid Postgres URL for cache invalidation: {}", e))?;
+        Ok(Self {
+            url,
+            channel: channel.into(),
+            pool,
+        })
+    }
+}
+
+#[async_trait]
+impl InvalidationTransport for PostgresInvalidationTransport {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn publish(&self, payload: &str) -> Result<(), String> {
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(&self.channel)
+            .bind(payload)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Postgres NOTIFY failed: {}", e))?;
+        Ok(())
+    }
+
+    async fn subscribe(&self) -> Result<BoxStream<'static, Result<String, String>>, String> {
+        let mut listener = sqlx::postgres::PgListener::connect(&self.url)
+            .await
+            .map_err(|e| format!("Failed to connect Postgres listener: {}", e))?;
+        listener
+            .listen(&self.channel)
+            .await
+            .map_err(|e| format!("Postgres LISTEN failed: {}", e))?;
+
+        Ok(stream::unfold(listener, |mut listener| async move {
+            let notification = listener
+                .recv()
+                .await
+                .map(|notification| notification.payload().to_string())
+                .map_err(|e| format!("Postgres listener failed: {}", e));
+            Some((notification, listener))
+        })
+        .boxed())
+    }
+}
+
+/// In-process transport connecting buses in the same process
+///
+/// Useful for tests and single-instance deployments; clones share the channel.
+#[derive(Clone)]
+pub struct LocalInvalidationTransport {
+    sender: broadcast::Sender<String>,
+}
+
+impl LocalInvalidationTransport {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(1024);
+        Self { sender }
+    }
+}
+
+impl Default for LocalInvalidationTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl InvalidationTransport for LocalInvalidationTransport {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn publish(&self, payload: &str) -> Result<(), String> {
+        // No receivers is not an error; there is simply nobody to tell
+        let _ = self.sender.send(payload.to_string());
+        Ok(())
+    }
+
+    async fn subscribe(&self) -> Result<BoxStream<'static, Result<String, String>>, String> {
+        let receiver = self.sender.subscribe();
+        Ok(stream::unfold(receiver, |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(payload) => return Some((Ok(payload), receiver)),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("⚠️ Dropped {} cache invalidation messages", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+        .boxed())
+    }
+}
+
+/// Publishes evictions to, and applies evictions from, other replicas
+pub struct InvalidationBus {
+    origin: String,
+    transport: Arc<dyn InvalidationTransport>,
+    registry: Option<CacheRegistry>,
+    near_cache: Option<CacheRegistry>,
+}
+
+impl InvalidationBus {
+    /// Create a bus evicting from `registry` (if caching is enabled)
+    pub fn new(transport: Arc<dyn InvalidationTransport>, registry: Option<CacheRegistry>) -> Self {
+        Self {
+            origin: Uuid::new_v4().to_string(),
+            transport,
+            registry,
+            near_cache: None,
+        }
+    }
+
+    /// Also evict from the in-process tier of a tiered cache provider
+    pub fn with_near_cache(mut self, registry: CacheRegistry) -> Self {
+        self.near_cache = Some(registry);
+        self
+    }
+
+    /// Build the bus configured in `cache.invalidation`, or `None` when it is disabled
+    pub fn from_app_config(
+        config: &AppConfig,
+        registry: Option<CacheRegistry>,
+    ) -> Result<Option<Self>, String> {
+        let settings = &config.cache.invalidation;
+        if !settings.enabled {
+            return Ok(None);
+        }
+
+        let transport: Arc<dyn InvalidationTransport> = match settings.backend {
+            InvalidationBackend::Redis => Arc::new(RedisInvalidationTransport::new(
+                RedisConfig::from_app_config(config),
+                settings.channel.clone(),
+            )),
+            InvalidationBackend::Postgres => Arc::new(PostgresInvalidationTransport::new(
+                config.database.url.clone(),
+                settings.channel.clone(),
+            )?),
+        };
+
+        Ok(Some(Self::new(transport, registry)))
+    }
+
+    /// Unique ID of this bus, carried in every message it publishes
+    pub fn origin(&self) -> &str {
+        &self.origin
+    }
+
+    /// Name of the underlying transport
+    pub fn transport_name(&self) -> &'static str {
+        self.transport.name()
+    }
+
+    /// Evict `key` locally and tell every other replica to evict it
+    ///
+    /// The local eviction happens even if publishing fails.
+    pub async fn publish(&self, resource_type: &str, key: &str) -> Result<(), String> {
+        self.send(InvalidationMessage {
+            resource_type: resource_type.to_string(),
+            key: key.to_string(),
+            all: false,
+            origin: self.origin.clone(),
+        })
+        .await
+    }
+
+    /// Clear the `resource_type` cache locally and tell every other replica to clear it
+    ///
+    /// The local clear happens even if publishing fails.
+    pub async fn publish_clear(&self, resource_type: &str) -> Result<(), String> {
+        self.send(InvalidationMessage {
+            resource_type: resource_type.to_string(),
+            key: String::new(),
+            all: true,
+            origin: self.origin.clone(),
+        })
+        .await
+    }
+
+    async fn send(&self, message: InvalidationMessage) -> Result<(), String> {
+        let resource_type = message.resource_type.as_str();
+        self.evict_local(&message).await;
+
+        let payload = serde_json::to_string(&message)
+            .map_err(|e| format!("Failed to encode invalidation message: {}", e))?;
+
+        let result = self.transport.publish(&payload).await;
+        record_message(resource_type, "sent", result.is_ok());
+        result
+    }
+
+    /// Apply a message received from another replica
+    ///
+    /// Returns whether a local entry was evicted. Messages from this bus are
+    /// ignored, since `publish` already evicted the key locally.
+    pub async fn apply(&self, message: &InvalidationMessage) -> bool {
+        if message.origin == self.origin {
+            return false;
+        }
+        record_message(&message.resource_type, "received", true);
+
+        self.evict_local(message).await
+    }
+
+    /// Evict the message's key (or every entry) from each local registry,
+    /// returning whether anything was evicted
+    async fn evict_local(&self, message: &InvalidationMessage) -> bool {
+        let mut evicted = false;
+        for registry in [&self.registry, &self.near_cache].into_iter().flatten() {
+            evicted |= if message.all {
+                registry.invalidate_all(&message.resource_type).await > 0
+            } else {
+                registry
+                    .invalidate_key(&message.resource_type, &message.key)
+                    .await
+            };
+        }
+        evicted
+    }
+
+    /// Subscribe in the background and apply every message received
+    ///
+    /// A lost subscription is retried after `reconnect_interval`. Invalidations
+    /// published while disconnected are missed, so entries evicted then only
+    /// expire by TTL.
+    pub fn start(self: &Arc<Self>, reconnect_interval: Duration) -> JoinHandle<()> {
+        let bus = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match bus.transport.subscribe().await {
+                    Ok(mut messages) => {
+                        info!(
+                            "📡 Listening for cache invalidations over {}",
+                            bus.transport.name()
+                        );
+                        while let Some(payload) = messages.next().await {
+                            match payload {
+                                Ok(payload) => bus.apply_payload(&payload).await,
+                                Err(e) => {
+                                    warn!("⚠️ Cache invalidation subscription lost: {}", e);
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => warn!("⚠️ Failed to subscribe to cache invalidations: {}", e),
+                }
+                tokio::time::sleep(reconnect_interval).await;
+            }
+        })
+    }
+
+    async fn apply_payload(&self, payload: &str) {
+        match serde_json::from_str::<InvalidationMessage>(payload) {
+            Ok(message) => {
+                if self.apply(&message).await {
+                    debug!(
+                        "🧹 Evicted {} {} on behalf of {}",
+                        message.resource_type, message.key, message.origin
+                    );
+                }
+            }
+            Err(e) => warn!("⚠️ Ignoring malformed cache invalidation message: {}", e),
+        }
+    }
+}
+
+fn record_message(resource_type: &str, direction: &'static str, ok: bool) {
+    let status = if ok { "ok" } else { "error" };
+    counter!(
+        "cache_invalidation_messages_total",
+        "resource_type" => resource_type.to_string(),
+        "direction" => direction,
+        "status" => status
+    )
+    .increment(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::cache::{get_resource_cache, init_cache_registry, register_resource_cache};
+    use crate::utils::api_resource::ApiResource;
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct Account {
+        id: String,
+    }
+
+    impl ApiResource for Account {
+        type Id = String;
+
+        fn resource_type() -> &'static str {
+            "account"
+        }
+
+        fn api_name() -> &'static str {
+            "AccountService"
+        }
+    }
+
+    /// A registry holding an "account" cache with `ids` cached
+    async fn replica_registry(ids: &[&str]) -> CacheRegistry {
+        let registry = init_cache_registry(true, 100, 60);
+        register_resource_cache::<Account>(&registry, "account").unwrap();
+        let cache = get_resource_cache::<Account>(&registry, "account").unwrap();
+        for id in ids {
+            cache
+                .insert(id.to_string(), Account { id: id.to_string() })
+                .await;
+        }
+        registry
+    }
+
+    async fn is_cached(registry: &CacheRegistry, key: &str) -> bool {
+        let cache = registry.resource_cache_ops("account").unwrap();
+        cache.get_json(key).await.is_some()
+    }
+
+    /// Wait until `key` is evicted, for at most a second
+    async fn wait_until_evicted(registry: &CacheRegistry, key: &str) -> bool {
+        for _ in 0..100 {
+            if !is_cached(registry, key).await {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        false
+    }
+
+    #[test]
+    fn test_message_round_trip() {
+        let message = InvalidationMessage {
+            resource_type: "user".to_string(),
+            key: "42".to_string(),
+            all: false,
+            origin: "replica-a".to_string(),
+        };
+        let payload = serde_json::to_string(&message).unwrap();
+        assert_eq!(
+            serde_json::from_str::<InvalidationMessage>(&payload).unwrap(),
+            message
+        );
+    }
+
+    #[tokio::test]
+    async fn test_publish_evicts_key_on_every_replica() {
+        let transport = Arc::new(LocalInvalidationTransport::new());
+        let registry_a = replica_registry(&["1", "2"]).await;
+        let registry_b = replica_registry(&["1", "2"]).await;
+
+        let bus_a = Arc::new(InvalidationBus::new(
+            transport.clone(),
+            Some(registry_a.clone()),
+        ));
+        let bus_b = Arc::new(InvalidationBus::new(
+            transport.clone(),
+            Some(registry_b.clone()),
+        ));
+        let listener_b = bus_b.start(Duration::from_millis(10));
+        // Give the listener a moment to subscribe
+        while transport.sender.receiver_count() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        bus_a.publish("account", "1").await.unwrap();
+
+        assert!(!is_cached(&registry_a, "1").await);
+        assert!(wait_until_evicted(&registry_b, "1").await);
+        assert!(is_cached(&registry_a, "2").await);
+        assert!(is_cached(&registry_b, "2").await);
+        listener_b.abort();
+    }
+
+    #[tokio::test]
+    async fn test_apply_ignores_own_messages() {
+        let registry = replica_registry(&["1"]).await;
+        let bus = InvalidationBus::new(
+            Arc::new(LocalInvalidationTransport::new()),
+            Some(registry.clone()),
+        );
+
+        let mut message = InvalidationMessage {
+            resource_type: "account".to_string(),
+            key: "1".to_string(),
+            all: false,
+            origin: bus.origin().to_string(),
+        };
+        assert!(!bus.apply(&message).await);
+        assert!(is_cached(&registry, "1").await);
+
+        message.origin = "another-replica".to_string();
+        assert!(bus.apply(&message).await);
+        assert!(!is_cached(&registry, "1").await);
+
+        // Unknown resource types are ignored
+        message.resource_type = "unknown".to_string();
+        assert!(!bus.apply(&message).await);
+    }
+
+    #[tokio::test]
+    async fn test_publish_clear_empties_every_replica() {
+        let transport = Arc::new(LocalInvalidationTransport::new());
+        let registry_a = replica_registry(&["1", "2"]).await;
+        let registry_b = replica_registry(&["1", "2"]).await;
+
+        let bus_a = InvalidationBus::new(transport.clone(), Some(registry_a.clone()));
+        let bus_b = Arc::new(InvalidationBus::new(
+            transport.clone(),
+            Some(registry_b.clone()),
+        ));
+        let listener_b = bus_b.start(Duration::from_millis(10));
+        while transport.sender.receiver_count() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        bus_a.publish_clear("account").await.unwrap();
+
+        assert!(!is_cached(&registry_a, "1").await);
+        assert!(!is_cached(&registry_a, "2").await);
+        assert!(wait_until_evicted(&registry_b, "1").await);
+        assert!(wait_until_evicted(&registry_b, "2").await);
+        listener_b.abort();
+    }
+
+    #[test]
+    fn test_messages_without_all_evict_one_key() {
+        let message: InvalidationMessage =
+            serde_json::from_str(r#"{"resource_type":"user","key":"42","origin":"old-replica"}"#)
+                .unwrap();
+        assert!(!message.all);
+    }
+
+    #[tokio::test]
+    async fn test_apply_evicts_from_near_cache() {
+        let registry = replica_registry(&["1"]).await;
+        let near_cache = replica_registry(&["1"]).await;
+        let bus = InvalidationBus::new(
+            Arc::new(LocalInvalidationTransport::new()),
+            Some(registry.clone()),
+        )
+        .with_near_cache(near_cache.clone());
+
+        let message = InvalidationMessage {
+            resource_type: "account".to_string(),
+            key: "1".to_string(),
+            all: false,
+            origin: "another-replica".to_string(),
+        };
+        assert!(bus.apply(&message).await);
+        assert!(!is_cached(&registry, "1").await);
+        assert!(!is_cached(&near_cache, "1").await);
+    }
+
+    #[tokio::test]
+    async fn test_from_app_config_is_none_when_disabled() {
+        let config = AppConfig::default();
+        assert!(
+            InvalidationBus::from_app_config(&config, None)
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,391 @@
+use async_trait::async_trait;
+use std::future::Future;
+use tracing::{info, warn};
+
+use super::{
+    CacheProvider,
+    fallback::{FallbackCacheProvider, FallbackConfig},
+    memory::MemoryCacheProvider,
+    redis::{RedisCacheProvider, RedisConfig},
+    tiered::TieredCacheProvider,
+};
+use crate::config::{AppConfig, CacheProviderType};
+use crate::core::cache::{
+    CacheOutcome, CacheRegistry, FetchError, get_or_fetch_revalidating, get_or_fetch_with_provider,
+};
+use crate::utils::api_resource::ApiResource;
+
+/// The cache backend selected by `cache.provider` in the application config
+///
+/// `CacheProvider` has generic methods and so cannot be used as a trait object;
+/// this enum provides a single concrete type that `AppState` can hold while
+/// delegating to whichever provider was configured.
+pub enum CacheBackend {
+    Memory(MemoryCacheProvider),
+    Redis(RedisCacheProvider),
+    Fallback(FallbackCacheProvider),
+    Tiered(TieredCacheProvider),
+}
+
+impl CacheBackend {
+    /// Build the configured provider
+    ///
+    /// The memory provider (and the memory tier of the fallback provider) share
+    /// `registry`, so statistics and metrics continue to cover those entries.
+    pub fn from_app_config(app_config: &AppConfig, registry: &CacheRegistry) -> Self {
+        match app_config.cache.provider {
+            CacheProviderType::Memory => {
+                CacheBackend::Memory(MemoryCacheProvider::from_registry(registry.clone()))
+            }
+            CacheProviderType::Redis => CacheBackend::Redis(RedisCacheProvider::new(
+                RedisConfig::from_app_config(app_config),
+            )),
+            CacheProviderType::Fallback => {
+                CacheBackend::Fallback(FallbackCacheProvider::with_memory_provider(
+                    FallbackConfig::from_app_config(app_config),
+                    MemoryCacheProvider::from_registry(registry.clone()),
+                ))
+            }
+            CacheProviderType::Tiered => {
+                CacheBackend::Tiered(TieredCacheProvider::from_app_config(app_config))
+            }
+        }
+    }
+
+    /// The kind of provider in use
+    pub fn provider_type(&self) -> CacheProviderType {
+        match self {
+            CacheBackend::Memory(_) => CacheProviderType::Memory,
+            CacheBackend::Redis(_) => CacheProviderType::Redis,
+            CacheBackend::Fallback(_) => CacheProviderType::Fallback,
+            CacheBackend::Tiered(_) => CacheProviderType::Tiered,
+        }
+    }
+
+    /// The registry of the in-process tier of a tiered provider
+    ///
+    /// Cross-replica invalidation must evict from it as well as from the
+    /// application registry, or replicas keep serving their L1 copies.
+    pub fn near_cache_registry(&self) -> Option<CacheRegistry> {
+        match self {
+            CacheBackend::Tiered(provider) => Some(provider.l1_registry()),
+            _ => None,
+        }
+    }
+
+    /// The key a resource is stored under in shared providers (e.g. `"pet:42"`)
+    pub fn cache_key(resource_type: &str, id: &str) -> String {
+        format!("{}:{}", resource_type, id)
+    }
+
+    /// Initialize the provider and start any background tasks it needs
+    ///
+    /// Initialization failures are logged rather than returned: a cache outage
+    /// must not prevent the application from starting, and requests fall through
+    /// to the upstream fetch while the cache is unavailable.
+    pub async fn start(&self) {
+        match self.init().await {
+            Ok(_) => info!("✅ Cache provider '{}' initialized", self.provider_type()),
+            Err(e) => warn!(
+                "⚠️ Cache provider '{}' failed to initialize: {}",
+                self.provider_type(),
+                e
+            ),
+        }
+
+        if let CacheBackend::Fallback(provider) = self {
+            provider.start_reconnect_task().await;
+        }
+    }
+
+    /// Get a resource from the configured cache, or fetch and store it on a miss
+    ///
+    /// The memory provider uses the registry's `get_or_fetch_revalidating`, so
+    /// concurrent misses are coalesced and stale windows apply; remote providers
+    /// store the resource under [`CacheBackend::cache_key`] with `ttl_seconds`
+    /// (0 = no expiry). The returned [`CacheOutcome`] records how the lookup was
+    /// answered.
+    pub async fn get_or_fetch<T, E, F, Fut>(
+        &self,
+        resource_type: &str,
+        id: &str,
+        ttl_seconds: u64,
+        fetch_fn: F,
+    ) -> Result<(T, CacheOutcome), E>
+    where
+        T: ApiResource + 'static,
+        E: FetchError,
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+    {
+        match self {
+            CacheBackend::Memory(memory) => {
+                get_or_fetch_revalidating(&memory.registry(), resource_type, id, fetch_fn).await
+            }
+            _ => {
+                let key = Self::cache_key(resource_type, id);
+                get_or_fetch_with_provider(self, resource_type, &key, ttl_seconds, fetch_fn).await
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl CacheProvider for CacheBackend {
+    async fn init(&self) -> Result<(), String> {
+        match self {
+            CacheBackend::Memory(p) => p.init().await,
+            CacheBackend::Redis(p) => p.init().await,
+            CacheBackend::Fallback(p) => p.init().await,
+            CacheBackend::Tiered(p) => p.init().await,
+        }
+    }
+
+    async fn set<T: ApiResource>(
+        &self,
+        key: &str,
+        value: T,
+        ttl_seconds: u64,
+    ) -> Result<(), String> {
+        match self {
+            CacheBackend::Memory(p) => p.set(key, value, ttl_seconds).await,
+            CacheBackend::Redis(p) => p.set(key, value, ttl_seconds).await,
+            CacheBackend::Fallback(p) => p.set(key, value, ttl_seconds).await,
+            CacheBackend::Tiered(p) => p.set(key, value, ttl_seconds).await,
+        }
+    }
+
+    async fn set_with_tags<T: ApiResource>(
+        &self,
+        key: &str,
+        value: T,
+        ttl_seconds: u64,
+        tags: &[String],
+    ) -> Result<(), String> {
+        match self {
+            CacheBackend::Memory(p) => p.set_with_tags(key, value, ttl_seconds, tags).await,
+            CacheBackend::Redis(p) => p.set_with_tags(key, value, ttl_seconds, tags).await,
+            CacheBackend::Fallback(p) => p.set_with_tags(key, value, ttl_seconds, tags).await,
+            CacheBackend::Tiered(p) => p.set_with_tags(key, value, ttl_seconds, tags).await,
+        }
+    }
+
+    async fn get<T: ApiResource>(&self, key: &str) -> Result<Option<T>, String> {
+        match self {
+            CacheBackend::Memory(p) => p.get(key).await,
+            CacheBackend::Redis(p) => p.get(key).await,
+            CacheBackend::Fallback(p) => p.get(key).await,
+            CacheBackend::Tiered(p) => p.get(key).await,
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        match self {
+            CacheBackend::Memory(p) => p.delete(key).await,
+            CacheBackend::Redis(p) => p.delete(key).await,
+            CacheBackend::Fallback(p) => p.delete(key).await,
+            CacheBackend::Tiered(p) => p.delete(key).await,
+        }
+    }
+
+    async fn clear(&self) -> Result<(), String> {
+        match self {
+            CacheBackend::Memory(p) => p.clear().await,
+            CacheBackend::Redis(p) => p.clear().await,
+            CacheBackend::Fallback(p) => p.clear().await,
+            CacheBackend::Tiered(p) => p.clear().await,
+        }
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, String> {
+        match self {
+            CacheBackend::Memory(p) => p.exists(key).await,
+            CacheBackend::Redis(p) => p.exists(key).await,
+            CacheBackend::Fallback(p) => p.exists(key).await,
+            CacheBackend::Tiered(p) => p.exists(key).await,
+        }
+    }
+
+    async fn invalidate_tag(&self, tag: &str) -> Result<u64, String> {
+        match self {
+            CacheBackend::Memory(p) => p.invalidate_tag(tag).await,
+            CacheBackend::Redis(p) => p.invalidate_tag(tag).await,
+            CacheBackend::Fallback(p) => p.invalidate_tag(tag).await,
+            CacheBackend::Tiered(p) => p.invalidate_tag(tag).await,
+        }
+    }
+
+    async fn invalidate_prefix(&self, prefix: &str) -> Result<u64, String> {
+        match self {
+            CacheBackend::Memory(p) => p.invalidate_prefix(prefix).await,
+            CacheBackend::Redis(p) => p.invalidate_prefix(prefix).await,
+            CacheBackend::Fallback(p) => p.invalidate_prefix(prefix).await,
+            CacheBackend::Tiered(p) => p.invalidate_prefix(prefix).await,
+        }
+    }
+
+    async fn invalidate_all(&self, resource_type: &str) -> Result<u64, String> {
+        match self {
+            CacheBackend::Memory(p) => p.invalidate_all(resource_type).await,
+            CacheBackend::Redis(p) => p.invalidate_all(resource_type).await,
+            CacheBackend::Fallback(p) => p.invalidate_all(resource_type).await,
+            CacheBackend::Tiered(p) => p.invalidate_all(resource_type).await,
+        }
+    }
+
+    async fn get_stats(&self) -> Result<serde_json::Value, String> {
+        match self {
+            CacheBackend::Memory(p) => p.get_stats().await,
+            CacheBackend::Redis(p) => p.get_stats().await,
+            CacheBackend::Fallback(p) => p.get_stats().await,
+            CacheBackend::Tiered(p) => p.get_stats().await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::cache::{get_resource_cache, init_cache_registry, register_resource_cache};
+    use crate::core::error::AppError;
+    use serde::{Deserialize, Serialize};
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct TestResource {
+        id: String,
+        value: i32,
+    }
+
+    impl ApiResource for TestResource {
+        type Id = String;
+
+        fn resource_type() -> &'static str {
+            "test_resource"
+        }
+
+        fn api_name() -> &'static str {
+            "TestService"
+        }
+    }
+
+    fn app_config_with_provider(provider: CacheProviderType) -> AppConfig {
+        let mut config = AppConfig::default();
+        config.cache.provider = provider;
+        // Point Redis at a closed port so tests never touch a real server
+        config.cache.redis.url = "redis://127.0.0.1:1".to_string();
+        config.cache.redis.connect_timeout_ms = 200;
+        config
+    }
+
+    #[test]
+    fn test_from_app_config_selects_provider() {
+        let registry = init_cache_registry(true, 100, 60);
+
+        for provider in [
+            CacheProviderType::Memory,
+            CacheProviderType::Redis,
+            CacheProviderType::Fallback,
+            CacheProviderType::Tiered,
+        ] {
+            let backend =
+                CacheBackend::from_app_config(&app_config_with_provider(provider), &registry);
+            assert_eq!(backend.provider_type(), provider);
+        }
+    }
+
+    #[test]
+    fn test_cache_key() {
+        assert_eq!(CacheBackend::cache_key("pet", "42"), "pet:42");
+    }
+
+    #[tokio::test]
+    async fn test_memory_backend_uses_shared_registry() {
+        let registry = init_cache_registry(true, 100, 60);
+        let _ = register_resource_cache::<TestResource>(&registry, "test_resource");
+        let backend = CacheBackend::from_app_config(
+            &app_config_with_provider(CacheProviderType::Memory),
+            &registry,
+        );
+
+        let resource = TestResource {
+            id: "1".to_string(),
+            value: 7,
+        };
+        let fetched = backend
+            .get_or_fetch("test_resource", "1", 60, {
+                let resource = resource.clone();
+                move || async move { Ok::<_, AppError>(resource) }
+            })
+            .await
+            .unwrap();
+        assert_eq!(fetched, (resource.clone(), CacheOutcome::Miss));
+
+        let cache = get_resource_cache::<TestResource>(&registry, "test_resource").unwrap();
+        assert_eq!(cache.cache.get("1").await, Some(resource));
+    }
+
+    #[tokio::test]
+    async fn test_fallback_backend_serves_from_memory_when_redis_down() {
+        let registry = init_cache_registry(true, 100, 60);
+        let _ = register_resource_cache::<TestResource>(&registry, "test_resource");
+        let backend = CacheBackend::from_app_config(
+            &app_config_with_provider(CacheProviderType::Fallback),
+            &registry,
+        );
+        let _ = backend.init().await;
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let resource = TestResource {
+            id: "2".to_string(),
+            value: 9,
+        };
+
+        for expected in [CacheOutcome::Miss, CacheOutcome::Hit] {
+            let fetched = backend
+                .get_or_fetch("test_resource", "2", 60, {
+                    let calls = calls.clone();
+                    let resource = resource.clone();
+                    move || async move {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        Ok::<_, AppError>(resource)
+                    }
+                })
+                .await
+                .unwrap();
+            assert_eq!(fetched, (resource.clone(), expected));
+        }
+
+        // The second call is served by the memory tier of the fallback provider
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        let cache = get_resource_cache::<TestResource>(&registry, "test_resource").unwrap();
+        assert_eq!(cache.cache.get("2").await, Some(resource));
+    }
+
+    #[tokio::test]
+    async fn test_redis_backend_fetches_when_redis_down() {
+        let registry = init_cache_registry(true, 100, 60);
+        let backend = CacheBackend::from_app_config(
+            &app_config_with_provider(CacheProviderType::Redis),
+            &registry,
+        );
+
+        let resource = TestResource {
+            id: "3".to_string(),
+            value: 1,
+        };
+
+        // A cache outage must not fail the request
+        let fetched = backend
+            .get_or_fetch("test_resource", "3", 60, {
+                let resource = resource.clone();
+                move || async move { Ok::<_, AppError>(resource) }
+            })
+            .await
+            .unwrap();
+        assert_eq!(fetched, (resource, CacheOutcome::Miss));
+    }
+}
@@ -0,0 +1,528 @@
+//! Encoding of values stored in remote caches
+//!
+//! Remote providers store values as JSON. A [`CacheCodec`] can compress large
+//! values and encrypt them with AES-256-GCM before they leave the process.
+//! Encoded values are text, so they stay readable by the RESP client and by
+//! tools like `redis-cli`:
+//!
+//! ```text
+//! $cc1:<compression>:<key id or ->:<base64 payload>
+//! ```
+//!
+//! The payload is the (possibly compressed) JSON, or for encrypted values a
+//! 12-byte nonce followed by the ciphertext. The header and the cache key are
+//! authenticated as associated data, so a value can neither be relabelled nor
+//! moved to another key without failing decryption. Values without the header
+//! are plain JSON. Once encryption is enabled, plain and unencrypted values are
+//! rejected, unless `accept_plaintext_during_migration` is set to let entries
+//! written before then expire naturally.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use crate::config::{CacheCodecConfig, CompressionAlgorithm};
+
+/// Prefix marking an encoded value
+const ENVELOPE_PREFIX: &str = "$cc1:";
+/// Key ID written for values that are not encrypted
+const NO_KEY_ID: &str = "-";
+/// Length of the AES-GCM nonce stored before the ciphertext
+const NONCE_LEN: usize = 12;
+/// Upper bound on a decompressed value, guarding against decompression bombs
+const MAX_DECODED_BYTES: u64 = 64 * 1024 * 1024;
+/// zstd level balancing speed and ratio for small JSON documents
+const ZSTD_LEVEL: i32 = 3;
+
+/// Compresses and encrypts cache values according to [`CacheCodecConfig`]
+pub struct CacheCodec {
+    compression: CompressionAlgorithm,
+    compression_threshold: usize,
+    active_key_id: Option<String>,
+    keys: HashMap<String, Aes256Gcm>,
+    accept_plaintext: bool,
+}
+
+impl std::fmt::Debug for CacheCodec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut key_ids: Vec<&String> = self.keys.keys().collect();
+        key_ids.sort();
+        f.debug_struct("CacheCodec")
+            .field("compression", &self.compression)
+            .field("compression_threshold", &self.compression_threshold)
+            .field("active_key_id", &self.active_key_id)
+            .field("key_ids", &key_ids)
+            .field("accept_plaintext", &self.accept_plaintext)
+            .finish()
+    }
+}
+
+impl Default for CacheCodec {
+    /// A codec that stores plain JSON
+    fn default() -> Self {
+        Self {
+            compression: CompressionAlgorithm::None,
+            compression_threshold: 0,
+            active_key_id: None,
+            keys: HashMap::new(),
+            accept_plaintext: true,
+        }
+    }
+}
+
+impl CacheCodec {
+    /// Build the codec, validating the encryption keys
+    pub fn from_config(config: &CacheCodecConfig) -> Result<Self, String> {
+        let mut codec = Self {
+            compression: config.compression,
+            compression_threshold: config.compression_threshold_bytes,
+            ..Self::default()
+        };
+
+        let encryption = &config.encryption;
+        if !encryption.enabled {
+            return Ok(codec);
+        }
+
+        for (key_id, encoded) in &encryption.keys {
+            if key_id.is_empty() || key_id == NO_KEY_ID || key_id.contains(':') {
+                return Err(format!(
+                    "Invalid cache encryption key ID '{}': must be non-empty, not '-' and without ':'",
+                    key_id
+                ));
+            }
+            let key = BASE64
+                .decode(encoded.trim())
+                .map_err(|e| format!("Cache encryption key '{}' is not base64: {}", key_id, e))?;
+            let cipher = Aes256Gcm::new_from_slice(&key).map_err(|_| {
+                format!(
+                    "Cache encryption key '{}' must be 32 bytes, got {}",
+                    key_id,
+                    key.len()
+                )
+            })?;
+            codec.keys.insert(key_id.clone(), cipher);
+        }
+
+        if !codec.keys.contains_key(&encryption.active_key_id) {
+            return Err(format!(
+                "Active cache encryption key '{}' is not configured",
+                encryption.active_key_id
+            ));
+        }
+        codec.active_key_id = Some(encryption.active_key_id.clone());
+        codec.accept_plaintext = encryption.accept_plaintext_during_migration;
+
+        Ok(codec)
+    }
+
+    /// Whether values are encrypted
+    pub fn encrypts(&self) -> bool {
+        self.active_key_id.is_some()
+    }
+
+    /// Summary of the codec settings, without key material
+    pub fn describe(&self) -> serde_json::Value {
+        serde_json::json!({
+            "compression": self.compression.to_string(),
+            "compression_threshold_bytes": self.compression_threshold,
+            "encryption": self.encrypts(),
+            "active_key_id": self.active_key_id,
+        })
+    }
+
+    /// Encode serialized JSON for storage under `key`
+    pub fn encode(&self, key: &str, json: &str) -> Result<String, String> {
+        let compression = if self.compression != CompressionAlgorithm::None
+            && json.len() >= self.compression_threshold
+        {
+            self.compression
+        } else {
+            CompressionAlgorithm::None
+        };
+
+        if compression == CompressionAlgorithm::None && !self.encrypts() {
+            return Ok(json.to_string());
+        }
+
+        let body = compress(compression, json.as_bytes())?;
+        let key_id = self.active_key_id.as_deref().unwrap_or(NO_KEY_ID);
+        let header = format!("{}{}:{}:", ENVELOPE_PREFIX, compression, key_id);
+
+        let payload = match self
+            .active_key_id
+            .as_deref()
+            .and_then(|id| self.keys.get(id))
+        {
+            Some(cipher) => {
+                let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+                let ciphertext = cipher
+                    .encrypt(
+                        &nonce,
+                        Payload {
+                            msg: &body,
+                            aad: &associated_data(header.as_bytes(), key),
+                        },
+                    )
+                    .map_err(|_| "Failed to encrypt cache value".to_string())?;
+                let mut payload = nonce.to_vec();
+                payload.extend(ciphertext);
+                payload
+            }
+            None => body,
+        };
+
+        Ok(format!("{}{}", header, BASE64.encode(payload)))
+    }
+
+    /// Decode a value stored under `key` back into JSON bytes
+    pub fn decode(&self, key: &str, stored: &[u8]) -> Result<Vec<u8>, String> {
+        let Some(envelope) = stored.strip_prefix(ENVELOPE_PREFIX.as_bytes()) else {
+            // Written before the codec was enabled
+            self.check_plaintext_accepted()?;
+            return Ok(stored.to_vec());
+        };
+        let envelope = std::str::from_utf8(envelope)
+            .map_err(|_| "Encoded cache value is not valid UTF-8".to_string())?;
+
+        let mut parts = envelope.splitn(3, ':');
+        let (Some(compression), Some(key_id), Some(encoded)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err("Malformed encoded cache value".to_string());
+        };
+        let header_len = ENVELOPE_PREFIX.len() + compression.len() + key_id.len() + 2;
+        let header = &stored[..header_len];
+
+        let payload = BASE64
+            .decode(encoded)
+            .map_err(|e| format!("Encoded cache value is not base64: {}", e))?;
+
+        let body = if key_id == NO_KEY_ID {
+            self.check_plaintext_accepted()?;
+            payload
+        } else {
+            let cipher = self
+                .keys
+                .get(key_id)
+                .ok_or_else(|| format!("Cache value encrypted with unknown key '{}'", key_id))?;
+            if payload.len() < NONCE_LEN {
+                return Err("Encrypted cache value is truncated".to_string());
+            }
+            let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+            cipher
+                .decrypt(
+                    Nonce::from_slice(nonce),
+                    Payload {
+                        msg: ciphertext,
+                        aad: &associated_data(header, key),
+                    },
+                )
+                .map_err(|_| format!("Failed to decrypt cache value with key '{}'", key_id))?
+        };
+
+        decompress(compression, &body)
+    }
+
+    /// Unencrypted values are only trusted without encryption or while migrating to it
+    fn check_plaintext_accepted(&self) -> Result<(), String> {
+        if self.encrypts() && !self.accept_plaintext {
+            return Err("Rejected unencrypted cache value".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Associated data binding a ciphertext to its header and cache key
+fn associated_data(header: &[u8], key: &str) -> Vec<u8> {
+    let mut aad = header.to_vec();
+    aad.extend_from_slice(key.as_bytes());
+    aad
+}
+
+fn compress(algorithm: CompressionAlgorithm, data: &[u8]) -> Result<Vec<u8>, String> {
+    match algorithm {
+        CompressionAlgorithm::None => Ok(data.to_vec()),
+        CompressionAlgorithm::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder
+                .write_all(data)
+                .and_then(|_| encoder.finish())
+                .map_err(|e| format!("Failed to gzip cache value: {}", e))
+        }
+        CompressionAlgorithm::Zstd => zstd::encode_all(data, ZSTD_LEVEL)
+            .map_err(|e| format!("Failed to zstd-compress cache value: {}", e)),
+    }
+}
+
+fn decompress(algorithm: &str, data: &[u8]) -> Result<Vec<u8>, String> {
+    let reader: Box<dyn Read + '_> = match algorithm {
+        "none" => return Ok(data.to_vec()),
+        "gzip" => Box::new(GzDecoder::new(data)),
+        "zstd" => Box::new(
+            zstd::stream::read::Decoder::new(data)
+                .map_err(|e| format!("Failed to read zstd cache value: {}", e))?,
+        ),
+        other => return Err(format!("Unknown cache value compression '{}'", other)),
+    };
+
+    let mut decoded = Vec::new();
+    reader
+        .take(MAX_DECODED_BYTES + 1)
+        .read_to_end(&mut decoded)
+        .map_err(|e| format!("Failed to decompress cache value: {}", e))?;
+    if decoded.len() as u64 > MAX_DECODED_BYTES {
+        return Err(format!(
+            "Decompressed cache value exceeds {} bytes",
+            MAX_DECODED_BYTES
+        ));
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CacheEncryptionConfig;
+
+    fn key(byte: u8) -> String {
+        BASE64.encode([byte; 32])
+    }
+
+    fn config(
+        compression: CompressionAlgorithm,
+        active_key_id: Option<&str>,
+        keys: &[(&str, String)],
+    ) -> CacheCodecConfig {
+        CacheCodecConfig {
+            compression,
+            compression_threshold_bytes: 64,
+            encryption: CacheEncryptionConfig {
+                enabled: active_key_id.is_some(),
+                active_key_id: active_key_id.unwrap_or_default().to_string(),
+                keys: keys
+                    .iter()
+                    .map(|(id, key)| (id.to_string(), key.clone()))
+                    .collect(),
+                accept_plaintext_during_migration: false,
+            },
+        }
+    }
+
+    fn large_json() -> String {
+        serde_json::json!({ "name": "x".repeat(2000) }).to_string()
+    }
+
+    #[test]
+    fn test_default_codec_stores_plain_json() {
+        let codec = CacheCodec::from_config(&CacheCodecConfig::default()).unwrap();
+        let json = large_json();
+        assert_eq!(codec.encode("pet:1", &json).unwrap(), json);
+        assert_eq!(
+            codec.decode("pet:1", json.as_bytes()).unwrap(),
+            json.as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_compression_respects_threshold() {
+        for algorithm in [CompressionAlgorithm::Gzip, CompressionAlgorithm::Zstd] {
+            let codec = CacheCodec::from_config(&config(algorithm, None, &[])).unwrap();
+
+            let small = r#"{"id":"1"}"#;
+            assert_eq!(codec.encode("pet:1", small).unwrap(), small);
+
+            let json = large_json();
+            let encoded = codec.encode("pet:1", &json).unwrap();
+            assert!(encoded.starts_with(&format!("$cc1:{}:-:", algorithm)));
+            assert!(encoded.len() < json.len() / 4);
+            assert_eq!(
+                codec.decode("pet:1", encoded.as_bytes()).unwrap(),
+                json.as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn test_encryption_round_trip_hides_plaintext() {
+        let codec = CacheCodec::from_config(&config(
+            CompressionAlgorithm::Zstd,
+            Some("k1"),
+            &[("k1", key(1))],
+        ))
+        .unwrap();
+
+        let small = r#"{"email":"secret@example.com"}"#;
+        let encoded = codec.encode("pet:1", small).unwrap();
+        assert!(encoded.starts_with("$cc1:none:k1:"));
+        assert!(!encoded.contains("secret"));
+        assert_eq!(
+            codec.decode("pet:1", encoded.as_bytes()).unwrap(),
+            small.as_bytes()
+        );
+
+        // A fresh nonce per value
+        assert_ne!(codec.encode("pet:1", small).unwrap(), encoded);
+
+        let json = large_json();
+        let encoded = codec.encode("pet:1", &json).unwrap();
+        assert!(encoded.starts_with("$cc1:zstd:k1:"));
+        assert_eq!(
+            codec.decode("pet:1", encoded.as_bytes()).unwrap(),
+            json.as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_key_rotation_reads_values_written_with_retired_keys() {
+        let old = CacheCodec::from_config(&config(
+            CompressionAlgorithm::None,
+            Some("k1"),
+            &[("k1", key(1))],
+        ))
+        .unwrap();
+        let written_with_k1 = old.encode("pet:1", r#"{"id":"1"}"#).unwrap();
+
+        let rotated = CacheCodec::from_config(&config(
+            CompressionAlgorithm::None,
+            Some("k2"),
+            &[("k1", key(1)), ("k2", key(2))],
+        ))
+        .unwrap();
+        assert_eq!(
+            rotated.decode("pet:1", written_with_k1.as_bytes()).unwrap(),
+            br#"{"id":"1"}"#
+        );
+        assert!(
+            rotated
+                .encode("pet:1", "{}")
+                .unwrap()
+                .starts_with("$cc1:none:k2:")
+        );
+
+        // Once k1 is removed its values can no longer be read
+        let k2_only = CacheCodec::from_config(&config(
+            CompressionAlgorithm::None,
+            Some("k2"),
+            &[("k2", key(2))],
+        ))
+        .unwrap();
+        let error = k2_only
+            .decode("pet:1", written_with_k1.as_bytes())
+            .unwrap_err();
+        assert!(error.contains("unknown key 'k1'"));
+    }
+
+    #[test]
+    fn test_tampered_values_are_rejected() {
+        let codec = CacheCodec::from_config(&config(
+            CompressionAlgorithm::Gzip,
+            Some("k1"),
+            &[("k1", key(1))],
+        ))
+        .unwrap();
+        let encoded = codec.encode("pet:1", &large_json()).unwrap();
+
+        // Changing the header breaks authentication
+        let relabelled = encoded.replacen("$cc1:gzip:", "$cc1:zstd:", 1);
+        assert!(codec.decode("pet:1", relabelled.as_bytes()).is_err());
+
+        // Or moving the value to another cache key
+        assert!(codec.decode("pet:2", encoded.as_bytes()).is_err());
+
+        // So does a different key with the same ID
+        let other = CacheCodec::from_config(&config(
+            CompressionAlgorithm::Gzip,
+            Some("k1"),
+            &[("k1", key(9))],
+        ))
+        .unwrap();
+        assert!(other.decode("pet:1", encoded.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_unencrypted_values_are_rejected_once_encryption_is_enabled() {
+        let plain = r#"{"id":"1"}"#;
+        let compressed = CacheCodec::from_config(&config(CompressionAlgorithm::Gzip, None, &[]))
+            .unwrap()
+            .encode("pet:1", &large_json())
+            .unwrap();
+
+        let mut settings = config(CompressionAlgorithm::None, Some("k1"), &[("k1", key(1))]);
+        let codec = CacheCodec::from_config(&settings).unwrap();
+        assert!(codec.decode("pet:1", plain.as_bytes()).is_err());
+        assert!(codec.decode("pet:1", compressed.as_bytes()).is_err());
+
+        settings.encryption.accept_plaintext_during_migration = true;
+        let migrating = CacheCodec::from_config(&settings).unwrap();
+        assert_eq!(
+            migrating.decode("pet:1", plain.as_bytes()).unwrap(),
+            plain.as_bytes()
+        );
+        assert_eq!(
+            migrating.decode("pet:1", compressed.as_bytes()).unwrap(),
+            large_json().as_bytes()
+        );
+        // New values are still encrypted
+        assert!(
+            migrating
+                .encode("pet:1", plain)
+                .unwrap()
+                .starts_with("$cc1:none:k1:")
+        );
+    }
+
+    #[test]
+    fn test_invalid_key_configuration_is_rejected() {
+        let short_key = BASE64.encode([1u8; 16]);
+        assert!(
+            CacheCodec::from_config(&config(
+                CompressionAlgorithm::None,
+                Some("k1"),
+                &[("k1", short_key)]
+            ))
+            .is_err()
+        );
+        assert!(
+            CacheCodec::from_config(&config(
+                CompressionAlgorithm::None,
+                Some("missing"),
+                &[("k1", key(1))]
+            ))
+            .is_err()
+        );
+        assert!(
+            CacheCodec::from_config(&config(
+                CompressionAlgorithm::None,
+                Some("a:b"),
+                &[("a:b", key(1))]
+            ))
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_debug_output_omits_key_material() {
+        let encoded_key = key(7);
+        let codec = CacheCodec::from_config(&config(
+            CompressionAlgorithm::None,
+            Some("k1"),
+            &[("k1", encoded_key.clone())],
+        ))
+        .unwrap();
+        assert!(!format!("{:?}", codec).contains(&encoded_key));
+
+        let settings = config(
+            CompressionAlgorithm::None,
+            Some("k1"),
+            &[("k1", encoded_key.clone())],
+        );
+        assert!(!format!("{:?}", settings).contains(&encoded_key));
+    }
+}
@@ -33,16 +33,9 @@ pub struct FallbackConfig {
 impl Default for FallbackConfig {
     fn default() -> Self {
         // Get the app config to use its cache settings
-        let app_config = match config::get_config() {
-            config => config,
-        };
+        let app_config = config::get_config();
 
-        Self {
-            redis_config: RedisConfig::default(),
-            memory_max_capacity: app_config.cache.max_capacity,
-            memory_ttl_seconds: app_config.cache.ttl_seconds,
-            reconnect_interval_seconds: app_config.cache.reconnect_interval_seconds,
-        }
+        Self::from_app_config(&app_config)
     }
 }
 
@@ -50,7 +43,7 @@ impl Default for FallbackConfig {
 impl FallbackConfig {
     pub fn from_app_config(app_config: &AppConfig) -> Self {
         Self {
-            redis_config: RedisConfig::default(),
+            redis_config: RedisConfig::from_app_config(app_config),
             memory_max_capacity: app_config.cache.max_capacity,
             memory_ttl_seconds: app_config.cache.ttl_seconds,
             reconnect_interval_seconds: app_config.cache.reconnect_interval_seconds,
@@ -75,31 +68,46 @@ impl ApiResource for StringValue {
 }
 
 /// Fallback cache provider that tries Redis first, then falls back to memory
+///
+/// Clones share the same Redis connection pool, memory cache and fallback state,
+/// so the reconnect task started by [`FallbackCacheProvider::start_reconnect_task`]
+/// switches every clone back to Redis once it recovers.
 pub struct FallbackCacheProvider {
     redis: Arc<RedisCacheProvider>,
     memory: Arc<MemoryCacheProvider>,
-    using_fallback: AtomicBool,
-    last_redis_failure: Mutex<Option<Instant>>,
-    reconnect_lock: AsyncMutex<()>,
+    using_fallback: Arc<AtomicBool>,
+    last_redis_failure: Arc<Mutex<Option<Instant>>>,
+    reconnect_lock: Arc<AsyncMutex<()>>,
     config: FallbackConfig,
 }
 
 impl FallbackCacheProvider {
     /// Create a new fallback cache provider
     pub fn new(config: FallbackConfig) -> Self {
+        let memory =
+            MemoryCacheProvider::new(config.memory_max_capacity, config.memory_ttl_seconds);
+        Self::with_memory_provider(config, memory)
+    }
+
+    /// Create a fallback cache provider around an existing memory provider
+    ///
+    /// Use this to share the application's cache registry with the fallback tier.
+    pub fn with_memory_provider(config: FallbackConfig, memory: MemoryCacheProvider) -> Self {
         Self {
             redis: Arc::new(RedisCacheProvider::new(config.redis_config.clone())),
-            memory: Arc::new(MemoryCacheProvider::new(
-                config.memory_max_capacity,
-                config.memory_ttl_seconds,
-            )),
-            using_fallback: AtomicBool::new(false),
-            last_redis_failure: Mutex::new(None),
-            reconnect_lock: AsyncMutex::new(()),
+            memory: Arc::new(memory),
+            using_fallback: Arc::new(AtomicBool::new(false)),
+            last_redis_failure: Arc::new(Mutex::new(None)),
+            reconnect_lock: Arc::new(AsyncMutex::new(())),
             config,
         }
     }
 
+    /// Whether the provider is currently serving from the memory fallback
+    pub fn is_using_fallback(&self) -> bool {
+        self.using_fallback.load(Ordering::Relaxed)
+    }
+
     /// Start the reconnection task
     pub async fn start_reconnect_task(&self) {
         let provider = self.clone();
@@ -129,7 +137,7 @@ impl FallbackCacheProvider {
         info!("🔄 Attempting to reconnect to Redis...");
 
         // Try to initialize Redis
-        match self.redis.init() {
+        match self.redis.init().await {
             Ok(_) => {
                 // Test Redis with a simple operation
                 let test_key = "reconnect_test";
@@ -187,9 +195,9 @@ impl Clone for FallbackCacheProvider {
         Self {
             redis: self.redis.clone(),
             memory: self.memory.clone(),
-            using_fallback: AtomicBool::new(self.using_fallback.load(Ordering::Relaxed)),
-            last_redis_failure: Mutex::new(*self.last_redis_failure.lock().unwrap()),
-            reconnect_lock: AsyncMutex::new(()),
+            using_fallback: self.using_fallback.clone(),
+            last_redis_failure: self.last_redis_failure.clone(),
+            reconnect_lock: self.reconnect_lock.clone(),
             config: self.config.clone(),
         }
     }
@@ -197,9 +205,9 @@ impl Clone for FallbackCacheProvider {
 
 #[async_trait]
 impl CacheProvider for FallbackCacheProvider {
-    fn init(&self) -> Result<(), String> {
+    async fn init(&self) -> Result<(), String> {
         // Initialize both providers
-        match self.redis.init() {
+        match self.redis.init().await {
             Ok(_) => {
                 // Redis initialized successfully, use it as primary
                 self.using_fallback.store(false, Ordering::SeqCst);
@@ -216,7 +224,7 @@ impl CacheProvider for FallbackCacheProvider {
         }
 
         // Always initialize memory cache as fallback
-        self.memory.init()?;
+        self.memory.init().await?;
 
         Ok(())
     }
@@ -242,6 +250,34 @@ impl CacheProvider for FallbackCacheProvider {
         self.memory.set(key, value, ttl_seconds).await
     }
 
+    async fn set_with_tags<T: ApiResource>(
+        &self,
+        key: &str,
+        value: T,
+        ttl_seconds: u64,
+        tags: &[String],
+    ) -> Result<(), String> {
+        // Try Redis first if not in fallback mode
+        if !self.using_fallback.load(Ordering::Relaxed) {
+            match self
+                .redis
+                .set_with_tags(key, value.clone(), ttl_seconds, tags)
+                .await
+            {
+                Ok(_) => return Ok(()),
+                Err(e) => {
+                    self.handle_redis_failure(&e);
+                    // Fall through to memory cache
+                }
+            }
+        }
+
+        // Use memory cache as fallback
+        self.memory
+            .set_with_tags(key, value, ttl_seconds, tags)
+            .await
+    }
+
     async fn get<T: ApiResource>(&self, key: &str) -> Result<Option<T>, String> {
         // Try Redis first if not in fallback mode
         if !self.using_fallback.load(Ordering::Relaxed) {
@@ -323,6 +359,46 @@ impl CacheProvider for FallbackCacheProvider {
         }
     }
 
+    async fn invalidate_tag(&self, tag: &str) -> Result<u64, String> {
+        let mut removed = 0;
+
+        // Try Redis first if not in fallback mode
+        if !self.using_fallback.load(Ordering::Relaxed) {
+            match self.redis.invalidate_tag(tag).await {
+                Ok(count) => removed += count,
+                Err(e) => {
+                    self.handle_redis_failure(&e);
+                    // Continue with memory cache regardless
+                }
+            }
+        }
+
+        // Entries written while Redis was down live in the memory cache
+        removed += self.memory.invalidate_tag(tag).await?;
+
+        Ok(removed)
+    }
+
+    async fn invalidate_prefix(&self, prefix: &str) -> Result<u64, String> {
+        let mut removed = 0;
+
+        // Try Redis first if not in fallback mode
+        if !self.using_fallback.load(Ordering::Relaxed) {
+            match self.redis.invalidate_prefix(prefix).await {
+                Ok(count) => removed += count,
+                Err(e) => {
+                    self.handle_redis_failure(&e);
+                    // Continue with memory cache regardless
+                }
+            }
+        }
+
+        // Entries written while Redis was down live in the memory cache
+        removed += self.memory.invalidate_prefix(prefix).await?;
+
+        Ok(removed)
+    }
+
     async fn get_stats(&self) -> Result<serde_json::Value, String> {
         let is_fallback = self.using_fallback.load(Ordering::Relaxed);
 
@@ -391,13 +467,25 @@ mod tests {
         assert!(provider.last_redis_failure.lock().unwrap().is_none());
     }
 
+    #[tokio::test]
+    async fn test_fallback_cache_clones_share_state() {
+        let config = FallbackConfig::default();
+        let provider = FallbackCacheProvider::new(config);
+        let clone = provider.clone();
+
+        provider.handle_redis_failure("connection refused");
+
+        // The reconnect task works on a clone, so the flag must be shared
+        assert!(clone.is_using_fallback());
+    }
+
     #[tokio::test]
     async fn test_fallback_cache_init() {
         let config = FallbackConfig::default();
         let provider = FallbackCacheProvider::new(config);
 
         // Initialize provider should succeed
-        let init_result = provider.init();
+        let init_result = provider.init().await;
         assert!(init_result.is_ok());
     }
 
@@ -407,7 +495,7 @@ mod tests {
         let provider = FallbackCacheProvider::new(config);
 
         // Initialize all providers
-        let _ = provider.init();
+        let _ = provider.init().await;
 
         // Create a test resource
         let resource = TestResource {
@@ -441,7 +529,7 @@ mod tests {
         let provider = FallbackCacheProvider::new(config);
 
         // Initialize all providers
-        let _ = provider.init();
+        let _ = provider.init().await;
 
         // Get nonexistent item
         let get_result = provider.get::<TestResource>("nonexistent").await;
@@ -458,7 +546,7 @@ mod tests {
         let provider = FallbackCacheProvider::new(config);
 
         // Initialize all providers
-        let _ = provider.init();
+        let _ = provider.init().await;
 
         // Delete call should try to delete from all providers
         let _delete_result = provider.delete("test-1").await;
@@ -473,7 +561,7 @@ mod tests {
         let provider = FallbackCacheProvider::new(config);
 
         // Initialize all providers
-        let _ = provider.init();
+        let _ = provider.init().await;
 
         // Clear call should try to clear all providers
         let _clear_result = provider.clear().await;
@@ -488,7 +576,7 @@ mod tests {
         let provider = FallbackCacheProvider::new(config);
 
         // Initialize all providers
-        let _ = provider.init();
+        let _ = provider.init().await;
 
         // Exists call should check all providers
         let _exists_result = provider.exists("test-1").await;
@@ -503,7 +591,7 @@ mod tests {
         let provider = FallbackCacheProvider::new(config);
 
         // Initialize all providers
-        let _ = provider.init();
+        let _ = provider.init().await;
 
         // Get stats should collect from all providers
         let stats_result = provider.get_stats().await;
@@ -2,6 +2,8 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
 
 use super::CacheProvider;
 use crate::core::cache::{
@@ -12,6 +14,10 @@ use crate::core::metrics::try_record_metrics;
 use crate::utils::api_resource::ApiResource;
 
 /// In-memory cache provider using Moka cache
+///
+/// Entries live in the per-resource-type caches of a [`CacheRegistry`]. Keys may be
+/// qualified with the resource type (`"pet:42"`, as used by remote providers); the
+/// qualifier is stripped so the entry lines up with the registry's own `"42"` key.
 pub struct MemoryCacheProvider {
     registry: Arc<CacheRegistry>,
 }
@@ -20,11 +26,26 @@ impl MemoryCacheProvider {
     /// Create a new memory cache provider
     pub fn new(max_capacity: u64, ttl_seconds: u64) -> Self {
         let registry = init_cache_registry(true, max_capacity, ttl_seconds);
+        Self::from_registry(registry)
+    }
+
+    /// Create a memory cache provider backed by an existing cache registry
+    ///
+    /// The registry's caches are shared, so entries written through the provider are
+    /// visible to `get_or_fetch` and cache statistics on the same registry.
+    pub fn from_registry(registry: CacheRegistry) -> Self {
         Self {
             registry: Arc::new(registry),
         }
     }
 
+    /// Strip an optional `"{resource_type}:"` qualifier from a key
+    fn entry_key<T: ApiResource>(key: &str) -> &str {
+        key.strip_prefix(T::resource_type())
+            .and_then(|rest| rest.strip_prefix(':'))
+            .unwrap_or(key)
+    }
+
     /// Get the underlying cache registry
     pub fn registry(&self) -> Arc<CacheRegistry> {
         self.registry.clone()
@@ -33,7 +54,7 @@ impl MemoryCacheProvider {
 
 #[async_trait]
 impl CacheProvider for MemoryCacheProvider {
-    fn init(&self) -> Result<(), String> {
+    async fn init(&self) -> Result<(), String> {
         // Already initialized with the constructor
         Ok(())
     }
@@ -42,17 +63,47 @@ impl CacheProvider for MemoryCacheProvider {
         &self,
         key: &str,
         value: T,
-        _ttl_seconds: u64,
+        ttl_seconds: u64,
+    ) -> Result<(), String> {
+        self.set_with_tags(key, value, ttl_seconds, &[]).await
+    }
+
+    async fn set_with_tags<T: ApiResource>(
+        &self,
+        key: &str,
+        value: T,
+        ttl_seconds: u64,
+        tags: &[String],
     ) -> Result<(), String> {
         let resource_type = T::resource_type();
 
-        // Ensure the cache is registered for this resource type
-        let _ = register_resource_cache::<T>(&self.registry, &resource_type);
+        // Register the cache for this resource type on first use, keeping entries
+        // already stored through the shared registry
+        if !self.registry.is_registered(resource_type) {
+            let _ = register_resource_cache::<T>(&self.registry, resource_type);
+        }
 
         // Get the cache for this resource type
         if let Some(resource_cache) = get_resource_cache::<T>(&self.registry, &resource_type) {
-            // Insert the value with the provided key
-            resource_cache.cache.insert(key.to_string(), value).await;
+            let key = Self::entry_key::<T>(key).to_string();
+            // A TTL of 0 leaves the entry to the cache's own TTL
+            if ttl_seconds == 0 {
+                resource_cache.insert_with_tags(key, value, tags).await;
+                return Ok(());
+            }
+            if resource_cache.ttl_seconds > 0 && ttl_seconds > resource_cache.ttl_seconds {
+                warn!(
+                    "TTL of {}s for {} entry {} exceeds the cache TTL of {}s; it expires after {}s",
+                    ttl_seconds,
+                    resource_type,
+                    key,
+                    resource_cache.ttl_seconds,
+                    resource_cache.ttl_seconds
+                );
+            }
+            resource_cache
+                .insert_with_ttl(key, value, Duration::from_secs(ttl_seconds), tags)
+                .await;
             Ok(())
         } else {
             Err(format!(
@@ -68,19 +119,26 @@ impl CacheProvider for MemoryCacheProvider {
         // Get the cache for this resource type
         if let Some(resource_cache) = get_resource_cache::<T>(&self.registry, &resource_type) {
             // Get the value with the provided key
-            let value = resource_cache.cache.get(&key.to_string()).await;
+            let value = resource_cache.cache.get(Self::entry_key::<T>(key)).await;
             Ok(value)
         } else {
             Ok(None) // Cache not found for this resource type
         }
     }
 
-    async fn delete(&self, _key: &str) -> Result<(), String> {
-        // Since we need the resource type to find the right cache,
-        // and we only have the key, we would need to keep track of
-        // which cache contains which key. For simplicity, we'll just
-        // report that delete is not supported in this basic implementation.
-        Err("Delete by key only is not supported in memory provider. Use get_resource_cache directly.".to_string())
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        // A qualified key ("{resource_type}:{id}") names the cache to delete from;
+        // an unqualified key could belong to any resource type
+        for resource_type in self.registry.resource_types() {
+            if let Some(id) = key
+                .strip_prefix(resource_type.as_str())
+                .and_then(|rest| rest.strip_prefix(':'))
+            {
+                self.registry.invalidate_key(&resource_type, id).await;
+                return Ok(());
+            }
+        }
+        Err("Delete by key only is not supported in memory provider. Use a key qualified with a registered resource type or get_resource_cache directly.".to_string())
     }
 
     async fn clear(&self) -> Result<(), String> {
@@ -94,6 +152,31 @@ impl CacheProvider for MemoryCacheProvider {
         Err("Exists by key only is not supported in memory provider. Use get_resource_cache directly.".to_string())
     }
 
+    async fn invalidate_tag(&self, tag: &str) -> Result<u64, String> {
+        Ok(self.registry.invalidate_tag(tag).await)
+    }
+
+    async fn invalidate_prefix(&self, prefix: &str) -> Result<u64, String> {
+        // Keys are "{resource_type}:{id}"; map the prefix onto each registered type
+        let mut removed = 0;
+        for resource_type in self.registry.resource_types() {
+            let qualifier = format!("{}:", resource_type);
+            if let Some(id_prefix) = prefix.strip_prefix(&qualifier) {
+                removed += self
+                    .registry
+                    .invalidate_prefix(&resource_type, id_prefix)
+                    .await;
+            } else if qualifier.starts_with(prefix) {
+                removed += self.registry.invalidate_all(&resource_type).await;
+            }
+        }
+        Ok(removed)
+    }
+
+    async fn invalidate_all(&self, resource_type: &str) -> Result<u64, String> {
+        Ok(self.registry.invalidate_all(resource_type).await)
+    }
+
     async fn get_stats(&self) -> Result<serde_json::Value, String> {
         // Use the metrics recorder to get stats for all caches
         let _metrics_text = match try_record_metrics() {
@@ -121,6 +204,7 @@ impl CacheProvider for MemoryCacheProvider {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::cache::register_resource_cache;
     use crate::utils::api_resource::ApiResource;
     use serde::{Deserialize, Serialize};
     use std::sync::Arc;
@@ -156,7 +240,7 @@ mod tests {
     #[tokio::test]
     async fn test_memory_cache_init() {
         let provider = MemoryCacheProvider::new(100, 3600);
-        let result = provider.init();
+        let result = provider.init().await;
         assert!(result.is_ok());
     }
 
@@ -196,6 +280,57 @@ mod tests {
         assert_eq!(retrieved_resource.value, 42);
     }
 
+    #[tokio::test]
+    async fn test_memory_cache_shares_registry_and_strips_qualified_keys() {
+        let registry = init_cache_registry(true, 100, 3600);
+        let _ = register_resource_cache::<TestResource>(&registry, "test_resource");
+        let provider = MemoryCacheProvider::from_registry(registry.clone());
+
+        let resource = TestResource {
+            id: "test-1".to_string(),
+            name: "Test Resource".to_string(),
+            value: 42,
+        };
+        provider
+            .set("test_resource:test-1", resource.clone(), 3600)
+            .await
+            .unwrap();
+
+        // Visible through the shared registry under the unqualified key
+        let cache = get_resource_cache::<TestResource>(&registry, "test_resource").unwrap();
+        assert_eq!(cache.cache.get("test-1").await, Some(resource.clone()));
+
+        // And through the provider with either form of the key
+        assert_eq!(
+            provider.get::<TestResource>("test-1").await.unwrap(),
+            Some(resource.clone())
+        );
+        assert_eq!(
+            provider
+                .get::<TestResource>("test_resource:test-1")
+                .await
+                .unwrap(),
+            Some(resource)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memory_cache_deletes_qualified_keys() {
+        let provider = MemoryCacheProvider::new(100, 3600);
+        let resource = TestResource {
+            id: "test-1".to_string(),
+            name: "Test Resource".to_string(),
+            value: 42,
+        };
+        provider
+            .set("test_resource:test-1", resource, 3600)
+            .await
+            .unwrap();
+
+        provider.delete("test_resource:test-1").await.unwrap();
+        assert_eq!(provider.get::<TestResource>("test-1").await.unwrap(), None);
+    }
+
     #[tokio::test]
     async fn test_memory_cache_get_nonexistent() {
         let provider = MemoryCacheProvider::new(100, 3600);
@@ -248,4 +383,137 @@ mod tests {
         assert_eq!(stats["ttl_seconds"], 3600);
         assert!(stats["stats"].is_array());
     }
+
+    #[tokio::test]
+    async fn test_memory_cache_set_keeps_existing_entries() {
+        let provider = MemoryCacheProvider::new(100, 3600);
+
+        let first = TestResource {
+            id: "1".to_string(),
+            name: "First".to_string(),
+            value: 1,
+        };
+        let second = TestResource {
+            id: "2".to_string(),
+            name: "Second".to_string(),
+            value: 2,
+        };
+
+        provider.set("1", first.clone(), 0).await.unwrap();
+        provider.set("2", second.clone(), 60).await.unwrap();
+
+        // The second set must not replace the cache registered by the first
+        let retrieved: Option<TestResource> = provider.get("1").await.unwrap();
+        assert_eq!(retrieved, Some(first));
+        let retrieved: Option<TestResource> = provider.get("2").await.unwrap();
+        assert_eq!(retrieved, Some(second));
+
+        // The resource type uses the provider's TTL, not that of the first set
+        let cache =
+            get_resource_cache::<TestResource>(&provider.registry(), "test_resource").unwrap();
+        assert_eq!(cache.ttl_seconds, 3600);
+    }
+
+    #[tokio::test]
+    async fn test_memory_cache_set_honours_ttl_per_entry() {
+        let provider = MemoryCacheProvider::new(100, 3600);
+
+        provider
+            .set("long", test_resource("long"), 60)
+            .await
+            .unwrap();
+        provider
+            .set("short", test_resource("short"), 1)
+            .await
+            .unwrap();
+        provider
+            .set("default", test_resource("default"), 0)
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+
+        // Only the entry stored with the short TTL has expired
+        let short: Option<TestResource> = provider.get("short").await.unwrap();
+        assert_eq!(short, None);
+        let long: Option<TestResource> = provider.get("long").await.unwrap();
+        assert_eq!(long, Some(test_resource("long")));
+        let default: Option<TestResource> = provider.get("default").await.unwrap();
+        assert_eq!(default, Some(test_resource("default")));
+
+        // Rewriting the entry with a longer TTL replaces the short one
+        provider
+            .set("short", test_resource("short"), 1)
+            .await
+            .unwrap();
+        provider
+            .set("short", test_resource("short"), 60)
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        let short: Option<TestResource> = provider.get("short").await.unwrap();
+        assert_eq!(short, Some(test_resource("short")));
+    }
+
+    #[tokio::test]
+    async fn test_memory_cache_ttl_is_capped_by_cache_ttl() {
+        let provider = MemoryCacheProvider::new(100, 1);
+
+        provider.set("1", test_resource("1"), 60).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+
+        let retrieved: Option<TestResource> = provider.get("1").await.unwrap();
+        assert_eq!(retrieved, None);
+    }
+
+    fn test_resource(id: &str) -> TestResource {
+        TestResource {
+            id: id.to_string(),
+            name: format!("Resource {}", id),
+            value: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_cache_invalidate_tag() {
+        let provider = MemoryCacheProvider::new(100, 3600);
+        let owner = vec!["owner:42".to_string()];
+
+        provider
+            .set_with_tags("test_resource:1", test_resource("1"), 60, &owner)
+            .await
+            .unwrap();
+        provider
+            .set_with_tags("test_resource:2", test_resource("2"), 60, &owner)
+            .await
+            .unwrap();
+        provider.set("3", test_resource("3"), 60).await.unwrap();
+
+        assert_eq!(provider.invalidate_tag("owner:42").await.unwrap(), 2);
+
+        let retrieved: Option<TestResource> = provider.get("1").await.unwrap();
+        assert_eq!(retrieved, None);
+        let retrieved: Option<TestResource> = provider.get("3").await.unwrap();
+        assert_eq!(retrieved, Some(test_resource("3")));
+    }
+
+    #[tokio::test]
+    async fn test_memory_cache_invalidate_prefix_and_all() {
+        let provider = MemoryCacheProvider::new(100, 3600);
+        for id in ["10", "11", "2"] {
+            provider.set(id, test_resource(id), 60).await.unwrap();
+        }
+
+        // Prefixes are qualified with the resource type, as for remote providers
+        assert_eq!(
+            provider.invalidate_prefix("test_resource:1").await.unwrap(),
+            2
+        );
+        let retrieved: Option<TestResource> = provider.get("2").await.unwrap();
+        assert_eq!(retrieved, Some(test_resource("2")));
+
+        assert_eq!(provider.invalidate_all("test_resource").await.unwrap(), 1);
+        let retrieved: Option<TestResource> = provider.get("2").await.unwrap();
+        assert_eq!(retrieved, None);
+    }
 }
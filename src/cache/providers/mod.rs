@@ -1,8 +1,16 @@
+pub mod backend;
+pub mod codec;
 pub mod fallback;
 pub mod memory;
 #[cfg(test)]
 mod property_tests;
 pub mod redis;
+pub(crate) mod resp;
+pub mod tiered;
+
+pub use backend::CacheBackend;
+pub use codec::CacheCodec;
+pub use tiered::TieredCacheProvider;
 
 use crate::utils::api_resource::ApiResource;
 use async_trait::async_trait;
@@ -12,8 +20,8 @@ use std::pin::Pin;
 /// Cache provider interface for implementing different caching backends
 #[async_trait]
 pub trait CacheProvider: Send + Sync + 'static {
-    /// Initialize the cache provider with configuration
-    fn init(&self) -> Result<(), String>;
+    /// Initialize the cache provider with configuration, checking any connection it needs
+    async fn init(&self) -> Result<(), String>;
 
     /// Store a value in the cache
     async fn set<T: ApiResource>(
@@ -23,6 +31,15 @@ pub trait CacheProvider: Send + Sync + 'static {
         ttl_seconds: u64,
     ) -> Result<(), String>;
 
+    /// Store a value tagged with `tags` in addition to its own `cache_tags`
+    async fn set_with_tags<T: ApiResource>(
+        &self,
+        key: &str,
+        value: T,
+        ttl_seconds: u64,
+        tags: &[String],
+    ) -> Result<(), String>;
+
     /// Retrieve a value from the cache
     async fn get<T: ApiResource>(&self, key: &str) -> Result<Option<T>, String>;
 
@@ -35,6 +52,17 @@ pub trait CacheProvider: Send + Sync + 'static {
     /// Check if a key exists in the cache
     async fn exists(&self, key: &str) -> Result<bool, String>;
 
+    /// Remove every value tagged `tag`, returning how many were removed
+    async fn invalidate_tag(&self, tag: &str) -> Result<u64, String>;
+
+    /// Remove every value whose key starts with `prefix`, returning how many were removed
+    async fn invalidate_prefix(&self, prefix: &str) -> Result<u64, String>;
+
+    /// Remove every value of a resource type (keys of the form `"{resource_type}:{id}"`)
+    async fn invalidate_all(&self, resource_type: &str) -> Result<u64, String> {
+        self.invalidate_prefix(&format!("{}:", resource_type)).await
+    }
+
     /// Get cache statistics
     async fn get_stats(&self) -> Result<serde_json::Value, String>;
 }
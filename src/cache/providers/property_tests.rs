@@ -54,7 +54,7 @@ proptest! {
         };
 
         // Initialize provider
-        let _ = provider.init();
+        let _ = run_async(provider.init());
 
         // Register the resource type
         let registry = provider.registry();
@@ -88,7 +88,7 @@ proptest! {
         let provider = MemoryCacheProvider::new(100, 3600);
 
         // Initialize provider
-        let _ = provider.init();
+        let _ = run_async(provider.init());
 
         // Register the resource type
         let registry = provider.registry();
@@ -112,7 +112,7 @@ proptest! {
         let provider = MemoryCacheProvider::new(max_capacity, ttl);
 
         // Initialize provider
-        let _ = provider.init();
+        let _ = run_async(provider.init());
 
         // Get stats
         let stats_result = run_async(provider.get_stats());
@@ -0,0 +1,388 @@
+//! Minimal RESP2 (REdis Serialization Protocol) client
+//!
+//! Only the subset of the protocol needed by the Redis cache provider is
+//! implemented: commands are sent as arrays of bulk strings and replies are
+//! parsed into [`RespValue`]s.
+
+use futures::future::BoxFuture;
+use std::io;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufStream};
+use tokio::net::TcpStream;
+
+/// Longest bulk string accepted in a reply, matching Redis' default `proto-max-bulk-len`
+const MAX_BULK_LEN: i64 = 512 * 1024 * 1024;
+/// Most elements accepted in a reply array
+const MAX_ARRAY_LEN: i64 = 1024 * 1024;
+/// Most bytes or elements reserved up front; larger replies grow as they are read
+const MAX_PREALLOCATION: usize = 64 * 1024;
+
+/// A single value in the RESP2 protocol
+#[derive(Debug, Clone, PartialEq)]
+pub enum RespValue {
+    SimpleString(String),
+    Error(String),
+    Integer(i64),
+    BulkString(Option<Vec<u8>>),
+    Array(Option<Vec<RespValue>>),
+}
+
+impl RespValue {
+    /// Interpret the value as a UTF-8 string, if it is a (non-null) string reply
+    pub fn as_string(&self) -> Option<String> {
+        match self {
+            RespValue::SimpleString(s) => Some(s.clone()),
+            RespValue::BulkString(Some(bytes)) => String::from_utf8(bytes.clone()).ok(),
+            _ => None,
+        }
+    }
+
+    /// Interpret the value as an integer reply
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            RespValue::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Whether this is a null bulk string or null array
+    pub fn is_null(&self) -> bool {
+        matches!(self, RespValue::BulkString(None) | RespValue::Array(None))
+    }
+
+    /// Encode the value using the RESP2 wire format
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode_into(&mut out);
+        out
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) {
+        match self {
+            RespValue::SimpleString(s) => {
+                out.extend_from_slice(format!("+{}\r\n", s).as_bytes());
+            }
+            RespValue::Error(e) => {
+                out.extend_from_slice(format!("-{}\r\n", e).as_bytes());
+            }
+            RespValue::Integer(i) => {
+                out.extend_from_slice(format!(":{}\r\n", i).as_bytes());
+            }
+            RespValue::BulkString(None) => out.extend_from_slice(b"$-1\r\n"),
+            RespValue::BulkString(Some(bytes)) => {
+                out.extend_from_slice(format!("${}\r\n", bytes.len()).as_bytes());
+                out.extend_from_slice(bytes);
+                out.extend_from_slice(b"\r\n");
+            }
+            RespValue::Array(None) => out.extend_from_slice(b"*-1\r\n"),
+            RespValue::Array(Some(items)) => {
+                out.extend_from_slice(format!("*{}\r\n", items.len()).as_bytes());
+                for item in items {
+                    item.encode_into(out);
+                }
+            }
+        }
+    }
+}
+
+/// Encode a command as a RESP array of bulk strings
+pub fn encode_command<A: AsRef<[u8]>>(args: &[A]) -> Vec<u8> {
+    RespValue::Array(Some(
+        args.iter()
+            .map(|arg| RespValue::BulkString(Some(arg.as_ref().to_vec())))
+            .collect(),
+    ))
+    .encode()
+}
+
+/// A single buffered connection speaking RESP2
+pub struct RespConnection {
+    stream: BufStream<TcpStream>,
+}
+
+impl RespConnection {
+    /// Open a TCP connection to `addr` with the given connect timeout
+    pub async fn connect(addr: &str, timeout: Duration) -> io::Result<Self> {
+        let stream = tokio::time::timeout(timeout, TcpStream::connect(addr))
+            .await
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("Timed out connecting to {}", addr),
+                )
+            })??;
+        stream.set_nodelay(true)?;
+        Ok(Self::from_stream(stream))
+    }
+
+    /// Wrap an already connected stream
+    pub fn from_stream(stream: TcpStream) -> Self {
+        Self {
+            stream: BufStream::new(stream),
+        }
+    }
+
+    /// Send a command and wait for its reply
+    ///
+    /// Server error replies are returned as `Ok(RespValue::Error(..))` so callers
+    /// can distinguish protocol/IO failures from command failures.
+    pub async fn command<A: AsRef<[u8]>>(&mut self, args: &[A]) -> io::Result<RespValue> {
+        self.send(args).await?;
+        self.read_value().await
+    }
+
+    /// Send a command and wait for its reply, failing with `TimedOut` if the
+    /// whole exchange takes longer than `timeout`
+    ///
+    /// A connection that timed out may still receive the late reply and must be discarded.
+    pub async fn command_with_timeout<A: AsRef<[u8]>>(
+        &mut self,
+        args: &[A],
+        timeout: Duration,
+    ) -> io::Result<RespValue> {
+        tokio::time::timeout(timeout, self.command(args))
+            .await
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("No reply within {}ms", timeout.as_millis()),
+                )
+            })?
+    }
+
+    /// Send a command without waiting for a reply
+    pub async fn send<A: AsRef<[u8]>>(&mut self, args: &[A]) -> io::Result<()> {
+        self.stream.write_all(&encode_command(args)).await?;
+        self.stream.flush().await
+    }
+
+    /// Write a raw value (used by servers replying to clients)
+    pub async fn write_value(&mut self, value: &RespValue) -> io::Result<()> {
+        self.stream.write_all(&value.encode()).await?;
+        self.stream.flush().await
+    }
+
+    /// Read the next value from the connection
+    ///
+    /// Bulk strings longer than 512 MiB and arrays of more than 2^20 elements are
+    /// rejected with `InvalidData`, and memory is only reserved as data arrives,
+    /// so a corrupt or hostile length cannot force a huge allocation.
+    pub fn read_value(&mut self) -> BoxFuture<'_, io::Result<RespValue>> {
+        Box::pin(async move {
+            let line = self.read_line().await?;
+            let prefix = line.as_bytes()[0];
+            if !prefix.is_ascii() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Unexpected RESP type prefix in line: {:?}", line),
+                ));
+            }
+            let rest = &line[1..];
+
+            match prefix {
+                b'+' => Ok(RespValue::SimpleString(rest.to_string())),
+                b'-' => Ok(RespValue::Error(rest.to_string())),
+                b':' => Ok(RespValue::Integer(parse_int(rest)?)),
+                b'$' => {
+                    let len = parse_int(rest)?;
+                    if len < 0 {
+                        return Ok(RespValue::BulkString(None));
+                    }
+                    let len = check_len(len, MAX_BULK_LEN, "Bulk string")?;
+
+                    // The string and its trailing CRLF
+                    let mut buf = Vec::with_capacity((len + 2).min(MAX_PREALLOCATION));
+                    (&mut self.stream)
+                        .take(len as u64 + 2)
+                        .read_to_end(&mut buf)
+                        .await?;
+                    if buf.len() < len + 2 {
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "Connection closed in the middle of a bulk string",
+                        ));
+                    }
+                    buf.truncate(len);
+                    Ok(RespValue::BulkString(Some(buf)))
+                }
+                b'*' => {
+                    let len = parse_int(rest)?;
+                    if len < 0 {
+                        return Ok(RespValue::Array(None));
+                    }
+                    let len = check_len(len, MAX_ARRAY_LEN, "Array")?;
+                    let mut items = Vec::with_capacity(len.min(MAX_PREALLOCATION));
+                    for _ in 0..len {
+                        items.push(self.read_value().await?);
+                    }
+                    Ok(RespValue::Array(Some(items)))
+                }
+                other => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Unexpected RESP type prefix: {:?}", other as char),
+                )),
+            }
+        })
+    }
+
+    async fn read_line(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        let read = self.stream.read_line(&mut line).await?;
+        if read == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Connection closed by peer",
+            ));
+        }
+        let line = line.trim_end_matches(['\r', '\n']).to_string();
+        if line.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Empty RESP line",
+            ));
+        }
+        Ok(line)
+    }
+}
+
+/// Reject a `what` length above `max`, which only a corrupt or hostile reply sends
+fn check_len(len: i64, max: i64, what: &str) -> io::Result<usize> {
+    if len > max {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} of {} exceeds the limit of {}", what, len, max),
+        ));
+    }
+    Ok(len as usize)
+}
+
+fn parse_int(s: &str) -> io::Result<i64> {
+    s.parse::<i64>().map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid RESP integer: {}", s),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_encode_command() {
+        let encoded = encode_command(&["SET", "key", "value"]);
+        assert_eq!(
+            encoded,
+            b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_encode_null_values() {
+        assert_eq!(RespValue::BulkString(None).encode(), b"$-1\r\n".to_vec());
+        assert_eq!(RespValue::Array(None).encode(), b"*-1\r\n".to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_values() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        let values = vec![
+            RespValue::SimpleString("OK".to_string()),
+            RespValue::Error("ERR boom".to_string()),
+            RespValue::Integer(-42),
+            RespValue::BulkString(Some(b"hello\r\nworld".to_vec())),
+            RespValue::BulkString(None),
+            RespValue::Array(Some(vec![
+                RespValue::Integer(1),
+                RespValue::BulkString(Some(b"two".to_vec())),
+            ])),
+        ];
+
+        let to_send = values.clone();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut conn = RespConnection::from_stream(stream);
+            for value in &to_send {
+                conn.write_value(value).await.unwrap();
+            }
+        });
+
+        let mut client = RespConnection::connect(&addr, Duration::from_secs(1))
+            .await
+            .unwrap();
+        for expected in values {
+            assert_eq!(client.read_value().await.unwrap(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_malformed_prefix_is_an_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream.write_all("é1\r\n?2\r\n".as_bytes()).await.unwrap();
+            // Keep the connection open until the client is done
+            let _ = stream.read(&mut [0u8; 1]).await;
+        });
+
+        let mut client = RespConnection::connect(&addr, Duration::from_secs(1))
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            let error = client.read_value().await.unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oversized_lengths_are_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream
+                .write_all(b"$9999999999\r\n*9999999999\r\n")
+                .await
+                .unwrap();
+            let _ = stream.read(&mut [0u8; 1]).await;
+        });
+
+        let mut client = RespConnection::connect(&addr, Duration::from_secs(1))
+            .await
+            .unwrap();
+        for what in ["Bulk string", "Array"] {
+            let error = client.read_value().await.unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            assert!(error.to_string().starts_with(what), "{}", error);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_command_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        // Accept and read commands, but never reply
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 64];
+            while stream.read(&mut buf).await.is_ok_and(|n| n > 0) {}
+        });
+
+        let mut client = RespConnection::connect(&addr, Duration::from_secs(1))
+            .await
+            .unwrap();
+        let error = client
+            .command_with_timeout(&["PING"], Duration::from_millis(50))
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+    }
+}
@@ -0,0 +1,381 @@
+use async_trait::async_trait;
+use serde_json::json;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::warn;
+
+use super::{
+    CacheProvider,
+    memory::MemoryCacheProvider,
+    redis::{RedisCacheProvider, RedisConfig},
+};
+use crate::config::{AppConfig, TieredCacheConfig};
+use crate::core::cache::CacheRegistry;
+use crate::utils::api_resource::ApiResource;
+
+/// Lookup counters for each tier
+#[derive(Debug, Default)]
+struct TierCounters {
+    l1_hits: AtomicU64,
+    l2_hits: AtomicU64,
+    misses: AtomicU64,
+    l2_errors: AtomicU64,
+}
+
+/// Percentage of `hits` out of `lookups` (0 when there were no lookups)
+fn hit_ratio(hits: u64, lookups: u64) -> f64 {
+    if lookups == 0 {
+        0.0
+    } else {
+        (hits as f64 / lookups as f64) * 100.0
+    }
+}
+
+/// Two-tier cache: a short-lived in-process L1 in front of a shared L2
+///
+/// Reads try L1 first; an L2 hit is promoted into L1. Writes go to L2 first and
+/// then to L1, so L1 never holds a value that L2 rejected. L1 entries live for
+/// at most `l1_ttl_seconds`, which bounds how stale a replica's copy can get
+/// when another replica writes; pair it with an `InvalidationBus` evicting
+/// from [`TieredCacheProvider::l1_registry`] to drop copies immediately.
+///
+/// L1 has its own registry, separate from the application's, so its short TTL
+/// does not affect `get_or_fetch` caches.
+pub struct TieredCacheProvider<R: CacheProvider = RedisCacheProvider> {
+    l1: MemoryCacheProvider,
+    l2: Arc<R>,
+    config: TieredCacheConfig,
+    counters: Arc<TierCounters>,
+}
+
+impl TieredCacheProvider<RedisCacheProvider> {
+    /// Build the provider from `cache.tiered` and `cache.redis`
+    pub fn from_app_config(app_config: &AppConfig) -> Self {
+        Self::with_l2(
+            app_config.cache.tiered,
+            Arc::new(RedisCacheProvider::new(RedisConfig::from_app_config(
+                app_config,
+            ))),
+        )
+    }
+}
+
+impl<R: CacheProvider> TieredCacheProvider<R> {
+    /// Put a new in-process tier in front of `l2`
+    pub fn with_l2(config: TieredCacheConfig, l2: Arc<R>) -> Self {
+        Self {
+            l1: MemoryCacheProvider::new(config.l1_max_capacity, config.l1_ttl_seconds),
+            l2,
+            config,
+            counters: Arc::new(TierCounters::default()),
+        }
+    }
+
+    /// The registry holding the in-process tier
+    pub fn l1_registry(&self) -> CacheRegistry {
+        (*self.l1.registry()).clone()
+    }
+
+    /// TTL for an L1 copy of a value stored for `ttl_seconds` (0 = no expiry)
+    fn l1_ttl(&self, ttl_seconds: u64) -> u64 {
+        match ttl_seconds {
+            0 => self.config.l1_ttl_seconds,
+            ttl => ttl.min(self.config.l1_ttl_seconds),
+        }
+    }
+
+    /// Drop L1's copy of `key`; failures only mean there was nothing to drop
+    async fn evict_l1(&self, key: &str) {
+        let _ = self.l1.delete(key).await;
+    }
+
+    fn l2_failed(&self, operation: &str, error: &str) {
+        self.counters.l2_errors.fetch_add(1, Ordering::Relaxed);
+        warn!("⚠️ Tiered cache L2 {} failed: {}", operation, error);
+    }
+}
+
+#[async_trait]
+impl<R: CacheProvider> CacheProvider for TieredCacheProvider<R> {
+    async fn init(&self) -> Result<(), String> {
+        self.l1.init().await?;
+        self.l2.init().await
+    }
+
+    async fn set<T: ApiResource>(
+        &self,
+        key: &str,
+        value: T,
+        ttl_seconds: u64,
+    ) -> Result<(), String> {
+        self.set_with_tags(key, value, ttl_seconds, &[]).await
+    }
+
+    async fn set_with_tags<T: ApiResource>(
+        &self,
+        key: &str,
+        value: T,
+        ttl_seconds: u64,
+        tags: &[String],
+    ) -> Result<(), String> {
+        if let Err(e) = self
+            .l2
+            .set_with_tags(key, value.clone(), ttl_seconds, tags)
+            .await
+        {
+            self.l2_failed("write", &e);
+            // Don't keep serving the value L2 no longer agrees with
+            self.evict_l1(key).await;
+            return Err(e);
+        }
+
+        self.l1
+            .set_with_tags(key, value, self.l1_ttl(ttl_seconds), tags)
+            .await
+    }
+
+    async fn get<T: ApiResource>(&self, key: &str) -> Result<Option<T>, String> {
+        if let Some(value) = self.l1.get::<T>(key).await? {
+            self.counters.l1_hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(Some(value));
+        }
+
+        match self.l2.get::<T>(key).await {
+            Ok(Some(value)) => {
+                self.counters.l2_hits.fetch_add(1, Ordering::Relaxed);
+                // Promote into L1 so the next read stays in-process
+                self.l1
+                    .set(key, value.clone(), self.config.l1_ttl_seconds)
+                    .await?;
+                Ok(Some(value))
+            }
+            Ok(None) => {
+                self.counters.misses.fetch_add(1, Ordering::Relaxed);
+                Ok(None)
+            }
+            Err(e) => {
+                self.l2_failed("read", &e);
+                Err(e)
+            }
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        self.evict_l1(key).await;
+        self.l2.delete(key).await
+    }
+
+    async fn clear(&self) -> Result<(), String> {
+        self.l1.invalidate_prefix("").await?;
+        self.l2.clear().await
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, String> {
+        // L1 only holds copies of L2 entries
+        self.l2.exists(key).await
+    }
+
+    async fn invalidate_tag(&self, tag: &str) -> Result<u64, String> {
+        self.l1.invalidate_tag(tag).await?;
+        self.l2.invalidate_tag(tag).await
+    }
+
+    async fn invalidate_prefix(&self, prefix: &str) -> Result<u64, String> {
+        self.l1.invalidate_prefix(prefix).await?;
+        self.l2.invalidate_prefix(prefix).await
+    }
+
+    async fn invalidate_all(&self, resource_type: &str) -> Result<u64, String> {
+        self.l1.invalidate_all(resource_type).await?;
+        self.l2.invalidate_all(resource_type).await
+    }
+
+    async fn get_stats(&self) -> Result<serde_json::Value, String> {
+        let l1_hits = self.counters.l1_hits.load(Ordering::Relaxed);
+        let l2_hits = self.counters.l2_hits.load(Ordering::Relaxed);
+        let misses = self.counters.misses.load(Ordering::Relaxed);
+        let l2_lookups = l2_hits + misses;
+        let lookups = l1_hits + l2_lookups;
+
+        let l1_registry = self.l1.registry();
+        let l1_entries: u64 = l1_registry
+            .resource_types()
+            .iter()
+            .filter_map(|resource_type| l1_registry.resource_cache_ops(resource_type))
+            .map(|cache| cache.entry_count())
+            .sum();
+
+        let l2_stats = match self.l2.get_stats().await {
+            Ok(stats) => stats,
+            Err(e) => json!({ "error": e }),
+        };
+
+        Ok(json!({
+            "provider": "tiered",
+            "lookups": lookups,
+            "hit_ratio": hit_ratio(l1_hits + l2_hits, lookups),
+            "l1": {
+                "type": "memory",
+                "ttl_seconds": self.config.l1_ttl_seconds,
+                "max_capacity": self.config.l1_max_capacity,
+                "entries": l1_entries,
+                "hits": l1_hits,
+                "hit_ratio": hit_ratio(l1_hits, lookups),
+            },
+            "l2": {
+                "hits": l2_hits,
+                "misses": misses,
+                "errors": self.counters.l2_errors.load(Ordering::Relaxed),
+                // Of the lookups that reached L2
+                "hit_ratio": hit_ratio(l2_hits, l2_lookups),
+                "stats": l2_stats,
+            },
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct TestResource {
+        id: String,
+        value: i32,
+    }
+
+    impl ApiResource for TestResource {
+        type Id = String;
+
+        fn resource_type() -> &'static str {
+            "test_resource"
+        }
+
+        fn api_name() -> &'static str {
+            "TestService"
+        }
+    }
+
+    fn resource(id: &str, value: i32) -> TestResource {
+        TestResource {
+            id: id.to_string(),
+            value,
+        }
+    }
+
+    fn config() -> TieredCacheConfig {
+        TieredCacheConfig {
+            l1_ttl_seconds: 30,
+            l1_max_capacity: 100,
+        }
+    }
+
+    /// Two replicas sharing one L2
+    fn replicas() -> (
+        Arc<MemoryCacheProvider>,
+        TieredCacheProvider<MemoryCacheProvider>,
+        TieredCacheProvider<MemoryCacheProvider>,
+    ) {
+        let shared = Arc::new(MemoryCacheProvider::new(100, 3600));
+        let a = TieredCacheProvider::with_l2(config(), shared.clone());
+        let b = TieredCacheProvider::with_l2(config(), shared.clone());
+        (shared, a, b)
+    }
+
+    #[tokio::test]
+    async fn test_writes_go_through_to_both_tiers() {
+        let (shared, a, _) = replicas();
+
+        a.set("test_resource:1", resource("1", 1), 300)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            shared.get::<TestResource>("test_resource:1").await.unwrap(),
+            Some(resource("1", 1))
+        );
+        assert_eq!(
+            a.l1.get::<TestResource>("test_resource:1").await.unwrap(),
+            Some(resource("1", 1))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_l2_hits_are_promoted_into_l1() {
+        let (_, a, b) = replicas();
+        a.set("test_resource:1", resource("1", 1), 300)
+            .await
+            .unwrap();
+
+        // Replica b has nothing in L1, so the first read comes from L2
+        assert_eq!(
+            b.l1.get::<TestResource>("test_resource:1").await.unwrap(),
+            None
+        );
+        assert_eq!(
+            b.get::<TestResource>("test_resource:1").await.unwrap(),
+            Some(resource("1", 1))
+        );
+        assert_eq!(
+            b.l1.get::<TestResource>("test_resource:1").await.unwrap(),
+            Some(resource("1", 1))
+        );
+
+        // The second read is an L1 hit
+        b.get::<TestResource>("test_resource:1").await.unwrap();
+        assert_eq!(
+            b.get::<TestResource>("test_resource:2").await.unwrap(),
+            None
+        );
+
+        let stats = b.get_stats().await.unwrap();
+        assert_eq!(stats["provider"], "tiered");
+        assert_eq!(stats["lookups"], 3);
+        assert_eq!(stats["l1"]["hits"], 1);
+        assert_eq!(stats["l2"]["hits"], 1);
+        assert_eq!(stats["l2"]["misses"], 1);
+        assert_eq!(stats["l2"]["hit_ratio"], 50.0);
+    }
+
+    #[tokio::test]
+    async fn test_delete_and_invalidate_clear_both_tiers() {
+        let (shared, a, _) = replicas();
+        a.set("test_resource:1", resource("1", 1), 300)
+            .await
+            .unwrap();
+        a.set_with_tags(
+            "test_resource:2",
+            resource("2", 2),
+            300,
+            &["owner:7".to_string()],
+        )
+        .await
+        .unwrap();
+
+        a.delete("test_resource:1").await.unwrap();
+        assert_eq!(
+            a.get::<TestResource>("test_resource:1").await.unwrap(),
+            None
+        );
+        assert_eq!(
+            shared.get::<TestResource>("test_resource:1").await.unwrap(),
+            None
+        );
+
+        assert_eq!(a.invalidate_tag("owner:7").await.unwrap(), 1);
+        assert_eq!(
+            a.get::<TestResource>("test_resource:2").await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_l1_ttl_never_exceeds_configured_limit() {
+        let (_, a, _) = replicas();
+        assert_eq!(a.l1_ttl(300), 30);
+        assert_eq!(a.l1_ttl(10), 10);
+        // No expiry in L2 still expires in L1
+        assert_eq!(a.l1_ttl(0), 30);
+    }
+}
@@ -10,8 +10,12 @@ mod tests;
 
 // Re-export key components from core config
 pub use crate::core::config::app_config::{
-    AppConfig, AuthConfig, CacheConfig, EnvironmentType, LoggingConfig, ReliabilityConfig,
-    ServerConfig, load_config,
+    AppConfig, AuthConfig, AuthPolicyConfig, AuthProvider, CacheCodecConfig, CacheConfig,
+    CacheEncryptionConfig, CacheInvalidationConfig, CacheProviderType, CacheSnapshotConfig,
+    CacheWarmupConfig, ClaimPredicateConfig, CompressionAlgorithm, EnvironmentType, HealthConfig,
+    InvalidationBackend, JwksConfig, LoggingConfig, OidcConfig, OidcIssuerConfig, RedisCacheConfig,
+    ReliabilityConfig, RepositoryCacheConfig, ResourceCacheConfig, ServerConfig, TieredCacheConfig,
+    UpstreamConfig, WriteMode, load_config,
 };
 
 use lazy_static::lazy_static;
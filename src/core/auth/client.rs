@@ -18,6 +18,9 @@ struct TokenCacheEntry {
 }
 
 /// Entra token client for acquiring tokens for downstream services
+///
+/// Clones share the token cache.
+#[derive(Clone)]
 pub struct EntraTokenClient {
     /// HTTP client for making requests
     client: Client,
@@ -0,0 +1,564 @@
+//! Signing key sets (JWKS) published by identity providers
+//!
+//! A [`JwksManager`] keeps one key set usable across key rotations at the IdP:
+//! keys are cached for the `max-age` the IdP sends and refreshed in the
+//! background before they expire, a token naming an unknown key triggers one
+//! (rate limited) refetch, and the last good set keeps being served for a
+//! while if the IdP cannot be reached. Managers are shared by every auth layer
+//! using the same key set, and report their freshness to the detailed health
+//! check.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant, SystemTime};
+
+use lazy_static::lazy_static;
+use metrics::counter;
+use reqwest::Client;
+use reqwest::header::{CACHE_CONTROL, HeaderMap};
+use serde::Deserialize;
+use tracing::{debug, info, warn};
+
+use super::middleware::{AuthError, Jwk, JwksResponse};
+use crate::core::config::app_config::JwksConfig;
+use crate::models::DependencyStatus;
+
+lazy_static! {
+    /// Managers by key set, so auth layers share keys and the health check can find them
+    static ref MANAGERS: Mutex<HashMap<String, Weak<JwksManager>>> = Mutex::new(HashMap::new());
+}
+
+/// Keys are refreshed in the background once this fraction of their max-age has passed
+const REFRESH_AHEAD_FRACTION: f64 = 0.8;
+
+/// OpenID Connect discovery document, as far as we need it
+#[derive(Debug, Clone, Deserialize)]
+struct OpenIdConfiguration {
+    jwks_uri: String,
+    issuer: String,
+}
+
+/// Where a key set is published
+#[derive(Debug)]
+enum JwksSource {
+    /// A fixed JWKS URI
+    Uri(String),
+    /// The `jwks_uri` of an issuer's discovery document, found once
+    Discovery {
+        url: String,
+        issuer: String,
+        jwks_uri: Mutex<Option<String>>,
+    },
+}
+
+/// The cached keys and the bookkeeping around fetching them
+#[derive(Debug, Default)]
+struct JwksState {
+    keys: Option<JwksResponse>,
+    fetched_at: Option<SystemTime>,
+    expires_at: Option<Instant>,
+    refresh_at: Option<Instant>,
+    last_attempt: Option<Instant>,
+    last_error: Option<String>,
+}
+
+/// Caches and refreshes one identity provider's signing keys
+#[derive(Debug)]
+pub struct JwksManager {
+    source: JwksSource,
+    settings: JwksConfig,
+    client: Client,
+    state: Mutex<JwksState>,
+    /// Held while fetching, so concurrent requests share one fetch
+    refreshing: tokio::sync::Mutex<()>,
+    background_started: AtomicBool,
+}
+
+impl JwksManager {
+    /// The manager for the key set at `uri`
+    pub fn for_uri(uri: &str, settings: &JwksConfig) -> Arc<Self> {
+        Self::shared(uri.to_string(), settings, || {
+            JwksSource::Uri(uri.to_string())
+        })
+    }
+
+    /// The manager for the key set named in `issuer`'s discovery document at `discovery_url`
+    ///
+    /// The document's `issuer` must match, so one issuer cannot supply another's keys.
+    pub fn for_discovery(discovery_url: &str, issuer: &str, settings: &JwksConfig) -> Arc<Self> {
+        Self::shared(format!("{} ({})", discovery_url, issuer), settings, || {
+            JwksSource::Discovery {
+                url: discovery_url.to_string(),
+                issuer: issuer.to_string(),
+                jwks_uri: Mutex::new(None),
+            }
+        })
+    }
+
+    /// Reuse the live manager registered under `key`, or register a new one
+    ///
+    /// The settings of whichever layer created the manager first apply.
+    fn shared(
+        key: String,
+        settings: &JwksConfig,
+        source: impl FnOnce() -> JwksSource,
+    ) -> Arc<Self> {
+        let mut managers = MANAGERS.lock().unwrap();
+        managers.retain(|_, manager| manager.strong_count() > 0);
+        if let Some(manager) = managers.get(&key).and_then(Weak::upgrade) {
+            return manager;
+        }
+
+        let manager = Arc::new(Self {
+            source: source(),
+            settings: settings.clone(),
+            client: Client::new(),
+            state: Mutex::new(JwksState::default()),
+            refreshing: tokio::sync::Mutex::new(()),
+            background_started: AtomicBool::new(false),
+        });
+        managers.insert(key, Arc::downgrade(&manager));
+        manager
+    }
+
+    /// Health of every key set in use, for the detailed health check
+    pub fn health_statuses() -> Vec<DependencyStatus> {
+        let managers: Vec<_> = MANAGERS
+            .lock()
+            .unwrap()
+            .values()
+            .filter_map(Weak::upgrade)
+            .collect();
+        let mut statuses: Vec<_> = managers.iter().map(|manager| manager.health()).collect();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        statuses
+    }
+
+    /// URL the keys are found through
+    pub fn source(&self) -> &str {
+        match &self.source {
+            JwksSource::Uri(uri) => uri,
+            JwksSource::Discovery { url, .. } => url,
+        }
+    }
+
+    /// The key with ID `kid`
+    ///
+    /// Expired keys are refreshed first; if `kid` is still unknown the keys
+    /// are refetched once, unless they were fetched too recently.
+    pub(super) async fn key(self: &Arc<Self>, kid: &str) -> Result<Jwk, AuthError> {
+        if !self.is_fresh() {
+            self.refresh().await;
+        }
+        self.start_background_refresh();
+
+        if let Some(jwk) = self.find(kid)? {
+            return Ok(jwk);
+        }
+
+        // The IdP may have rotated its keys since we last looked
+        debug!(
+            "Key {} not found in JWKS from {}, refetching",
+            kid,
+            self.source()
+        );
+        self.refresh().await;
+        self.find(kid)?.ok_or_else(|| {
+            AuthError::ValidationFailed(format!("No matching key found for kid: {}", kid))
+        })
+    }
+
+    /// Look up `kid` in the cached keys, failing if there are no usable keys
+    fn find(&self, kid: &str) -> Result<Option<Jwk>, AuthError> {
+        let state = self.state.lock().unwrap();
+        let unavailable = |reason: &str| {
+            AuthError::InternalError(format!(
+                "No signing keys available from {}: {}",
+                self.source(),
+                state.last_error.as_deref().unwrap_or(reason)
+            ))
+        };
+
+        let Some(jwks) = &state.keys else {
+            return Err(unavailable("not loaded"));
+        };
+        if self.is_too_stale(&state) {
+            return Err(unavailable("keys expired"));
+        }
+        Ok(jwks.keys.iter().find(|key| key.key_id == kid).cloned())
+    }
+
+    fn is_fresh(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.keys.is_some() && state.expires_at.is_some_and(|at| at > Instant::now())
+    }
+
+    /// Whether the keys expired longer ago than stale keys may be used
+    fn is_too_stale(&self, state: &JwksState) -> bool {
+        state.expires_at.is_some_and(|at| {
+            Instant::now().saturating_duration_since(at)
+                > Duration::from_secs(self.settings.max_stale_seconds)
+        })
+    }
+
+    /// Fetch the keys, unless they were fetched within the minimum refetch interval
+    ///
+    /// A failed fetch keeps the previous keys and records the error.
+    async fn refresh(&self) {
+        let _refreshing = self.refreshing.lock().await;
+
+        {
+            let mut state = self.state.lock().unwrap();
+            let min_interval = Duration::from_secs(self.settings.min_refetch_interval_seconds);
+            if let Some(last) = state.last_attempt
+                && last.elapsed() < min_interval
+            {
+                return;
+            }
+            state.last_attempt = Some(Instant::now());
+        }
+
+        match self.fetch().await {
+            Ok((jwks, max_age)) => {
+                info!(
+                    "🔑 Fetched {} signing keys from {}, valid for {}s",
+                    jwks.keys.len(),
+                    self.source(),
+                    max_age.as_secs()
+                );
+                counter!("jwks_refreshes_total", "outcome" => "success").increment(1);
+
+                let now = Instant::now();
+                let mut state = self.state.lock().unwrap();
+                state.keys = Some(jwks);
+                state.fetched_at = Some(SystemTime::now());
+                state.expires_at = Some(now + max_age);
+                state.refresh_at = Some(now + max_age.mul_f64(REFRESH_AHEAD_FRACTION));
+                state.last_error = None;
+            }
+            Err(error) => {
+                warn!(
+                    "❌ Failed to refresh signing keys from {}: {}",
+                    self.source(),
+                    error
+                );
+                counter!("jwks_refreshes_total", "outcome" => "failure").increment(1);
+                self.state.lock().unwrap().last_error = Some(error);
+            }
+        }
+    }
+
+    /// Fetch the key set and how long it may be cached
+    async fn fetch(&self) -> Result<(JwksResponse, Duration), String> {
+        let uri = self.jwks_uri().await?;
+        let response = self
+            .client
+            .get(&uri)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch JWKS: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "Failed to fetch JWKS, status: {}",
+                response.status()
+            ));
+        }
+
+        let max_age = max_age(response.headers())
+            .unwrap_or(Duration::from_secs(self.settings.default_max_age_seconds));
+        let jwks = response
+            .json::<JwksResponse>()
+            .await
+            .map_err(|e| format!("Failed to parse JWKS: {}", e))?;
+        Ok((jwks, max_age))
+    }
+
+    /// The JWKS URI, looking it up in the discovery document the first time
+    async fn jwks_uri(&self) -> Result<String, String> {
+        let (url, issuer, jwks_uri) = match &self.source {
+            JwksSource::Uri(uri) => return Ok(uri.clone()),
+            JwksSource::Discovery {
+                url,
+                issuer,
+                jwks_uri,
+            } => (url, issuer, jwks_uri),
+        };
+        if let Some(uri) = jwks_uri.lock().unwrap().clone() {
+            return Ok(uri);
+        }
+
+        debug!("Discovering OpenID configuration at {}", url);
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch OpenID configuration: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "Failed to fetch OpenID configuration, status: {}",
+                response.status()
+            ));
+        }
+        let discovery = response
+            .json::<OpenIdConfiguration>()
+            .await
+            .map_err(|e| format!("Failed to parse OpenID configuration: {}", e))?;
+
+        // A discovery document for another issuer must not supply our keys
+        if &discovery.issuer != issuer {
+            return Err(format!(
+                "OpenID configuration at {} is for issuer {}, expected {}",
+                url, discovery.issuer, issuer
+            ));
+        }
+
+        *jwks_uri.lock().unwrap() = Some(discovery.jwks_uri.clone());
+        Ok(discovery.jwks_uri)
+    }
+
+    /// Start refreshing the keys ahead of their expiry, once
+    ///
+    /// The task stops when the last auth layer using this manager is dropped.
+    fn start_background_refresh(self: &Arc<Self>) {
+        if !self.settings.background_refresh || self.background_started.swap(true, Ordering::SeqCst)
+        {
+            return;
+        }
+
+        let manager = Arc::downgrade(self);
+        tokio::spawn(async move {
+            while let Some(wait) = manager.upgrade().map(|m| m.next_refresh_in()) {
+                tokio::time::sleep(wait).await;
+                let Some(manager) = manager.upgrade() else {
+                    break;
+                };
+                manager.refresh().await;
+            }
+        });
+    }
+
+    /// How long the background task waits before its next refresh
+    fn next_refresh_in(&self) -> Duration {
+        let state = self.state.lock().unwrap();
+        let min_interval = Duration::from_secs(self.settings.min_refetch_interval_seconds);
+        let now = Instant::now();
+
+        // Retry failures, and keys we have not managed to load, after the minimum interval
+        let due = match (state.refresh_at, &state.last_error) {
+            (Some(at), None) => at,
+            _ => now + min_interval,
+        };
+        let allowed = state.last_attempt.map_or(now, |last| last + min_interval);
+        due.max(allowed)
+            .saturating_duration_since(now)
+            .max(Duration::from_millis(100))
+    }
+
+    /// Freshness of the keys as a health check dependency
+    pub fn health(&self) -> DependencyStatus {
+        let state = self.state.lock().unwrap();
+        let mut details = BTreeMap::new();
+        details.insert("source".to_string(), self.source().to_string());
+
+        let status = match (&state.keys, &state.last_error) {
+            (None, None) => "not loaded".to_string(),
+            (None, Some(error)) => format!("down ({})", error),
+            (Some(_), _) if self.is_too_stale(&state) => "down (keys expired)".to_string(),
+            (Some(_), _) if state.expires_at.is_some_and(|at| at <= Instant::now()) => {
+                "degraded (serving expired keys)".to_string()
+            }
+            (Some(_), _) => "up".to_string(),
+        };
+
+        if let Some(jwks) = &state.keys {
+            details.insert("keys".to_string(), jwks.keys.len().to_string());
+        }
+        if let Some(fetched_at) = state.fetched_at {
+            details.insert(
+                "fetched_at".to_string(),
+                chrono::DateTime::<chrono::Utc>::from(fetched_at).to_rfc3339(),
+            );
+        }
+        if let Some(expires_at) = state.expires_at {
+            let now = Instant::now();
+            let expires_in = if expires_at > now {
+                (expires_at - now).as_secs() as i64
+            } else {
+                -((now - expires_at).as_secs() as i64)
+            };
+            details.insert("expires_in_seconds".to_string(), expires_in.to_string());
+        }
+        if let Some(error) = &state.last_error {
+            details.insert("last_error".to_string(), error.clone());
+        }
+
+        DependencyStatus {
+            name: format!("JWKS ({})", self.source()),
+            status,
+            details: Some(details),
+        }
+    }
+}
+
+/// The `max-age` of a `Cache-Control` header
+fn max_age(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(CACHE_CONTROL)?
+        .to_str()
+        .ok()?
+        .split(',')
+        .find_map(|directive| directive.trim().strip_prefix("max-age="))
+        .and_then(|seconds| seconds.trim().parse().ok())
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::auth::test_support::{ec_p256_jwk, rsa_jwk};
+    use reqwest::header::HeaderValue;
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn settings(min_refetch_interval_seconds: u64) -> JwksConfig {
+        JwksConfig {
+            min_refetch_interval_seconds,
+            background_refresh: false,
+            ..Default::default()
+        }
+    }
+
+    fn jwks(keys: Vec<serde_json::Value>, max_age: u64) -> ResponseTemplate {
+        ResponseTemplate::new(200)
+            .insert_header(
+                "Cache-Control",
+                format!("public, max-age={}", max_age).as_str(),
+            )
+            .set_body_json(json!({ "keys": keys }))
+    }
+
+    async fn requests(server: &MockServer) -> usize {
+        server.received_requests().await.unwrap().len()
+    }
+
+    #[test]
+    fn test_max_age_from_cache_control() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(max_age(&headers), None);
+
+        headers.insert(
+            CACHE_CONTROL,
+            HeaderValue::from_static("public, max-age=600, must-revalidate"),
+        );
+        assert_eq!(max_age(&headers), Some(Duration::from_secs(600)));
+
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        assert_eq!(max_age(&headers), None);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_kid_triggers_one_rate_limited_refetch() {
+        let server = MockServer::start().await;
+        // The IdP rotates in a new key after the first fetch
+        Mock::given(method("GET"))
+            .and(path("/certs"))
+            .respond_with(jwks(vec![rsa_jwk()], 600))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/certs"))
+            .respond_with(jwks(vec![rsa_jwk(), ec_p256_jwk()], 600))
+            .mount(&server)
+            .await;
+
+        let uri = format!("{}/certs", server.uri());
+        let manager = JwksManager::for_uri(&uri, &settings(0));
+        assert!(Arc::ptr_eq(
+            &manager,
+            &JwksManager::for_uri(&uri, &settings(0))
+        ));
+
+        assert_eq!(manager.key("test-rsa").await.unwrap().key_id, "test-rsa");
+        assert_eq!(manager.key("test-rsa").await.unwrap().key_id, "test-rsa");
+        assert_eq!(requests(&server).await, 1);
+
+        // The rotated key is picked up by a refetch
+        assert_eq!(manager.key("test-p256").await.unwrap().key_id, "test-p256");
+        assert_eq!(requests(&server).await, 2);
+
+        // Within the minimum interval an unknown kid does not reach the IdP
+        let limited =
+            JwksManager::for_uri(&format!("{}/certs?limited", server.uri()), &settings(60));
+        limited.key("test-rsa").await.unwrap();
+        assert!(matches!(
+            limited.key("unknown").await,
+            Err(AuthError::ValidationFailed(_))
+        ));
+        assert_eq!(requests(&server).await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_serves_last_good_keys_while_the_idp_is_down() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/certs"))
+            .respond_with(jwks(vec![rsa_jwk()], 0))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/certs"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let manager = JwksManager::for_uri(&format!("{}/certs", server.uri()), &settings(0));
+        assert_eq!(manager.health().status, "not loaded");
+
+        manager.key("test-rsa").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        // The keys have expired and cannot be refreshed, but remain usable
+        assert_eq!(manager.key("test-rsa").await.unwrap().key_id, "test-rsa");
+        let health = manager.health();
+        assert!(health.status.starts_with("degraded"));
+        assert!(health.details.unwrap()["last_error"].contains("503"));
+
+        // With no keys to fall back on, tokens cannot be validated
+        let unloaded =
+            JwksManager::for_uri(&format!("{}/certs?unloaded", server.uri()), &settings(0));
+        assert!(matches!(
+            unloaded.key("test-rsa").await,
+            Err(AuthError::InternalError(_))
+        ));
+        assert!(unloaded.health().status.starts_with("down"));
+    }
+
+    #[tokio::test]
+    async fn test_keys_are_refreshed_in_the_background() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/certs"))
+            .respond_with(jwks(vec![rsa_jwk()], 1))
+            .mount(&server)
+            .await;
+
+        let manager = JwksManager::for_uri(
+            &format!("{}/certs", server.uri()),
+            &JwksConfig {
+                background_refresh: true,
+                ..settings(0)
+            },
+        );
+        manager.key("test-rsa").await.unwrap();
+        assert_eq!(requests(&server).await, 1);
+
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(requests(&server).await >= 2);
+        assert_eq!(manager.health().status, "up");
+    }
+}
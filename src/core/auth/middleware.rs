@@ -1,38 +1,46 @@
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use std::task::{Context, Poll};
-use std::time::{Duration, SystemTime};
 
 use axum::{
     body::Body,
-    extract::Request,
+    extract::{OriginalUri, Request},
     http::{HeaderMap, HeaderValue, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
 };
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use config::ConfigError;
 use futures::future::BoxFuture;
-use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header};
+use jsonwebtoken::{Algorithm, DecodingKey, Header, Validation, decode, decode_header};
+use metrics::counter;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use tower::{Layer, Service};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use crate::core::config::app_config;
-use crate::core::config::app_config::AppConfig;
+use crate::core::config::app_config::{AppConfig, AuthProvider, JwksConfig};
 use crate::core::config::constants;
+use crate::core::error::AppError;
 use crate::core::router::AppState;
 
+use super::jwks::JwksManager;
+use super::oidc::OidcValidator;
+use super::policy::{PolicyDecision, PolicyEngine};
+
 /// JWKS (JSON Web Key Set) response
 #[derive(Debug, Clone, Deserialize)]
-struct JwksResponse {
-    keys: Vec<Jwk>,
+pub(super) struct JwksResponse {
+    pub(super) keys: Vec<Jwk>,
 }
 
 /// JSON Web Key
 #[derive(Debug, Clone, Deserialize)]
-struct Jwk {
+pub(super) struct Jwk {
     #[serde(rename = "kid")]
-    key_id: String,
+    pub(super) key_id: String,
     #[serde(rename = "x5c")]
     x509_chain: Option<Vec<String>>,
     #[serde(rename = "n")]
@@ -40,13 +48,13 @@ struct Jwk {
     #[serde(rename = "e")]
     exponent: Option<String>,
     kty: String,
-}
-
-/// JWKS cache entry
-#[derive(Debug, Clone)]
-pub struct JwksCacheEntry {
-    jwks: JwksResponse,
-    expires_at: SystemTime,
+    /// Algorithm the key is meant for, if the issuer says
+    alg: Option<String>,
+    /// Curve of an EC or OKP key
+    crv: Option<String>,
+    /// Public point of an EC or OKP key
+    x: Option<String>,
+    y: Option<String>,
 }
 
 /// Claims from the JWT token we validate
@@ -75,6 +83,9 @@ pub struct EntraClaims {
     /// Scope in access token (can be string or array)
     #[serde(default)]
     pub scp: Option<String>,
+    /// Tenant ID of the caller
+    #[serde(default)]
+    pub tid: Option<String>,
 }
 
 impl EntraClaims {
@@ -135,21 +146,19 @@ pub struct EntraAuthConfig {
     pub client: Client,
     /// JWKS URI for token validation
     pub jwks_uri: String,
-    /// JWKS cache
-    pub jwks_cache: Arc<Mutex<Option<JwksCacheEntry>>>,
+    /// Entra signing keys, shared with every other layer using the same JWKS URI;
+    /// `None` when tokens come from OpenID Connect issuers instead
+    pub jwks: Option<Arc<JwksManager>>,
     /// Debug mode - skips signature validation
     pub debug_validation: bool,
     /// Default issuer URL formats
     pub issuer_url_formats: Vec<String>,
-}
-
-/// OpenID Connect configuration response
-#[derive(Debug, Clone, Deserialize)]
-struct OpenIdConfiguration {
-    #[serde(rename = "jwks_uri")]
-    jwks_uri: String,
-    #[serde(rename = "issuer")]
-    issuer: String,
+    /// Trusted OpenID Connect issuers; when set, tokens are validated against these instead of Entra
+    pub oidc: Option<Arc<OidcValidator>>,
+    /// Signing algorithms accepted in tokens
+    pub allowed_algorithms: Vec<Algorithm>,
+    /// Per-route authorization policies, checked after roles and permissions
+    pub policies: Option<Arc<PolicyEngine>>,
 }
 
 impl Default for EntraAuthConfig {
@@ -193,10 +202,13 @@ impl Default for EntraAuthConfig {
             required_roles: RoleRequirement::None,
             required_permissions: PermissionRequirement::None,
             client: Client::new(),
+            jwks: Some(JwksManager::for_uri(&jwks_uri, &JwksConfig::default())),
             jwks_uri,
-            jwks_cache: Arc::new(Mutex::new(None)),
             debug_validation,
             issuer_url_formats,
+            oidc: None,
+            allowed_algorithms: app_config::default_allowed_algorithms(),
+            policies: None,
         }
     }
 }
@@ -232,15 +244,20 @@ impl EntraAuthConfig {
             required_roles: RoleRequirement::None,
             required_permissions: PermissionRequirement::None,
             client: Client::new(),
+            jwks: Some(JwksManager::for_uri(&jwks_uri, &JwksConfig::default())),
             jwks_uri,
-            jwks_cache: Arc::new(Mutex::new(None)),
             debug_validation,
             issuer_url_formats,
+            oidc: None,
+            allowed_algorithms: app_config::default_allowed_algorithms(),
+            policies: None,
         }
     }
 
     /// Create a new EntraAuthConfig from AppConfig
-    pub fn from_app_config(config: &AppConfig) -> Self {
+    ///
+    /// Fails if the authorization policies are malformed.
+    pub fn from_app_config(config: &AppConfig) -> Result<Self, AppError> {
         let tenant_id = config.auth.entra.tenant_id.clone();
         let client_id = config.auth.entra.client_id.clone();
         let debug_validation = config.auth.debug;
@@ -270,7 +287,29 @@ impl EntraAuthConfig {
         // Get issuer URL formats from config
         let issuer_url_formats = config.auth.entra.issuer_url_formats.clone();
 
-        Self {
+        // Use the configured OpenID Connect issuers instead of Entra if selected; only
+        // Entra needs its key set, which would otherwise show up unloaded in health checks
+        let (oidc, jwks) = match config.auth.provider {
+            AuthProvider::Oidc => (
+                Some(Arc::new(OidcValidator::from_config(&config.auth))),
+                None,
+            ),
+            AuthProvider::Entra => (
+                None,
+                Some(JwksManager::for_uri(&jwks_uri, &config.auth.jwks)),
+            ),
+        };
+
+        // Check the built-in and configured per-route policies; `load_config` has
+        // already rejected malformed ones, so only hand-built configs can fail here
+        let policies = PolicyEngine::new(config.auth.effective_policies()).map_err(|e| {
+            AppError::ConfigError(ConfigError::Message(format!(
+                "Invalid auth.policies: {}",
+                e
+            )))
+        })?;
+
+        Ok(Self {
             tenant_id: tenant_id.clone(),
             client_id,
             audience,
@@ -278,11 +317,14 @@ impl EntraAuthConfig {
             required_roles: RoleRequirement::None,
             required_permissions: PermissionRequirement::None,
             client: Client::new(),
+            jwks,
             jwks_uri,
-            jwks_cache: Arc::new(Mutex::new(None)),
             debug_validation,
             issuer_url_formats,
-        }
+            oidc,
+            allowed_algorithms: config.auth.allowed_algorithms.clone(),
+            policies: Some(Arc::new(policies)),
+        })
     }
 
     /// Build on existing config to modify specific fields
@@ -394,63 +436,129 @@ fn extract_token(headers: &HeaderMap) -> Result<String, AuthError> {
     Ok(token)
 }
 
-/// Fetch and cache JWKS from the Microsoft endpoint
-async fn fetch_jwks(config: &EntraAuthConfig) -> Result<JwksResponse, AuthError> {
-    // Check if we have a cached JWKS that's still valid
-    {
-        let cache = config.jwks_cache.lock().unwrap();
-        if let Some(cache_entry) = &*cache {
-            if cache_entry.expires_at > SystemTime::now() {
-                return Ok(cache_entry.jwks.clone());
-            }
-        }
+/// Decode the JSON of a token's header (part 0) or claims (part 1) without verifying it
+pub(super) fn decode_token_part(token: &str, part: usize) -> Option<serde_json::Value> {
+    token
+        .split('.')
+        .nth(part)
+        .and_then(|part| URL_SAFE_NO_PAD.decode(part.trim_end_matches('=')).ok())
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+}
+
+/// Decode the token header, rejecting unsigned tokens explicitly
+pub(super) fn decode_token_header(token: &str) -> Result<Header, AuthError> {
+    // `alg: none` is not an `Algorithm`, so spot it before the header is parsed
+    let unsigned = decode_token_part(token, 0)
+        .and_then(|header| {
+            header
+                .get("alg")?
+                .as_str()
+                .map(|alg| alg.eq_ignore_ascii_case("none"))
+        })
+        .unwrap_or(false);
+    if unsigned {
+        return Err(AuthError::ValidationFailed(
+            "Unsigned tokens (alg: none) are not accepted".to_string(),
+        ));
     }
 
-    // If not, fetch a new JWKS
-    let response = config
-        .client
-        .get(&config.jwks_uri)
-        .send()
-        .await
-        .map_err(|e| AuthError::InternalError(format!("Failed to fetch JWKS: {}", e)))?;
-
-    if !response.status().is_success() {
-        return Err(AuthError::InternalError(format!(
-            "Failed to fetch JWKS, status: {}",
-            response.status()
+    decode_header(token)
+        .map_err(|e| AuthError::ValidationFailed(format!("Failed to decode token header: {}", e)))
+}
+
+/// Check the token's signing algorithm against the allow-list
+///
+/// HMAC algorithms are always rejected: our keys are public, so accepting
+/// them would let anyone holding a public key sign tokens with it.
+pub(super) fn check_algorithm(alg: Algorithm, allowed: &[Algorithm]) -> Result<(), AuthError> {
+    if matches!(alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+        return Err(AuthError::ValidationFailed(format!(
+            "Symmetric algorithm {:?} is not accepted",
+            alg
         )));
     }
+    if !allowed.contains(&alg) {
+        return Err(AuthError::ValidationFailed(format!(
+            "Algorithm {:?} is not allowed",
+            alg
+        )));
+    }
+    Ok(())
+}
 
-    let jwks = response
-        .json::<JwksResponse>()
-        .await
-        .map_err(|e| AuthError::InternalError(format!("Failed to parse JWKS: {}", e)))?;
-
-    // Cache the JWKS for 1 hour
-    let expires_at = SystemTime::now() + Duration::from_secs(3600);
+/// Create a decoding key from a JWK for verifying a token signed with `alg`
+///
+/// The key type (and curve) must match the algorithm, as must the key's own
+/// `alg` if it has one.
+pub(super) fn create_decoding_key(jwk: &Jwk, alg: Algorithm) -> Result<DecodingKey, AuthError> {
+    if let Some(key_alg) = &jwk.alg
+        && key_alg != &format!("{:?}", alg)
     {
-        let mut cache = config.jwks_cache.lock().unwrap();
-        *cache = Some(JwksCacheEntry {
-            jwks: jwks.clone(),
-            expires_at,
-        });
+        return Err(AuthError::ValidationFailed(format!(
+            "Key {} is for {}, but the token is signed with {:?}",
+            jwk.key_id, key_alg, alg
+        )));
     }
 
-    Ok(jwks)
-}
+    let expected_kty = match alg {
+        Algorithm::RS256
+        | Algorithm::RS384
+        | Algorithm::RS512
+        | Algorithm::PS256
+        | Algorithm::PS384
+        | Algorithm::PS512 => "RSA",
+        Algorithm::ES256 | Algorithm::ES384 => "EC",
+        Algorithm::EdDSA => "OKP",
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+            return Err(AuthError::ValidationFailed(format!(
+                "Symmetric algorithm {:?} is not accepted",
+                alg
+            )));
+        }
+    };
+    if jwk.kty != expected_kty {
+        return Err(AuthError::ValidationFailed(format!(
+            "Key {} has type {}, which cannot verify {:?}",
+            jwk.key_id, jwk.kty, alg
+        )));
+    }
 
-/// Find a JWK by its key ID
-fn find_jwk<'a>(jwks: &'a JwksResponse, kid: &str) -> Result<&'a Jwk, AuthError> {
-    jwks.keys
-        .iter()
-        .find(|key| key.key_id == kid)
-        .ok_or_else(|| {
-            AuthError::ValidationFailed(format!("No matching key found for kid: {}", kid))
-        })
+    match alg {
+        Algorithm::ES256 | Algorithm::ES384 | Algorithm::EdDSA => {
+            let expected_crv = match alg {
+                Algorithm::ES256 => "P-256",
+                Algorithm::ES384 => "P-384",
+                _ => "Ed25519",
+            };
+            if jwk.crv.as_deref() != Some(expected_crv) {
+                return Err(AuthError::ValidationFailed(format!(
+                    "Key {} is on curve {}, but {:?} needs {}",
+                    jwk.key_id,
+                    jwk.crv.as_deref().unwrap_or("(none)"),
+                    alg,
+                    expected_crv
+                )));
+            }
+
+            let key = match (alg, &jwk.x, &jwk.y) {
+                (Algorithm::EdDSA, Some(x), _) => DecodingKey::from_ed_components(x),
+                (_, Some(x), Some(y)) => DecodingKey::from_ec_components(x, y),
+                _ => {
+                    return Err(AuthError::ValidationFailed(
+                        "JWK doesn't contain necessary key material".to_string(),
+                    ));
+                }
+            };
+            key.map_err(|e| {
+                AuthError::ValidationFailed(format!("Failed to create key from components: {}", e))
+            })
+        }
+        _ => create_rsa_decoding_key(jwk),
+    }
 }
 
-/// Create a decoding key from a JWK
-fn create_decoding_key(jwk: &Jwk) -> Result<DecodingKey, AuthError> {
+/// Create an RSA decoding key from a JWK's certificate or modulus and exponent
+fn create_rsa_decoding_key(jwk: &Jwk) -> Result<DecodingKey, AuthError> {
     // First try X.509 certificate chain
     if let Some(x509_chain) = &jwk.x509_chain {
         if let Some(cert) = x509_chain.first() {
@@ -479,6 +587,24 @@ fn create_decoding_key(jwk: &Jwk) -> Result<DecodingKey, AuthError> {
     ))
 }
 
+/// Describe why a token failed validation
+pub(super) fn validation_error(e: jsonwebtoken::errors::Error, audience: &str) -> AuthError {
+    let detail = match e.kind() {
+        jsonwebtoken::errors::ErrorKind::InvalidToken => "Token format is invalid",
+        jsonwebtoken::errors::ErrorKind::InvalidSignature => "Invalid signature",
+        jsonwebtoken::errors::ErrorKind::Base64(_) => {
+            "Base64 decoding error - token may be malformed or corrupted"
+        }
+        jsonwebtoken::errors::ErrorKind::ExpiredSignature => "Token has expired",
+        jsonwebtoken::errors::ErrorKind::InvalidAudience => {
+            &format!("Invalid audience, expected: {}", audience)
+        }
+        jsonwebtoken::errors::ErrorKind::InvalidIssuer => "Invalid issuer",
+        _ => "Token validation failed",
+    };
+    AuthError::ValidationFailed(format!("{}: {}", detail, e))
+}
+
 /// Validate claims in the token
 fn validate_claims(claims: &EntraClaims, config: &EntraAuthConfig) -> Result<(), AuthError> {
     // Authorization based on roles
@@ -575,11 +701,11 @@ impl EntraAuthLayer {
         }
     }
 
-    /// Create a new EntraAuthLayer from AppConfig
-    pub fn from_app_config(config: &AppConfig) -> Self {
-        Self {
-            config: EntraAuthConfig::from_app_config(config),
-        }
+    /// Create a new EntraAuthLayer from AppConfig, failing on malformed policies
+    pub fn from_app_config(config: &AppConfig) -> Result<Self, AppError> {
+        Ok(Self {
+            config: EntraAuthConfig::from_app_config(config)?,
+        })
     }
 
     /// Create a new EntraAuthLayer with custom configuration
@@ -618,32 +744,43 @@ impl EntraAuthLayer {
     }
 
     /// Create a new auth layer from app config with added role requirements
-    pub fn from_app_config_with_roles(config: &AppConfig, roles: RoleRequirement) -> Self {
-        Self::new(EntraAuthConfig::from_app_config(config).with_role_requirement(roles))
+    pub fn from_app_config_with_roles(
+        config: &AppConfig,
+        roles: RoleRequirement,
+    ) -> Result<Self, AppError> {
+        Ok(Self::new(
+            EntraAuthConfig::from_app_config(config)?.with_role_requirement(roles),
+        ))
     }
 
     /// Create a new auth layer from app config requiring any of the given roles
-    pub fn from_app_config_require_any_role(config: &AppConfig, roles: Vec<String>) -> Self {
+    pub fn from_app_config_require_any_role(
+        config: &AppConfig,
+        roles: Vec<String>,
+    ) -> Result<Self, AppError> {
         Self::from_app_config_with_roles(config, RoleRequirement::Any(roles))
     }
 
     /// Create a new auth layer from app config requiring all of the specified roles
-    pub fn from_app_config_require_all_roles(config: &AppConfig, roles: Vec<String>) -> Self {
+    pub fn from_app_config_require_all_roles(
+        config: &AppConfig,
+        roles: Vec<String>,
+    ) -> Result<Self, AppError> {
         Self::from_app_config_with_roles(config, RoleRequirement::All(roles))
     }
 
     /// Create a new auth layer from app config requiring any of the admin roles
-    pub fn from_app_config_require_admin_role(config: &AppConfig) -> Self {
+    pub fn from_app_config_require_admin_role(config: &AppConfig) -> Result<Self, AppError> {
         Self::from_app_config_require_any_role(config, config.auth.entra.admin_roles.clone())
     }
 
     /// Create a new auth layer from app config requiring any of the read-only roles
-    pub fn from_app_config_require_read_only_role(config: &AppConfig) -> Self {
+    pub fn from_app_config_require_read_only_role(config: &AppConfig) -> Result<Self, AppError> {
         Self::from_app_config_require_any_role(config, config.auth.entra.read_only_roles.clone())
     }
 
     /// Create a new auth layer from app config requiring any of the full access roles
-    pub fn from_app_config_require_full_access_role(config: &AppConfig) -> Self {
+    pub fn from_app_config_require_full_access_role(config: &AppConfig) -> Result<Self, AppError> {
         Self::from_app_config_require_any_role(config, config.auth.entra.full_access_roles.clone())
     }
 }
@@ -703,6 +840,66 @@ where
     }
 }
 
+/// Check a validated token's claims against the required roles, permissions and policies
+fn authorize(
+    req: &Request,
+    claims: &EntraClaims,
+    token: &str,
+    config: &EntraAuthConfig,
+) -> Result<(), AuthError> {
+    // Role-based authorization check
+    validate_claims(claims, config)?;
+
+    // Permission-based authorization check
+    validate_permissions(claims, config)?;
+
+    // Policy-based authorization check
+    let Some(policies) = &config.policies else {
+        return Ok(());
+    };
+
+    // Policies are written against the full path, before any nesting stripped it
+    let path = req
+        .extensions()
+        .get::<OriginalUri>()
+        .map_or(req.uri().path(), |uri| uri.path());
+    let method = req.method();
+
+    // The signature has been verified, so the payload holds the token's claims
+    let raw_claims = decode_token_part(token, 1).unwrap_or_default();
+
+    match policies.evaluate(method, path, claims, &raw_claims) {
+        PolicyDecision::NoMatch => {
+            debug!("No policy covers {} {}", method, path);
+            Ok(())
+        }
+        PolicyDecision::Allow(names) => {
+            info!(
+                "✅ Policy {} allowed {} {} for {}",
+                names.join(", "),
+                method,
+                path,
+                claims.sub
+            );
+            for name in names {
+                counter!("auth_policy_decisions_total", "policy" => name, "decision" => "allow")
+                    .increment(1);
+            }
+            Ok(())
+        }
+        PolicyDecision::Deny { policy, reason } => {
+            warn!(
+                "🚫 Policy {} denied {} {} for {}: {}",
+                policy, method, path, claims.sub, reason
+            );
+            let message = format!("Access denied by policy '{}': {}", policy, reason);
+            counter!("auth_policy_decisions_total", "policy" => policy, "decision" => "deny")
+                .increment(1);
+            Err(AuthError::AccessDenied(message))
+        }
+    }
+}
+
 /// Wrapper function to validate token without using Next
 async fn validate_token_wrapper(
     mut req: Request,
@@ -712,9 +909,7 @@ async fn validate_token_wrapper(
     let headers = req.headers();
     let token = extract_token(headers)?;
 
-    let header = decode_header(&token).map_err(|e| {
-        AuthError::ValidationFailed(format!("Failed to decode token header: {}", e))
-    })?;
+    let header = decode_token_header(&token)?;
 
     if config.debug_validation {
         // In debug mode, let's completely bypass JWT validation
@@ -731,6 +926,7 @@ async fn validate_token_wrapper(
             appid: Some("debug_app_id".to_string()),
             app_id_uri: Some("debug_app_id_uri".to_string()),
             scp: Some("api-access".to_string()),
+            tid: Some("debug_tenant_id".to_string()),
         };
 
         req.extensions_mut().insert(claims);
@@ -742,24 +938,35 @@ async fn validate_token_wrapper(
         return Ok(req);
     }
 
+    // Tokens from OpenID Connect issuers are verified against their own keys
+    if let Some(oidc) = &config.oidc {
+        let claims = oidc.validate(&token).await?;
+        authorize(&req, &claims, &token, config)?;
+        req.extensions_mut().insert(claims);
+        return Ok(req);
+    }
+
     // Full validation with signature verification
 
+    // Only accept the algorithms we allow
+    check_algorithm(header.alg, &config.allowed_algorithms)?;
+
     // Get the key ID
     let kid = header.kid.ok_or_else(|| {
         AuthError::ValidationFailed("Token header missing 'kid' claim".to_string())
     })?;
 
-    // Fetch JWKS (JSON Web Key Set) from Microsoft
-    let jwks = fetch_jwks(config).await?;
-
-    // Find the key matching our token's kid
-    let jwk = find_jwk(&jwks, &kid)?;
+    // Find the key matching our token's kid in Microsoft's JWKS (JSON Web Key Set)
+    let jwks = config.jwks.as_ref().ok_or_else(|| {
+        AuthError::InternalError("No Entra signing keys are configured".to_string())
+    })?;
+    let jwk = jwks.key(&kid).await?;
 
-    // Create a decoding key
-    let decoding_key = create_decoding_key(jwk)?;
+    // Create a decoding key made for the token's algorithm
+    let decoding_key = create_decoding_key(&jwk, header.alg)?;
 
     // Set up validation
-    let mut validation = Validation::new(Algorithm::RS256);
+    let mut validation = Validation::new(header.alg);
     validation.validate_exp = true;
     validation.set_audience(&[&config.audience]);
     validation.set_required_spec_claims(&["exp", "iss", "sub", "aud"]);
@@ -772,31 +979,11 @@ async fn validate_token_wrapper(
     validation.set_issuer(&issuers);
 
     // Validate token with better error handling
-    let token_data = match decode::<EntraClaims>(&token, &decoding_key, &validation) {
-        Ok(data) => data,
-        Err(e) => {
-            let detail = match e.kind() {
-                jsonwebtoken::errors::ErrorKind::InvalidToken => "Token format is invalid",
-                jsonwebtoken::errors::ErrorKind::InvalidSignature => "Invalid signature",
-                jsonwebtoken::errors::ErrorKind::Base64(_) => {
-                    "Base64 decoding error - token may be malformed or corrupted"
-                }
-                jsonwebtoken::errors::ErrorKind::ExpiredSignature => "Token has expired",
-                jsonwebtoken::errors::ErrorKind::InvalidAudience => {
-                    &format!("Invalid audience, expected: {}", config.audience)
-                }
-                jsonwebtoken::errors::ErrorKind::InvalidIssuer => "Invalid issuer",
-                _ => "Token validation failed",
-            };
-            return Err(AuthError::ValidationFailed(format!("{}: {}", detail, e)));
-        }
-    };
+    let token_data = decode::<EntraClaims>(&token, &decoding_key, &validation)
+        .map_err(|e| validation_error(e, &config.audience))?;
 
-    // Role-based authorization check
-    validate_claims(&token_data.claims, config)?;
-
-    // Permission-based authorization check
-    validate_permissions(&token_data.claims, config)?;
+    // Role, permission and policy checks
+    authorize(&req, &token_data.claims, &token, config)?;
 
     // Store claims in request extensions for handlers to access
     req.extensions_mut().insert(token_data.claims);
@@ -847,7 +1034,7 @@ mod tests {
         app_config.auth.entra.client_id = "test-client".to_string();
         app_config.auth.entra.audience = "test-audience".to_string();
 
-        let config = EntraAuthConfig::from_app_config(&app_config);
+        let config = EntraAuthConfig::from_app_config(&app_config).unwrap();
 
         // Verify configuration was applied
         assert_eq!(config.tenant_id, "test-tenant");
@@ -943,6 +1130,7 @@ mod tests {
             appid: Some("test-app-id".to_string()),
             app_id_uri: Some("test-app-uri".to_string()),
             scp: Some("read write".to_string()),
+            tid: None,
         };
 
         // Test with no role requirements
@@ -996,6 +1184,7 @@ mod tests {
             appid: Some("test-app-id".to_string()),
             app_id_uri: Some("test-app-uri".to_string()),
             scp: Some("read write".to_string()),
+            tid: None,
         };
 
         // Test with no permission requirements
@@ -1107,6 +1296,7 @@ mod tests {
             appid: None,
             app_id_uri: None,
             scp: None,
+            tid: None,
         };
 
         let scopes = claims.get_scopes();
@@ -1124,6 +1314,7 @@ mod tests {
             appid: None,
             app_id_uri: None,
             scp: Some("read".to_string()),
+            tid: None,
         };
 
         let scopes = claims.get_scopes();
@@ -1142,6 +1333,7 @@ mod tests {
             appid: None,
             app_id_uri: None,
             scp: Some("read write delete".to_string()),
+            tid: None,
         };
 
         let scopes = claims.get_scopes();
@@ -1150,4 +1342,235 @@ mod tests {
         assert!(scopes.contains(&"write".to_string()));
         assert!(scopes.contains(&"delete".to_string()));
     }
+
+    #[test]
+    fn test_entra_keys_are_only_loaded_for_the_entra_provider() {
+        let mut app_config = AppConfig::default();
+        app_config.auth.entra.tenant_id = "tenant-only-for-this-test".to_string();
+        let jwks_uri = EntraAuthConfig::from_app_config(&app_config)
+            .unwrap()
+            .jwks_uri;
+        let registered = || {
+            JwksManager::health_statuses()
+                .iter()
+                .any(|status| status.name.contains(&jwks_uri))
+        };
+
+        let entra = EntraAuthConfig::from_app_config(&app_config).unwrap();
+        assert!(entra.jwks.is_some());
+        assert!(registered());
+        drop(entra);
+
+        app_config.auth.provider = AuthProvider::Oidc;
+        let oidc = EntraAuthConfig::from_app_config(&app_config).unwrap();
+        assert!(oidc.jwks.is_none());
+        assert!(!registered());
+    }
+
+    #[tokio::test]
+    async fn test_oidc_tokens_are_checked_against_role_requirements() {
+        use crate::core::auth::test_support::{
+            MockOidcProvider, expires_in_an_hour, rsa_jwk, sign_rs256,
+        };
+        use crate::core::config::app_config::OidcIssuerConfig;
+        use axum::{Extension, Router, routing::get};
+        use tower::ServiceExt;
+
+        let provider = MockOidcProvider::start("realms/partners", vec![rsa_jwk()]).await;
+        let issuer = provider.issuer("realms/partners");
+
+        let mut app_config = AppConfig::default();
+        app_config.auth.provider = AuthProvider::Oidc;
+        app_config.auth.oidc.issuers = vec![OidcIssuerConfig {
+            issuer: issuer.clone(),
+            audience: "backend".to_string(),
+            discovery_url: None,
+            roles_claim: "realm_access.roles".to_string(),
+            scopes_claim: "scope".to_string(),
+        }];
+
+        let app = Router::new()
+            .route(
+                "/",
+                get(|Extension(claims): Extension<EntraClaims>| async move { claims.sub }),
+            )
+            .layer(
+                EntraAuthLayer::from_app_config_require_any_role(
+                    &app_config,
+                    vec!["admin".to_string()],
+                )
+                .unwrap(),
+            );
+
+        let request = |roles: Vec<&str>| {
+            let token = sign_rs256(&json!({
+                "iss": issuer,
+                "sub": "user-1",
+                "aud": "backend",
+                "exp": expires_in_an_hour(),
+                "realm_access": { "roles": roles },
+            }));
+            Request::builder()
+                .uri("/")
+                .header("Authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(request(vec!["admin"])).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.oneshot(request(vec!["reader"])).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_create_decoding_key_for_each_key_type() {
+        use crate::core::auth::test_support::{
+            ec_p256_jwk, ec_p384_jwk, ed25519_jwk, rsa_jwk, sign,
+        };
+
+        let claims = json!({ "sub": "user-1", "exp": constants::timestamps::YEAR_2100 });
+        let jwk = |value: serde_json::Value| serde_json::from_value::<Jwk>(value).unwrap();
+
+        for (alg, key) in [
+            (Algorithm::RS256, rsa_jwk()),
+            (Algorithm::ES256, ec_p256_jwk()),
+            (Algorithm::ES384, ec_p384_jwk()),
+            (Algorithm::EdDSA, ed25519_jwk()),
+        ] {
+            let decoding_key = create_decoding_key(&jwk(key), alg).unwrap();
+            let mut validation = Validation::new(alg);
+            validation.set_required_spec_claims(&["exp"]);
+            let token = sign(alg, &claims);
+            assert!(
+                decode::<serde_json::Value>(&token, &decoding_key, &validation).is_ok(),
+                "{:?} token should verify",
+                alg
+            );
+        }
+
+        // The key type, curve and the key's own `alg` must fit the token's algorithm
+        assert!(create_decoding_key(&jwk(ec_p256_jwk()), Algorithm::RS256).is_err());
+        assert!(create_decoding_key(&jwk(ec_p256_jwk()), Algorithm::ES384).is_err());
+        assert!(create_decoding_key(&jwk(ed25519_jwk()), Algorithm::ES256).is_err());
+        assert!(create_decoding_key(&jwk(rsa_jwk()), Algorithm::PS256).is_err());
+        assert!(create_decoding_key(&jwk(rsa_jwk()), Algorithm::HS256).is_err());
+        let secret = jwk(json!({ "kty": "oct", "kid": "shared", "k": "c2VjcmV0" }));
+        assert!(create_decoding_key(&secret, Algorithm::RS256).is_err());
+    }
+
+    #[test]
+    fn test_unsigned_and_hmac_tokens_are_rejected() {
+        let unsigned = format!(
+            "{}.{}.",
+            URL_SAFE_NO_PAD.encode(r#"{"alg":"none","typ":"JWT"}"#),
+            URL_SAFE_NO_PAD.encode(r#"{"sub":"user-1"}"#)
+        );
+        match decode_token_header(&unsigned) {
+            Err(AuthError::ValidationFailed(message)) => assert!(message.contains("alg: none")),
+            other => panic!("expected an unsigned token error, got {:?}", other),
+        }
+
+        let allowed = app_config::default_allowed_algorithms();
+        assert!(check_algorithm(Algorithm::RS256, &allowed).is_ok());
+        assert!(check_algorithm(Algorithm::EdDSA, &allowed).is_ok());
+        // HMAC is refused even when explicitly allowed
+        assert!(check_algorithm(Algorithm::HS256, &[Algorithm::HS256]).is_err());
+        assert!(check_algorithm(Algorithm::ES256, &[Algorithm::RS256]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_policies_are_evaluated_per_route_and_method() {
+        use crate::core::auth::test_support::{
+            MockOidcProvider, expires_in_an_hour, rsa_jwk, sign_rs256,
+        };
+        use crate::core::config::app_config::{AuthPolicyConfig, OidcIssuerConfig};
+        use axum::{Router, routing::get};
+        use tower::ServiceExt;
+
+        let provider = MockOidcProvider::start("realms/policies", vec![rsa_jwk()]).await;
+        let issuer = provider.issuer("realms/policies");
+
+        let mut app_config = AppConfig::default();
+        app_config.auth.provider = AuthProvider::Oidc;
+        app_config.auth.oidc.issuers = vec![OidcIssuerConfig {
+            issuer: issuer.clone(),
+            audience: "backend".to_string(),
+            discovery_url: None,
+            roles_claim: "roles".to_string(),
+            scopes_claim: "scope".to_string(),
+        }];
+        app_config.auth.policies = vec![
+            AuthPolicyConfig {
+                name: "read-pets".to_string(),
+                path: "/protected/pet/{*rest}".to_string(),
+                methods: vec!["GET".to_string()],
+                roles: Vec::new(),
+                scopes: vec!["pets.read".to_string()],
+                claims: Vec::new(),
+            },
+            AuthPolicyConfig {
+                name: "delete-pets".to_string(),
+                path: "/protected/pet/{id}".to_string(),
+                methods: vec!["DELETE".to_string()],
+                roles: vec!["admin".to_string()],
+                scopes: Vec::new(),
+                claims: Vec::new(),
+            },
+        ];
+
+        // Nested, as in the application router, so the layer sees a stripped path
+        let pets = Router::new()
+            .route(
+                "/pet/{id}",
+                get(|| async { "pet" }).delete(|| async { "deleted" }),
+            )
+            .layer(EntraAuthLayer::from_app_config(&app_config).unwrap());
+        let app = Router::new().nest("/protected", pets);
+
+        let request = |method: &str, roles: Vec<&str>, scope: &str| {
+            let token = sign_rs256(&json!({
+                "iss": issuer,
+                "sub": "user-1",
+                "aud": "backend",
+                "exp": expires_in_an_hour(),
+                "roles": roles,
+                "scope": scope,
+            }));
+            Request::builder()
+                .method(method)
+                .uri("/protected/pet/1")
+                .header("Authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(request("GET", vec![], "pets.read"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .clone()
+            .oneshot(request("GET", vec!["admin"], ""))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .clone()
+            .oneshot(request("DELETE", vec![], "pets.read"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .oneshot(request("DELETE", vec!["admin"], ""))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
 }
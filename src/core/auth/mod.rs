@@ -2,10 +2,25 @@
 //!
 //! This module provides authentication and authorization functionality:
 //! - Middleware for validating incoming bearer tokens (protect our API)
+//! - Validation of tokens from any OpenID Connect issuer (Keycloak, Auth0, ...)
+//! - Signing key sets kept fresh across key rotations at the identity provider
+//! - Declarative per-route authorization policies from `auth.policies`
+//! - `Principal` extractor giving handlers the authenticated caller
 //! - Client for acquiring tokens for downstream API calls
 
 pub mod client;
+pub mod jwks;
 pub mod middleware;
+pub mod oidc;
+pub mod policy;
+pub mod principal;
+
+#[cfg(test)]
+pub(crate) mod test_support;
 
 pub use client::EntraTokenClient;
+pub use jwks::JwksManager;
 pub use middleware::EntraAuthLayer;
+pub use oidc::OidcValidator;
+pub use policy::PolicyEngine;
+pub use principal::Principal;
@@ -0,0 +1,363 @@
+//! Issuer-agnostic OpenID Connect token validation
+//!
+//! Tokens from any number of trusted issuers (Keycloak realms, Auth0 tenants,
+//! ...) are accepted. The issuer named by a token's `iss` claim is looked up
+//! in the configured list, its signing keys are found through its
+//! `/.well-known/openid-configuration` document, and its roles and scopes are
+//! read from configurable claim paths into the same [`EntraClaims`] the Entra
+//! mode produces, so role and permission requirements work unchanged.
+
+use std::sync::Arc;
+
+use jsonwebtoken::{Algorithm, Validation, decode};
+use serde_json::Value;
+
+use super::jwks::JwksManager;
+use super::middleware::{
+    AuthError, EntraClaims, check_algorithm, create_decoding_key, decode_token_header,
+    decode_token_part, validation_error,
+};
+use crate::core::config::app_config::{self, AuthConfig, JwksConfig, OidcIssuerConfig};
+
+/// A trusted issuer and its signing keys
+#[derive(Debug)]
+struct OidcProvider {
+    config: OidcIssuerConfig,
+    jwks: Arc<JwksManager>,
+}
+
+/// Validates tokens against a set of trusted OpenID Connect issuers
+#[derive(Debug)]
+pub struct OidcValidator {
+    providers: Vec<OidcProvider>,
+    allowed_algorithms: Vec<Algorithm>,
+}
+
+impl OidcValidator {
+    /// Create a validator trusting `issuers`
+    pub fn new(issuers: Vec<OidcIssuerConfig>) -> Self {
+        Self::with_jwks_config(issuers, &JwksConfig::default())
+    }
+
+    /// Create a validator trusting `issuers`, caching their keys as `jwks` says
+    pub fn with_jwks_config(issuers: Vec<OidcIssuerConfig>, jwks: &JwksConfig) -> Self {
+        Self {
+            providers: issuers
+                .into_iter()
+                .map(|config| OidcProvider {
+                    jwks: JwksManager::for_discovery(&config.discovery_url(), &config.issuer, jwks),
+                    config,
+                })
+                .collect(),
+            allowed_algorithms: app_config::default_allowed_algorithms(),
+        }
+    }
+
+    /// Accept only tokens signed with one of `algorithms`
+    pub fn with_allowed_algorithms(mut self, algorithms: Vec<Algorithm>) -> Self {
+        self.allowed_algorithms = algorithms;
+        self
+    }
+
+    /// Create a validator from the `auth` section
+    pub fn from_config(config: &AuthConfig) -> Self {
+        Self::with_jwks_config(config.oidc.issuers.clone(), &config.jwks)
+            .with_allowed_algorithms(config.allowed_algorithms.clone())
+    }
+
+    /// Issuers whose tokens are accepted
+    pub fn issuers(&self) -> impl Iterator<Item = &str> {
+        self.providers.iter().map(|p| p.config.issuer.as_str())
+    }
+
+    /// Verify `token` and return its claims
+    pub async fn validate(&self, token: &str) -> Result<EntraClaims, AuthError> {
+        let header = decode_token_header(token)?;
+        check_algorithm(header.alg, &self.allowed_algorithms)?;
+        let kid = header.kid.ok_or_else(|| {
+            AuthError::ValidationFailed("Token header missing 'kid' claim".to_string())
+        })?;
+
+        // The issuer picks the keys, so it is read before the signature is checked
+        // and then verified along with everything else
+        let issuer = unverified_issuer(token)?;
+        let provider = self
+            .providers
+            .iter()
+            .find(|p| p.config.issuer == issuer)
+            .ok_or_else(|| AuthError::ValidationFailed(format!("Untrusted issuer: {}", issuer)))?;
+
+        let jwk = provider.jwks.key(&kid).await?;
+        let decoding_key = create_decoding_key(&jwk, header.alg)?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.validate_exp = true;
+        validation.set_audience(&[&provider.config.audience]);
+        validation.set_issuer(&[&provider.config.issuer]);
+        validation.set_required_spec_claims(&["exp", "iss", "sub", "aud"]);
+
+        let token_data = decode::<Value>(token, &decoding_key, &validation)
+            .map_err(|e| validation_error(e, &provider.config.audience))?;
+
+        Ok(provider.claims(&token_data.claims))
+    }
+}
+
+impl OidcProvider {
+    /// Map verified claims onto `EntraClaims` using the configured claim paths
+    fn claims(&self, claims: &Value) -> EntraClaims {
+        let string = |name: &str| claims.get(name).and_then(Value::as_str).map(String::from);
+        let number =
+            |name: &str| claims.get(name).and_then(Value::as_u64).unwrap_or_default() as usize;
+        let scopes = string_list(claim_path(claims, &self.config.scopes_claim));
+
+        EntraClaims {
+            sub: string("sub").unwrap_or_default(),
+            // `aud` may be a list; validation has checked ours is in it
+            aud: self.config.audience.clone(),
+            iss: self.config.issuer.clone(),
+            exp: number("exp"),
+            nbf: number("nbf"),
+            iat: number("iat"),
+            roles: string_list(claim_path(claims, &self.config.roles_claim)),
+            appid: string("client_id"),
+            app_id_uri: string("azp"),
+            scp: (!scopes.is_empty()).then(|| scopes.join(" ")),
+            tid: string("tid"),
+        }
+    }
+}
+
+/// Read the `iss` claim without verifying the token
+fn unverified_issuer(token: &str) -> Result<String, AuthError> {
+    let payload = decode_token_part(token, 1).ok_or(AuthError::InvalidTokenFormat)?;
+
+    payload
+        .get("iss")
+        .and_then(Value::as_str)
+        .map(String::from)
+        .ok_or_else(|| AuthError::ValidationFailed("Token missing 'iss' claim".to_string()))
+}
+
+/// Look up a claim by path
+///
+/// A path is first tried as a single claim name, so namespaced claims such as
+/// `https://example.com/roles` work, and otherwise split on `.` to reach
+/// nested claims such as `realm_access.roles`.
+pub(super) fn claim_path<'a>(claims: &'a Value, path: &str) -> Option<&'a Value> {
+    claims.get(path).or_else(|| {
+        path.split('.')
+            .try_fold(claims, |value, key| value.get(key))
+    })
+}
+
+/// Read a claim holding either a list of strings or a space-separated string
+fn string_list(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .map(String::from)
+            .collect(),
+        Some(Value::String(s)) => s.split_whitespace().map(String::from).collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::auth::test_support::{
+        MockOidcProvider, RSA_MODULUS, ec_p256_jwk, ec_p384_jwk, ed25519_jwk, expires_in_an_hour,
+        rsa_jwk, sign, sign_rs256,
+    };
+    use jsonwebtoken::{EncodingKey, Header, encode};
+    use serde_json::json;
+
+    fn issuer_config(issuer: &str, audience: &str) -> OidcIssuerConfig {
+        OidcIssuerConfig {
+            issuer: issuer.to_string(),
+            audience: audience.to_string(),
+            discovery_url: None,
+            roles_claim: "roles".to_string(),
+            scopes_claim: "scope".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_claim_paths() {
+        let claims = json!({
+            "realm_access": { "roles": ["admin", "reader"] },
+            "https://example.com/roles": ["auditor"],
+            "scope": "openid pets:read",
+        });
+
+        assert_eq!(
+            string_list(claim_path(&claims, "realm_access.roles")),
+            vec!["admin", "reader"]
+        );
+        assert_eq!(
+            string_list(claim_path(&claims, "https://example.com/roles")),
+            vec!["auditor"]
+        );
+        assert_eq!(
+            string_list(claim_path(&claims, "scope")),
+            vec!["openid", "pets:read"]
+        );
+        assert!(string_list(claim_path(&claims, "resource_access.api.roles")).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_validates_tokens_from_several_issuers() {
+        let keycloak = MockOidcProvider::start("realms/partners", vec![rsa_jwk()]).await;
+        let auth0 = MockOidcProvider::start("tenant", vec![rsa_jwk()]).await;
+        let keycloak_issuer = keycloak.issuer("realms/partners");
+        let auth0_issuer = auth0.issuer("tenant");
+
+        let validator = OidcValidator::new(vec![
+            OidcIssuerConfig {
+                roles_claim: "realm_access.roles".to_string(),
+                ..issuer_config(&keycloak_issuer, "backend")
+            },
+            OidcIssuerConfig {
+                roles_claim: "https://example.com/roles".to_string(),
+                scopes_claim: "permissions".to_string(),
+                ..issuer_config(&auth0_issuer, "https://api.example.com")
+            },
+        ]);
+
+        let token = sign_rs256(&json!({
+            "iss": keycloak_issuer,
+            "sub": "user-1",
+            "aud": ["backend", "account"],
+            "exp": expires_in_an_hour(),
+            "azp": "partner-portal",
+            "realm_access": { "roles": ["admin"] },
+            "scope": "openid pets:read",
+        }));
+        let claims = validator.validate(&token).await.unwrap();
+        assert_eq!(claims.sub, "user-1");
+        assert_eq!(claims.aud, "backend");
+        assert_eq!(claims.roles, vec!["admin"]);
+        assert_eq!(claims.app_id_uri.as_deref(), Some("partner-portal"));
+        assert_eq!(claims.get_scopes(), vec!["openid", "pets:read", "admin"]);
+
+        let token = sign_rs256(&json!({
+            "iss": auth0_issuer,
+            "sub": "client@clients",
+            "aud": "https://api.example.com",
+            "exp": expires_in_an_hour(),
+            "https://example.com/roles": ["reader"],
+            "permissions": ["pets:write"],
+        }));
+        let claims = validator.validate(&token).await.unwrap();
+        assert_eq!(claims.roles, vec!["reader"]);
+        assert_eq!(claims.scp.as_deref(), Some("pets:write"));
+
+        // The audience is checked per issuer
+        let token = sign_rs256(&json!({
+            "iss": auth0_issuer,
+            "sub": "client@clients",
+            "aud": "backend",
+            "exp": expires_in_an_hour(),
+        }));
+        assert!(matches!(
+            validator.validate(&token).await,
+            Err(AuthError::ValidationFailed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_untrusted_issuers() {
+        let provider = MockOidcProvider::start("realms/partners", vec![rsa_jwk()]).await;
+        let issuer = provider.issuer("realms/partners");
+        let validator = OidcValidator::new(vec![issuer_config(&issuer, "backend")]);
+
+        let token = sign_rs256(&json!({
+            "iss": format!("{}/realms/other", provider.server.uri()),
+            "sub": "user-1",
+            "aud": "backend",
+            "exp": expires_in_an_hour(),
+        }));
+        match validator.validate(&token).await {
+            Err(AuthError::ValidationFailed(message)) => {
+                assert!(message.starts_with("Untrusted issuer"))
+            }
+            other => panic!("expected an untrusted issuer error, got {:?}", other.err()),
+        }
+
+        // A discovery document naming another issuer is not trusted either
+        let mismatched = OidcValidator::new(vec![OidcIssuerConfig {
+            discovery_url: Some(format!("{}/.well-known/openid-configuration", issuer)),
+            ..issuer_config("https://impostor.example.com", "backend")
+        }]);
+        let token = sign_rs256(&json!({
+            "iss": "https://impostor.example.com",
+            "sub": "user-1",
+            "aud": "backend",
+            "exp": expires_in_an_hour(),
+        }));
+        assert!(matches!(
+            mismatched.validate(&token).await,
+            Err(AuthError::InternalError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_algorithm_is_taken_from_the_token_header() {
+        let keys = vec![rsa_jwk(), ec_p256_jwk(), ec_p384_jwk(), ed25519_jwk()];
+        let provider = MockOidcProvider::start("realms/partners", keys).await;
+        let issuer = provider.issuer("realms/partners");
+        let claims = json!({
+            "iss": issuer,
+            "sub": "user-1",
+            "aud": "backend",
+            "exp": expires_in_an_hour(),
+        });
+
+        let validator = OidcValidator::new(vec![issuer_config(&issuer, "backend")]);
+        for alg in [
+            Algorithm::RS256,
+            Algorithm::ES256,
+            Algorithm::ES384,
+            Algorithm::EdDSA,
+        ] {
+            let token = sign(alg, &claims);
+            assert!(
+                validator.validate(&token).await.is_ok(),
+                "{:?} token should be accepted",
+                alg
+            );
+        }
+
+        // HMAC-signed with the RSA key's public modulus as the secret
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("test-rsa".to_string());
+        let forged = encode(
+            &header,
+            &claims,
+            &EncodingKey::from_secret(RSA_MODULUS.as_bytes()),
+        )
+        .unwrap();
+        assert!(matches!(
+            validator.validate(&forged).await,
+            Err(AuthError::ValidationFailed(message)) if message.contains("Symmetric")
+        ));
+
+        // Algorithms outside the allow-list are refused
+        let rsa_only = OidcValidator::new(vec![issuer_config(&issuer, "backend")])
+            .with_allowed_algorithms(vec![Algorithm::RS256]);
+        assert!(
+            rsa_only
+                .validate(&sign(Algorithm::RS256, &claims))
+                .await
+                .is_ok()
+        );
+        assert!(
+            rsa_only
+                .validate(&sign(Algorithm::ES256, &claims))
+                .await
+                .is_err()
+        );
+    }
+}
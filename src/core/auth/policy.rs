@@ -0,0 +1,488 @@
+//! Declarative per-route authorization policies
+//!
+//! Policies are loaded from `auth.policies` and checked by
+//! `EntraAuthMiddleware` once a token has been validated. Each policy names
+//! the paths and methods it covers and what the caller needs there: one of a
+//! set of roles, one of a set of scopes (from the token's `scp`, never its
+//! roles), and conditions on claims:
+//!
+//! ```yaml
+//! auth:
+//!   policies:
+//!     - name: read-pets
+//!       path: "/protected/pet/{*rest}"
+//!       methods: [GET]
+//!       scopes: [pets.read]
+//!     - name: delete-pets
+//!       path: "/protected/pet/{id}"
+//!       methods: [DELETE]
+//!       roles: [admin]
+//!       claims:
+//!         - claim: tid
+//!           one_of: ["our-tenant-id"]
+//! ```
+//!
+//! A request must satisfy every policy matching it; requests matching none
+//! only need a valid token. Policies are checked when the configuration is
+//! loaded, so a typo in a method or path fails startup instead of leaving a
+//! route unguarded.
+
+use axum::http::Method;
+use serde_json::Value;
+
+use super::middleware::EntraClaims;
+use super::oidc::claim_path;
+use crate::core::config::app_config::{AuthPolicyConfig, ClaimPredicateConfig};
+
+/// One segment of a policy path pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// Matches this exact segment
+    Literal(String),
+    /// `{name}` or `*`: matches any one segment
+    Any,
+    /// A final `{*name}`: matches the rest of the path, if any
+    Rest,
+}
+
+/// A policy with its path pattern and methods parsed
+#[derive(Debug)]
+struct Policy {
+    config: AuthPolicyConfig,
+    segments: Vec<Segment>,
+    methods: Vec<Method>,
+}
+
+/// The outcome of checking a request against the policies
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyDecision {
+    /// No policy covers the request
+    NoMatch,
+    /// Every matching policy, named here, allows the request
+    Allow(Vec<String>),
+    /// The named policy denies the request
+    Deny { policy: String, reason: String },
+}
+
+/// Evaluates the configured authorization policies
+#[derive(Debug)]
+pub struct PolicyEngine {
+    policies: Vec<Policy>,
+}
+
+/// Methods a policy may name
+const KNOWN_METHODS: [Method; 9] = [
+    Method::GET,
+    Method::HEAD,
+    Method::POST,
+    Method::PUT,
+    Method::DELETE,
+    Method::PATCH,
+    Method::OPTIONS,
+    Method::TRACE,
+    Method::CONNECT,
+];
+
+impl PolicyEngine {
+    /// Create an engine for `policies`, rejecting any that is malformed
+    ///
+    /// Each policy needs a name and a path starting with `/`, whose only
+    /// `{*name}` segment is the last one. Methods must be standard HTTP methods
+    /// (in any case) and claim predicates must name a claim.
+    pub fn new(policies: Vec<AuthPolicyConfig>) -> Result<Self, String> {
+        let policies = policies
+            .into_iter()
+            .map(|config| {
+                Policy::parse(config.clone())
+                    .map_err(|e| format!("policy '{}': {}", config.name, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { policies })
+    }
+
+    /// Whether there are no policies to check
+    pub fn is_empty(&self) -> bool {
+        self.policies.is_empty()
+    }
+
+    /// Check a `method` request for `path` by the caller with `claims`
+    ///
+    /// `raw_claims` holds every claim of the token, for claim predicates.
+    pub fn evaluate(
+        &self,
+        method: &Method,
+        path: &str,
+        claims: &EntraClaims,
+        raw_claims: &Value,
+    ) -> PolicyDecision {
+        let mut allowed_by = Vec::new();
+        for policy in self.policies.iter().filter(|p| p.matches(method, path)) {
+            if let Err(reason) = policy.check(claims, raw_claims) {
+                return PolicyDecision::Deny {
+                    policy: policy.config.name.clone(),
+                    reason,
+                };
+            }
+            allowed_by.push(policy.config.name.clone());
+        }
+
+        if allowed_by.is_empty() {
+            PolicyDecision::NoMatch
+        } else {
+            PolicyDecision::Allow(allowed_by)
+        }
+    }
+}
+
+impl Policy {
+    fn parse(config: AuthPolicyConfig) -> Result<Self, String> {
+        if config.name.trim().is_empty() {
+            return Err("name must not be empty".to_string());
+        }
+        let segments = parse_path(&config.path)?;
+        let methods = config
+            .methods
+            .iter()
+            .map(|method| {
+                KNOWN_METHODS
+                    .iter()
+                    .find(|known| known.as_str().eq_ignore_ascii_case(method.trim()))
+                    .cloned()
+                    .ok_or_else(|| format!("unknown HTTP method '{}'", method))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if config.claims.iter().any(|p| p.claim.trim().is_empty()) {
+            return Err("claim conditions must name a claim".to_string());
+        }
+
+        Ok(Self {
+            config,
+            segments,
+            methods,
+        })
+    }
+
+    fn matches(&self, method: &Method, path: &str) -> bool {
+        (self.methods.is_empty() || self.methods.contains(method))
+            && path_matches(&self.segments, path)
+    }
+
+    /// Check the caller's roles, scopes and claims, describing what is missing
+    fn check(&self, claims: &EntraClaims, raw_claims: &Value) -> Result<(), String> {
+        let config = &self.config;
+        if !config.roles.is_empty() && !config.roles.iter().any(|r| claims.roles.contains(r)) {
+            return Err(format!("requires one of the roles {:?}", config.roles));
+        }
+
+        // Only delegated scopes count here; roles are checked above
+        if !config.scopes.is_empty() {
+            let scopes: Vec<&str> = claims
+                .scp
+                .as_deref()
+                .map(|scp| scp.split_whitespace().collect())
+                .unwrap_or_default();
+            if !config.scopes.iter().any(|s| scopes.contains(&s.as_str())) {
+                return Err(format!("requires one of the scopes {:?}", config.scopes));
+            }
+        }
+
+        for predicate in &config.claims {
+            if !claim_matches(raw_claims, predicate) {
+                return Err(if predicate.one_of.is_empty() {
+                    format!("requires the claim '{}'", predicate.claim)
+                } else {
+                    format!(
+                        "requires claim '{}' to be one of {:?}",
+                        predicate.claim, predicate.one_of
+                    )
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_path(pattern: &str) -> Result<Vec<Segment>, String> {
+    if !pattern.starts_with('/') {
+        return Err(format!("path '{}' must start with '/'", pattern));
+    }
+
+    let segments = pattern
+        .trim_matches('/')
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            if segment.starts_with("{*") && segment.ends_with('}') {
+                Ok(Segment::Rest)
+            } else if segment == "*" || (segment.starts_with('{') && segment.ends_with('}')) {
+                Ok(Segment::Any)
+            } else if segment.contains(['{', '}', '*']) {
+                Err(format!(
+                    "path '{}' has a malformed segment '{}'",
+                    pattern, segment
+                ))
+            } else {
+                Ok(Segment::Literal(segment.to_string()))
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if segments
+        .iter()
+        .rev()
+        .skip(1)
+        .any(|segment| *segment == Segment::Rest)
+    {
+        return Err(format!(
+            "path '{}' may only end with a {{*name}} segment",
+            pattern
+        ));
+    }
+    Ok(segments)
+}
+
+fn path_matches(pattern: &[Segment], path: &str) -> bool {
+    let mut segments = path
+        .trim_matches('/')
+        .split('/')
+        .filter(|segment| !segment.is_empty());
+
+    for expected in pattern {
+        match expected {
+            Segment::Rest => return true,
+            Segment::Any => {
+                if segments.next().is_none() {
+                    return false;
+                }
+            }
+            Segment::Literal(literal) => {
+                if segments.next() != Some(literal.as_str()) {
+                    return false;
+                }
+            }
+        }
+    }
+    segments.next().is_none()
+}
+
+/// Whether the claim named by `predicate` is present and, if values are given, has one of them
+fn claim_matches(raw_claims: &Value, predicate: &ClaimPredicateConfig) -> bool {
+    let Some(value) = claim_path(raw_claims, &predicate.claim) else {
+        return false;
+    };
+    if predicate.one_of.is_empty() {
+        return !value.is_null();
+    }
+
+    let accepted = |value: &Value| {
+        let value = match value {
+            Value::String(s) => s.clone(),
+            Value::Null | Value::Array(_) | Value::Object(_) => return false,
+            other => other.to_string(),
+        };
+        predicate.one_of.contains(&value)
+    };
+    match value {
+        Value::Array(items) => items.iter().any(accepted),
+        other => accepted(other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn policy(name: &str, path: &str, methods: &[&str]) -> AuthPolicyConfig {
+        AuthPolicyConfig {
+            name: name.to_string(),
+            path: path.to_string(),
+            methods: methods.iter().map(|m| m.to_string()).collect(),
+            roles: Vec::new(),
+            scopes: Vec::new(),
+            claims: Vec::new(),
+        }
+    }
+
+    fn claims(roles: &[&str], scp: &str) -> EntraClaims {
+        EntraClaims {
+            sub: "user-1".to_string(),
+            aud: "backend".to_string(),
+            iss: "issuer".to_string(),
+            exp: 0,
+            nbf: 0,
+            iat: 0,
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+            appid: None,
+            app_id_uri: None,
+            scp: Some(scp.to_string()),
+            tid: None,
+        }
+    }
+
+    #[test]
+    fn test_path_patterns() {
+        let pattern = parse_path("/pets/{id}").unwrap();
+        assert!(path_matches(&pattern, "/pets/1"));
+        assert!(path_matches(&pattern, "/pets/1/"));
+        assert!(!path_matches(&pattern, "/pets"));
+        assert!(!path_matches(&pattern, "/pets/1/toys"));
+        assert!(!path_matches(&pattern, "/owners/1"));
+
+        let pattern = parse_path("/pets/{*rest}").unwrap();
+        assert!(path_matches(&pattern, "/pets"));
+        assert!(path_matches(&pattern, "/pets/1/toys"));
+        assert!(!path_matches(&pattern, "/owners"));
+
+        assert!(path_matches(
+            &parse_path("/*/health").unwrap(),
+            "/actuator/health"
+        ));
+    }
+
+    #[test]
+    fn test_requirements_depend_on_method() {
+        let engine = PolicyEngine::new(vec![
+            AuthPolicyConfig {
+                scopes: vec!["pets.read".to_string()],
+                ..policy("read-pets", "/pets/{*rest}", &["get"])
+            },
+            AuthPolicyConfig {
+                roles: vec!["admin".to_string()],
+                ..policy("delete-pets", "/pets/{id}", &["DELETE"])
+            },
+        ])
+        .unwrap();
+        let raw = json!({});
+        let reader = claims(&[], "pets.read");
+        let admin = claims(&["admin"], "");
+
+        assert_eq!(
+            engine.evaluate(&Method::GET, "/pets/1", &reader, &raw),
+            PolicyDecision::Allow(vec!["read-pets".to_string()])
+        );
+        assert!(matches!(
+            engine.evaluate(&Method::GET, "/pets/1", &admin, &raw),
+            PolicyDecision::Deny { policy, .. } if policy == "read-pets"
+        ));
+        assert!(matches!(
+            engine.evaluate(&Method::DELETE, "/pets/1", &reader, &raw),
+            PolicyDecision::Deny { policy, .. } if policy == "delete-pets"
+        ));
+        assert_eq!(
+            engine.evaluate(&Method::DELETE, "/pets/1", &admin, &raw),
+            PolicyDecision::Allow(vec!["delete-pets".to_string()])
+        );
+        assert_eq!(
+            engine.evaluate(&Method::GET, "/owners", &reader, &raw),
+            PolicyDecision::NoMatch
+        );
+    }
+
+    #[test]
+    fn test_claim_predicates() {
+        let engine = PolicyEngine::new(vec![AuthPolicyConfig {
+            claims: vec![
+                ClaimPredicateConfig {
+                    claim: "tid".to_string(),
+                    one_of: vec!["tenant-1".to_string()],
+                },
+                ClaimPredicateConfig {
+                    claim: "realm_access.groups".to_string(),
+                    one_of: vec!["partners".to_string()],
+                },
+                ClaimPredicateConfig {
+                    claim: "email_verified".to_string(),
+                    one_of: vec!["true".to_string()],
+                },
+            ],
+            ..policy("partners", "/partners/{*rest}", &[])
+        }])
+        .unwrap();
+        let caller = claims(&[], "");
+        let allowed = json!({
+            "tid": "tenant-1",
+            "realm_access": { "groups": ["staff", "partners"] },
+            "email_verified": true,
+        });
+        assert!(matches!(
+            engine.evaluate(&Method::POST, "/partners/orders", &caller, &allowed),
+            PolicyDecision::Allow(_)
+        ));
+
+        let other_tenant = json!({
+            "tid": "tenant-2",
+            "realm_access": { "groups": ["partners"] },
+            "email_verified": true,
+        });
+        match engine.evaluate(&Method::POST, "/partners/orders", &caller, &other_tenant) {
+            PolicyDecision::Deny { reason, .. } => assert!(reason.contains("'tid'")),
+            other => panic!("expected a denial, got {:?}", other),
+        }
+
+        let unverified = json!({
+            "tid": "tenant-1",
+            "realm_access": { "groups": ["partners"] },
+        });
+        assert!(matches!(
+            engine.evaluate(&Method::POST, "/partners/orders", &caller, &unverified),
+            PolicyDecision::Deny { .. }
+        ));
+    }
+
+    #[test]
+    fn test_roles_do_not_count_as_scopes() {
+        let engine = PolicyEngine::new(vec![AuthPolicyConfig {
+            scopes: vec!["pets.write".to_string()],
+            ..policy("write-pets", "/pets/{*rest}", &["POST"])
+        }])
+        .unwrap();
+        let raw = json!({});
+
+        assert!(matches!(
+            engine.evaluate(&Method::POST, "/pets", &claims(&["pets.write"], ""), &raw),
+            PolicyDecision::Deny { .. }
+        ));
+        assert!(matches!(
+            engine.evaluate(
+                &Method::POST,
+                "/pets",
+                &claims(&[], "openid pets.write"),
+                &raw
+            ),
+            PolicyDecision::Allow(_)
+        ));
+    }
+
+    #[test]
+    fn test_malformed_policies_are_rejected() {
+        let invalid = [
+            policy("typo", "/pets/{id}", &["DELET"]),
+            policy("two-methods", "/pets/{id}", &["GET POST"]),
+            policy("relative", "pets/{id}", &[]),
+            policy("rest-not-last", "/pets/{*rest}/toys", &[]),
+            policy("unclosed", "/pets/{id", &[]),
+            policy("", "/pets", &[]),
+            AuthPolicyConfig {
+                claims: vec![ClaimPredicateConfig {
+                    claim: " ".to_string(),
+                    one_of: Vec::new(),
+                }],
+                ..policy("unnamed-claim", "/pets", &[])
+            },
+        ];
+        for config in invalid {
+            let name = config.name.clone();
+            assert!(
+                PolicyEngine::new(vec![config]).is_err(),
+                "policy '{}' should be rejected",
+                name
+            );
+        }
+
+        let error = PolicyEngine::new(vec![policy("typo", "/pets/{id}", &["DELET"])]).unwrap_err();
+        assert!(error.contains("'typo'") && error.contains("DELET"));
+        assert!(PolicyEngine::new(vec![policy("ok", "/pets/{*rest}", &["get", "Delete"])]).is_ok());
+    }
+}
@@ -0,0 +1,190 @@
+//! The authenticated caller, for handlers
+//!
+//! `EntraAuthLayer` stores the validated token's claims on the request; the
+//! [`Principal`] extractor turns them into the caller's identity:
+//!
+//! ```rust,ignore
+//! async fn handler(principal: Principal) -> Result<String, AuthError> {
+//!     if !principal.has_scope("pets:write") {
+//!         return Err(AuthError::AccessDenied("pets:write scope required".to_string()));
+//!     }
+//!     Ok(principal.subject)
+//! }
+//! ```
+//!
+//! Extracting `Principal` rejects requests that were not authenticated with
+//! 401; `Option<Principal>` accepts them as `None`, for routes that work with
+//! or without a caller.
+
+use std::convert::Infallible;
+
+use axum::extract::{FromRequestParts, OptionalFromRequestParts};
+use axum::http::request::Parts;
+
+use super::middleware::{AuthError, EntraClaims};
+
+/// The authenticated caller of a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    /// Subject (user or client ID)
+    pub subject: String,
+    /// Tenant the caller belongs to, if the issuer says
+    pub tenant_id: Option<String>,
+    /// App (client) ID the token was issued to
+    pub app_id: Option<String>,
+    /// Issuer of the token
+    pub issuer: String,
+    /// Roles assigned to the caller
+    pub roles: Vec<String>,
+    /// Scopes (delegated permissions) granted to the caller
+    pub scopes: Vec<String>,
+}
+
+impl Principal {
+    /// Whether the caller has `role`
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    /// Whether the caller has any of `roles`
+    pub fn has_any_role<S: AsRef<str>>(&self, roles: &[S]) -> bool {
+        roles.iter().any(|role| self.has_role(role.as_ref()))
+    }
+
+    /// Whether the caller was granted `scope`
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+
+    /// Whether the caller is `subject`, for ownership checks
+    pub fn is(&self, subject: &str) -> bool {
+        self.subject == subject
+    }
+}
+
+impl From<&EntraClaims> for Principal {
+    fn from(claims: &EntraClaims) -> Self {
+        Self {
+            subject: claims.sub.clone(),
+            tenant_id: claims.tid.clone(),
+            app_id: claims.appid.clone().or_else(|| claims.app_id_uri.clone()),
+            issuer: claims.iss.clone(),
+            roles: claims.roles.clone(),
+            scopes: claims
+                .scp
+                .as_deref()
+                .map(|scp| scp.split_whitespace().map(String::from).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl<S> FromRequestParts<S> for Principal
+where
+    S: Send + Sync,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<EntraClaims>()
+            .map(Principal::from)
+            .ok_or(AuthError::MissingToken)
+    }
+}
+
+impl<S> OptionalFromRequestParts<S> for Principal
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts.extensions.get::<EntraClaims>().map(Principal::from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, http::Request, http::StatusCode, routing::get};
+    use tower::ServiceExt;
+
+    fn claims() -> EntraClaims {
+        EntraClaims {
+            sub: "user-1".to_string(),
+            aud: "api://backend".to_string(),
+            iss: "https://login.microsoftonline.com/tenant-1/v2.0".to_string(),
+            exp: 0,
+            nbf: 0,
+            iat: 0,
+            roles: vec!["admin".to_string()],
+            appid: None,
+            app_id_uri: Some("client-app".to_string()),
+            scp: Some("pets:read pets:write".to_string()),
+            tid: Some("tenant-1".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_principal_from_claims() {
+        let principal = Principal::from(&claims());
+
+        assert!(principal.is("user-1"));
+        assert_eq!(principal.tenant_id.as_deref(), Some("tenant-1"));
+        assert_eq!(principal.app_id.as_deref(), Some("client-app"));
+        assert!(principal.has_role("admin"));
+        assert!(!principal.has_role("reader"));
+        assert!(principal.has_any_role(&["reader", "admin"]));
+        assert!(principal.has_scope("pets:write"));
+        // Roles are not scopes
+        assert!(!principal.has_scope("admin"));
+    }
+
+    #[tokio::test]
+    async fn test_principal_extractors() {
+        let app = Router::new()
+            .route(
+                "/me",
+                get(|principal: Principal| async move { principal.subject }),
+            )
+            .route(
+                "/maybe",
+                get(|principal: Option<Principal>| async move {
+                    principal.map_or("anonymous".to_string(), |p| p.subject)
+                }),
+            );
+        let request = |uri: &str, claims: Option<EntraClaims>| {
+            let mut request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            if let Some(claims) = claims {
+                request.extensions_mut().insert(claims);
+            }
+            request
+        };
+        let body = |response: axum::response::Response| async move {
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            String::from_utf8(bytes.to_vec()).unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(request("/me", Some(claims())))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body(response).await, "user-1");
+
+        let response = app.clone().oneshot(request("/me", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app.oneshot(request("/maybe", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body(response).await, "anonymous");
+    }
+}
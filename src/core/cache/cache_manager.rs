@@ -1,20 +1,27 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use futures::stream::{self, StreamExt};
 use metrics::{counter, gauge};
+use moka::Expiry;
 use moka::future::Cache;
 use serde::{Deserialize, Serialize};
 use std::any::Any;
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::sync::{
-    Arc, RwLock,
+    Arc, Mutex, RwLock,
     atomic::{AtomicU64, Ordering},
 };
-use std::thread_local;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::time::interval;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
+use super::snapshot::{ResourceSnapshot, SnapshotEntry};
 // Import ApiResource trait
+use crate::cache::providers::CacheProvider;
+use crate::config::ResourceCacheConfig;
+use crate::core::error::AppError;
 use crate::utils::api_resource::ApiResource;
 
 /// Generic cache for any resource type that implements ApiResource
@@ -23,19 +30,835 @@ pub struct ResourceCache<T: ApiResource> {
     pub cache: Arc<Cache<String, T>>, // Use String keys for flexibility
     pub creation_time: SystemTime,
     pub ttl_seconds: u64,
+    pub tti_seconds: u64,
+    pub max_capacity: u64,
+    /// Byte budget when the cache is sized by weight (0 = sized by entry count)
+    pub max_bytes: u64,
+    pub stale_while_revalidate_seconds: u64,
+    pub stale_if_error_seconds: u64,
+    pub not_found_ttl_seconds: u64,
     pub active_entries: Arc<AtomicU64>,
     pub resource_type: String,
+    // Last good values, kept past expiry while a stale window is configured
+    stale: Option<moka::sync::Cache<String, StaleEntry<T>>>,
+    // Upstream "not found" errors, kept for `not_found_ttl_seconds`
+    not_found: Option<moka::sync::Cache<String, CachedError>>,
+    // Tags of the cached entries, for invalidation by tag
+    tags: Arc<Mutex<TagIndex>>,
+    // When each entry was stored and how often it was hit, for snapshots
+    usage: Arc<Mutex<HashMap<String, EntryUsage>>>,
+    // Lifetimes of entries being written with their own expiry
+    entry_ttls: Arc<Mutex<HashMap<String, Duration>>>,
+}
+
+/// A fetch error kept in a negative cache, downcast to its [`FetchError`] type on use
+type CachedError = Arc<dyn Any + Send + Sync>;
+
+/// An error returned by the fetch function of [`get_or_fetch`] and friends
+///
+/// Concurrent callers coalesced onto one fetch each receive their own copy of
+/// its error, and "not found" errors may be cached, so the cache needs to tell
+/// them apart and copy them. [`AppError`] implements it.
+pub trait FetchError: std::fmt::Display + Send + Sync + Sized + 'static {
+    /// Whether the upstream reported that the resource does not exist
+    fn is_not_found(&self) -> bool;
+
+    /// A copy of this error for another caller
+    fn duplicate(&self) -> Self;
+}
+
+/// When a cached entry was stored, its own TTL if any, and how often it has been hit since
+#[derive(Debug, Clone, Copy)]
+struct EntryUsage {
+    stored_at: SystemTime,
+    ttl: Option<Duration>,
+    hits: u64,
+}
+
+/// Per-entry expiry for values written with their own TTL
+///
+/// Such entries (e.g. restored from a snapshot, or stored through a cache provider)
+/// expire after that TTL, or the cache's TTL if sooner; all other entries (and these
+/// once rewritten without one) use the cache's TTL.
+struct EntryExpiry(Arc<Mutex<HashMap<String, Duration>>>);
+
+impl<T> Expiry<String, T> for EntryExpiry {
+    fn expire_after_create(
+        &self,
+        key: &String,
+        _value: &T,
+        _created_at: Instant,
+    ) -> Option<Duration> {
+        self.0.lock().ok()?.remove(key)
+    }
+
+    fn expire_after_update(
+        &self,
+        key: &String,
+        _value: &T,
+        _updated_at: Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        self.0.lock().ok()?.remove(key)
+    }
+}
+
+/// Tags attached to cached keys, indexed both ways
+#[derive(Debug, Default)]
+struct TagIndex {
+    by_tag: HashMap<String, HashSet<String>>,
+    by_key: HashMap<String, HashSet<String>>,
+}
+
+impl TagIndex {
+    /// Replace the tags of `key`
+    fn set(&mut self, key: &str, tags: HashSet<String>) {
+        self.remove_key(key);
+        if tags.is_empty() {
+            return;
+        }
+        for tag in &tags {
+            self.by_tag
+                .entry(tag.clone())
+                .or_default()
+                .insert(key.to_string());
+        }
+        self.by_key.insert(key.to_string(), tags);
+    }
+
+    /// Forget the tags of a key that is no longer cached
+    fn remove_key(&mut self, key: &str) {
+        let Some(tags) = self.by_key.remove(key) else {
+            return;
+        };
+        for tag in tags {
+            if let Some(keys) = self.by_tag.get_mut(&tag) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.by_tag.remove(&tag);
+                }
+            }
+        }
+    }
+
+    /// The keys currently tagged `tag`
+    fn keys(&self, tag: &str) -> Vec<String> {
+        self.by_tag
+            .get(tag)
+            .map(|keys| keys.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn clear(&mut self) {
+        self.by_tag.clear();
+        self.by_key.clear();
+    }
+}
+
+/// A previously cached value and when it was stored
+#[derive(Debug, Clone)]
+struct StaleEntry<T> {
+    value: T,
+    stored_at: Instant,
+}
+
+/// Why a stale value was served
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StaleReason {
+    Revalidate,
+    Error,
+}
+
+impl StaleReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            StaleReason::Revalidate => "revalidate",
+            StaleReason::Error => "error",
+        }
+    }
+}
+
+impl<T: ApiResource> ResourceCache<T> {
+    /// Insert a value, also recording it as the last good value for stale serving
+    ///
+    /// Any cached "not found" for the key is dropped, since the resource now exists.
+    /// The entry is tagged with the value's [`ApiResource::cache_tags`].
+    pub async fn insert(&self, key: String, value: T) {
+        self.insert_with_tags(key, value, &[]).await;
+    }
+
+    /// Insert a value tagged with `tags` in addition to its own `cache_tags`
+    ///
+    /// Tags replace any the key had before.
+    pub async fn insert_with_tags(&self, key: String, value: T, tags: &[String]) {
+        self.insert_entry(key, value, tags, None).await;
+    }
+
+    /// Insert a tagged value that expires after `ttl` instead of the cache's TTL
+    ///
+    /// The cache's TTL still applies when it is shorter.
+    pub async fn insert_with_ttl(&self, key: String, value: T, ttl: Duration, tags: &[String]) {
+        self.insert_entry(key, value, tags, Some(ttl)).await;
+    }
+
+    async fn insert_entry(&self, key: String, value: T, tags: &[String], ttl: Option<Duration>) {
+        if let Some(ttl) = ttl
+            && let Ok(mut entry_ttls) = self.entry_ttls.lock()
+        {
+            entry_ttls.insert(key.clone(), ttl);
+        }
+        self.forget_not_found(&key);
+        self.remember(&key, &value);
+        self.tag(&key, &value, tags);
+        self.record_stored(&key, SystemTime::now(), ttl);
+        self.cache.insert(key, value).await;
+    }
+
+    /// The tags of the entry cached under `key`, sorted
+    pub fn tags(&self, key: &str) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .tags
+            .lock()
+            .ok()
+            .and_then(|index| index.by_key.get(key).cloned())
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        tags.sort();
+        tags
+    }
+
+    fn tag(&self, key: &str, value: &T, extra: &[String]) {
+        let tags: HashSet<String> = value
+            .cache_tags()
+            .into_iter()
+            .chain(extra.iter().cloned())
+            .collect();
+        if let Ok(mut index) = self.tags.lock() {
+            index.set(key, tags);
+        }
+    }
+
+    fn record_stored(&self, key: &str, stored_at: SystemTime, ttl: Option<Duration>) {
+        if let Ok(mut usage) = self.usage.lock() {
+            usage
+                .entry(key.to_string())
+                .and_modify(|entry| {
+                    entry.stored_at = stored_at;
+                    entry.ttl = ttl;
+                })
+                .or_insert(EntryUsage {
+                    stored_at,
+                    ttl,
+                    hits: 0,
+                });
+        }
+    }
+
+    fn record_hit(&self, key: &str) {
+        if let Ok(mut usage) = self.usage.lock()
+            && let Some(entry) = usage.get_mut(key)
+        {
+            entry.hits += 1;
+        }
+    }
+
+    /// Up to `limit` cached keys, most hit first
+    pub fn hot_keys(&self, limit: usize) -> Vec<String> {
+        let Ok(usage) = self.usage.lock() else {
+            return Vec::new();
+        };
+        let mut keys: Vec<(&String, u64)> = usage
+            .iter()
+            .filter(|(_, entry)| entry.hits > 0)
+            .map(|(key, entry)| (key, entry.hits))
+            .collect();
+        keys.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        keys.into_iter()
+            .take(limit)
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    /// When the entry for `key` expires, or `None` if it never does
+    fn expires_at(&self, key: &str) -> Option<SystemTime> {
+        let usage = *self.usage.lock().ok()?.get(key)?;
+        let cache_ttl = (self.ttl_seconds > 0).then(|| Duration::from_secs(self.ttl_seconds));
+        let ttl = match (usage.ttl, cache_ttl) {
+            (Some(entry_ttl), Some(cache_ttl)) => entry_ttl.min(cache_ttl),
+            (entry_ttl, cache_ttl) => entry_ttl.or(cache_ttl)?,
+        };
+        Some(usage.stored_at + ttl)
+    }
+
+    /// Insert a value restored from a snapshot, keeping its original expiry
+    ///
+    /// Returns false (and inserts nothing) if the entry has already expired.
+    pub async fn restore(
+        &self,
+        key: String,
+        value: T,
+        expires_at: Option<SystemTime>,
+        tags: &[String],
+    ) -> bool {
+        let remaining = match expires_at {
+            Some(expires_at) => match expires_at.duration_since(SystemTime::now()) {
+                Ok(remaining) if !remaining.is_zero() => Some(remaining),
+                _ => return false,
+            },
+            None => None,
+        };
+
+        match remaining {
+            Some(remaining) => self.insert_with_ttl(key, value, remaining, tags).await,
+            None => self.insert_with_tags(key, value, tags).await,
+        }
+        true
+    }
+
+    /// The cached "not found" error for `key`, if any was cached as an `E`
+    pub fn not_found_error<E: FetchError>(&self, key: &str) -> Option<E> {
+        let error = self.not_found.as_ref()?.get(key)?;
+        error.downcast_ref::<E>().map(E::duplicate)
+    }
+
+    /// Remember that the upstream has no resource for `key`
+    ///
+    /// Does nothing unless the resource type has a `not_found_ttl_seconds`.
+    fn remember_not_found<E: FetchError>(&self, key: &str, error: &E) {
+        if let Some(not_found) = &self.not_found {
+            not_found.insert(key.to_string(), Arc::new(error.duplicate()));
+        }
+    }
+
+    /// Stop serving `key` stale, e.g. once the upstream reports it gone
+    fn forget_stale(&self, key: &str) {
+        if let Some(stale) = &self.stale {
+            stale.invalidate(key);
+        }
+    }
+
+    fn forget_not_found(&self, key: &str) {
+        if let Some(not_found) = &self.not_found {
+            not_found.invalidate(key);
+        }
+    }
+
+    fn remember(&self, key: &str, value: &T) {
+        if let Some(stale) = &self.stale {
+            stale.insert(
+                key.to_string(),
+                StaleEntry {
+                    value: value.clone(),
+                    stored_at: Instant::now(),
+                },
+            );
+        }
+    }
+
+    /// The last good value for `key` if it expired less than `window_seconds` ago
+    fn stale_value(&self, key: &str, window_seconds: u64) -> Option<T> {
+        if window_seconds == 0 || self.ttl_seconds == 0 {
+            return None;
+        }
+
+        let entry = self.stale.as_ref()?.get(key)?;
+        let max_age = Duration::from_secs(self.ttl_seconds + window_seconds);
+        (entry.stored_at.elapsed() < max_age).then_some(entry.value)
+    }
+
+    fn stale_window(&self, reason: StaleReason) -> u64 {
+        match reason {
+            StaleReason::Revalidate => self.stale_while_revalidate_seconds,
+            StaleReason::Error => self.stale_if_error_seconds,
+        }
+    }
+}
+
+/// Type-erased operations on a registered resource cache
+///
+/// The registry stores caches of different resource types side by side; this
+/// trait lets callers that don't know `T` (stats, metrics, admin endpoints)
+/// inspect and purge them.
+#[async_trait]
+pub trait ResourceCacheOps: Debug + Send + Sync {
+    /// Access the concrete `ResourceCache<T>` for downcasting
+    fn as_any(&self) -> &dyn Any;
+
+    /// Number of entries currently in the cache
+    fn entry_count(&self) -> u64;
+
+    /// Number of entries tracked by the eviction listener
+    fn active_entries(&self) -> u64;
+
+    /// The effective settings the cache was registered with
+    fn settings(&self) -> ResourceCacheConfig;
+
+    /// Approximate bytes held, for caches sized by weight
+    fn bytes_used(&self) -> Option<u64>;
+
+    /// The cached value for `key` as JSON, if present
+    async fn get_json(&self, key: &str) -> Option<serde_json::Value>;
+
+    /// Remove a single entry, returning whether it was present
+    async fn remove(&self, key: &str) -> bool;
+
+    /// Remove every entry, returning how many were present
+    async fn clear(&self) -> u64;
+
+    /// Remove every entry tagged `tag`, returning how many were present
+    async fn invalidate_tag(&self, tag: &str) -> u64;
+
+    /// Remove every entry whose key starts with `prefix`, returning how many were present
+    async fn invalidate_prefix(&self, prefix: &str) -> u64;
+
+    /// Every live entry as JSON, plus up to `hot_keys` of the most hit keys
+    async fn snapshot(&self, hot_keys: usize) -> ResourceSnapshot;
+
+    /// Insert the unexpired entries of a snapshot, returning how many were restored
+    async fn restore(&self, snapshot: &ResourceSnapshot) -> usize;
+}
+
+#[async_trait]
+impl<T: ApiResource> ResourceCacheOps for ResourceCache<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn entry_count(&self) -> u64 {
+        self.cache.entry_count()
+    }
+
+    fn active_entries(&self) -> u64 {
+        self.active_entries.load(Ordering::Relaxed)
+    }
+
+    fn settings(&self) -> ResourceCacheConfig {
+        ResourceCacheConfig {
+            ttl_seconds: Some(self.ttl_seconds),
+            tti_seconds: Some(self.tti_seconds),
+            max_capacity: Some(self.max_capacity),
+            stale_while_revalidate_seconds: Some(self.stale_while_revalidate_seconds),
+            stale_if_error_seconds: Some(self.stale_if_error_seconds),
+            not_found_ttl_seconds: Some(self.not_found_ttl_seconds),
+            max_bytes: Some(self.max_bytes),
+            budget_weight: None,
+        }
+    }
+
+    fn bytes_used(&self) -> Option<u64> {
+        (self.max_bytes > 0).then(|| {
+            let stale = self.stale.as_ref().map_or(0, |stale| stale.weighted_size());
+            self.cache.weighted_size() + stale
+        })
+    }
+
+    async fn get_json(&self, key: &str) -> Option<serde_json::Value> {
+        let value = self.cache.get(key).await?;
+        serde_json::to_value(value).ok()
+    }
+
+    async fn remove(&self, key: &str) -> bool {
+        self.forget_not_found(key);
+        self.cache.remove(key).await.is_some()
+    }
+
+    async fn clear(&self) -> u64 {
+        // Apply pending writes first so the count reflects every entry
+        self.cache.run_pending_tasks().await;
+        let cleared = self.cache.entry_count();
+
+        self.cache.invalidate_all();
+        if let Some(stale) = &self.stale {
+            stale.invalidate_all();
+        }
+        if let Some(not_found) = &self.not_found {
+            not_found.invalidate_all();
+        }
+        if let Ok(mut index) = self.tags.lock() {
+            index.clear();
+        }
+        self.cache.run_pending_tasks().await;
+
+        cleared
+    }
+
+    async fn invalidate_tag(&self, tag: &str) -> u64 {
+        let keys = match self.tags.lock() {
+            Ok(index) => index.keys(tag),
+            Err(_) => return 0,
+        };
+
+        let mut removed = 0;
+        for key in keys {
+            // The eviction listener drops the key's tags and stale value
+            if self.cache.remove(&key).await.is_some() {
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    async fn invalidate_prefix(&self, prefix: &str) -> u64 {
+        let keys: Vec<Arc<String>> = self
+            .cache
+            .iter()
+            .map(|(key, _)| key)
+            .filter(|key| key.starts_with(prefix))
+            .collect();
+
+        if let Some(not_found) = &self.not_found {
+            for (key, _) in not_found.iter().filter(|(key, _)| key.starts_with(prefix)) {
+                not_found.invalidate(key.as_str());
+            }
+        }
+
+        let mut removed = 0;
+        for key in keys {
+            if self.cache.remove(key.as_str()).await.is_some() {
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    async fn snapshot(&self, hot_keys: usize) -> ResourceSnapshot {
+        let entries = self
+            .cache
+            .iter()
+            .filter_map(|(key, value)| {
+                Some(SnapshotEntry {
+                    value: serde_json::to_value(value).ok()?,
+                    expires_at: self.expires_at(&key).map(DateTime::<Utc>::from),
+                    tags: self.tags(&key),
+                    key: key.to_string(),
+                })
+            })
+            .collect();
+
+        ResourceSnapshot {
+            entries,
+            hot_keys: self.hot_keys(hot_keys),
+        }
+    }
+
+    async fn restore(&self, snapshot: &ResourceSnapshot) -> usize {
+        let mut restored = 0;
+        for entry in &snapshot.entries {
+            let value: T = match serde_json::from_value(entry.value.clone()) {
+                Ok(value) => value,
+                Err(e) => {
+                    warn!(
+                        "⚠️ Skipping {} snapshot entry {}: {}",
+                        self.resource_type, entry.key, e
+                    );
+                    continue;
+                }
+            };
+            let expires_at = entry.expires_at.map(SystemTime::from);
+            if ResourceCache::restore(self, entry.key.clone(), value, expires_at, &entry.tags).await
+            {
+                restored += 1;
+            }
+        }
+        restored
+    }
+}
+
+/// A type-erased function that loads one resource into the registry by ID
+type LoaderFn =
+    Arc<dyn Fn(CacheRegistry, String) -> BoxFuture<'static, Result<(), String>> + Send + Sync>;
+
+/// Loader registered for a resource type, used to pre-warm its cache
+#[derive(Clone)]
+struct ResourceLoader(LoaderFn);
+
+impl Debug for ResourceLoader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ResourceLoader")
+    }
+}
+
+/// Result of pre-warming a single ID
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WarmResult {
+    pub id: String,
+    pub loaded: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 /// Cache registry to store caches for different resource types
 #[derive(Debug, Clone)]
 pub struct CacheRegistry {
     // Use RwLock to allow concurrent reads but exclusive writes
-    caches: Arc<RwLock<HashMap<String, Box<dyn Any + Send + Sync>>>>,
+    caches: Arc<RwLock<HashMap<String, Arc<dyn ResourceCacheOps>>>>,
+    // Loaders used to pre-warm caches, keyed by resource type
+    loaders: Arc<RwLock<HashMap<String, ResourceLoader>>>,
     pub enabled: bool,
     pub ttl_seconds: u64,
     pub max_capacity: u64,
+    /// Memory budget in bytes shared by all caches (0 = caches sized by entry count)
+    pub max_bytes: u64,
     pub creation_time: SystemTime,
+    // Per-resource overrides from `cache.resources`, applied on registration
+    resource_configs: Arc<HashMap<String, ResourceCacheConfig>>,
+    // Bytes granted to each resource type out of `max_bytes`
+    byte_allocations: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl CacheRegistry {
+    /// Attach per-resource overrides (usually `cache.resources` from the config)
+    pub fn with_resource_configs(mut self, configs: HashMap<String, ResourceCacheConfig>) -> Self {
+        self.resource_configs = Arc::new(configs);
+        self
+    }
+
+    /// Share a memory budget of `max_bytes` across resource caches (0 = no budget)
+    pub fn with_memory_budget(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Reserve the byte budget for a resource type about to be registered
+    ///
+    /// Without a global budget this is the type's own `max_bytes`. Otherwise the
+    /// budget is split by `budget_weight` across every configured or registered
+    /// type, capped by the type's `max_bytes` and by what other types have not
+    /// already reserved. Returns 0 when the cache should be sized by entry count.
+    fn allocate_bytes(&self, resource_type: &str, settings: &ResourceCacheConfig) -> u64 {
+        let own_limit = settings.max_bytes.unwrap_or(0);
+        if self.max_bytes == 0 {
+            return own_limit;
+        }
+
+        let Ok(mut allocations) = self.byte_allocations.lock() else {
+            return own_limit;
+        };
+
+        let weight_of = |resource_type: &str| {
+            self.resource_configs
+                .get(resource_type)
+                .and_then(|config| config.budget_weight)
+                .unwrap_or(1)
+                .max(1) as u64
+        };
+        let mut resource_types: HashSet<&str> =
+            self.resource_configs.keys().map(String::as_str).collect();
+        resource_types.extend(allocations.keys().map(String::as_str));
+        resource_types.insert(resource_type);
+        let total_weight: u64 = resource_types.iter().map(|rt| weight_of(rt)).sum();
+
+        let weight = settings.budget_weight.unwrap_or(1).max(1) as u64;
+        let share = self.max_bytes.saturating_mul(weight) / total_weight.max(1);
+        let reserved: u64 = allocations
+            .iter()
+            .filter(|(rt, _)| rt.as_str() != resource_type)
+            .map(|(_, bytes)| bytes)
+            .sum();
+
+        let mut granted = share.min(self.max_bytes.saturating_sub(reserved));
+        if own_limit > 0 {
+            granted = granted.min(own_limit);
+        }
+        if granted == 0 {
+            warn!(
+                "⚠️ Cache memory budget exhausted, {} gets the minimum of 1 byte",
+                resource_type
+            );
+            granted = 1;
+        }
+
+        allocations.insert(resource_type.to_string(), granted);
+        granted
+    }
+
+    /// Effective settings for a resource type
+    ///
+    /// Configured overrides win over `settings` passed in code, which in turn win
+    /// over the registry-wide TTL and capacity.
+    pub fn resource_config(
+        &self,
+        resource_type: &str,
+        settings: ResourceCacheConfig,
+    ) -> ResourceCacheConfig {
+        let configured = self
+            .resource_configs
+            .get(resource_type)
+            .copied()
+            .unwrap_or_default();
+
+        configured.or(settings).or(ResourceCacheConfig {
+            ttl_seconds: Some(self.ttl_seconds),
+            tti_seconds: Some(0),
+            max_capacity: Some(self.max_capacity),
+            stale_while_revalidate_seconds: Some(0),
+            stale_if_error_seconds: Some(0),
+            not_found_ttl_seconds: Some(0),
+            max_bytes: Some(0),
+            budget_weight: Some(1),
+        })
+    }
+
+    /// Whether a cache has been registered for the resource type
+    pub fn is_registered(&self, resource_type: &str) -> bool {
+        self.caches
+            .read()
+            .map(|caches| caches.contains_key(resource_type))
+            .unwrap_or(false)
+    }
+
+    /// All registered resource types, sorted by name
+    pub fn resource_types(&self) -> Vec<String> {
+        let mut resource_types: Vec<String> = self
+            .caches
+            .read()
+            .map(|caches| caches.keys().cloned().collect())
+            .unwrap_or_default();
+        resource_types.sort();
+        resource_types
+    }
+
+    /// The type-erased cache for a resource type
+    pub fn resource_cache_ops(&self, resource_type: &str) -> Option<Arc<dyn ResourceCacheOps>> {
+        self.caches.read().ok()?.get(resource_type).cloned()
+    }
+
+    /// Whether a loader has been registered for the resource type
+    pub fn has_loader(&self, resource_type: &str) -> bool {
+        self.loaders
+            .read()
+            .map(|loaders| loaders.contains_key(resource_type))
+            .unwrap_or(false)
+    }
+
+    /// Load `ids` into the cache for `resource_type` using its registered loader
+    ///
+    /// IDs already cached are left as they are. At most `concurrency` loads run
+    /// at once; the result for each ID is returned in the order given.
+    pub async fn warm(
+        &self,
+        resource_type: &str,
+        ids: Vec<String>,
+        concurrency: usize,
+    ) -> Result<Vec<WarmResult>, String> {
+        let loader = self
+            .loaders
+            .read()
+            .map_err(|_| "Failed to acquire read lock on cache loaders".to_string())?
+            .get(resource_type)
+            .cloned()
+            .ok_or_else(|| format!("No loader registered for resource type: {}", resource_type))?;
+
+        let results = stream::iter(ids)
+            .map(|id| {
+                let load = (loader.0)(self.clone(), id.clone());
+                async move {
+                    match load.await {
+                        Ok(_) => WarmResult {
+                            id,
+                            loaded: true,
+                            error: None,
+                        },
+                        Err(e) => WarmResult {
+                            id,
+                            loaded: false,
+                            error: Some(e),
+                        },
+                    }
+                }
+            })
+            .buffered(concurrency.max(1))
+            .collect::<Vec<_>>()
+            .await;
+
+        let loaded = results.iter().filter(|r| r.loaded).count();
+        info!(
+            "🔥 Warmed {} cache: {}/{} IDs loaded",
+            resource_type,
+            loaded,
+            results.len()
+        );
+
+        Ok(results)
+    }
+
+    /// Every registered cache, for operations that span resource types
+    fn all_resource_cache_ops(&self) -> Vec<(String, Arc<dyn ResourceCacheOps>)> {
+        self.caches
+            .read()
+            .map(|caches| {
+                caches
+                    .iter()
+                    .map(|(resource_type, cache)| (resource_type.clone(), cache.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Remove a single `resource_type` entry
+    ///
+    /// Returns whether an entry was removed (false if the type is not registered).
+    pub async fn invalidate_key(&self, resource_type: &str, key: &str) -> bool {
+        let Some(cache) = self.resource_cache_ops(resource_type) else {
+            return false;
+        };
+        let removed = cache.remove(key).await;
+        record_invalidation(resource_type, "key", removed as u64);
+        debug!("🧹 Invalidated {} cache entry {}", resource_type, key);
+        removed
+    }
+
+    /// Remove every entry tagged `tag`, across all resource types
+    ///
+    /// Returns the number of entries removed.
+    pub async fn invalidate_tag(&self, tag: &str) -> u64 {
+        let mut removed = 0;
+        for (resource_type, cache) in self.all_resource_cache_ops() {
+            let count = cache.invalidate_tag(tag).await;
+            record_invalidation(&resource_type, "tag", count);
+            removed += count;
+        }
+        info!("🏷️ Invalidated {} cache entries tagged {}", removed, tag);
+        removed
+    }
+
+    /// Remove every `resource_type` entry whose ID starts with `prefix`
+    ///
+    /// Returns the number of entries removed (0 if the type is not registered).
+    pub async fn invalidate_prefix(&self, resource_type: &str, prefix: &str) -> u64 {
+        let Some(cache) = self.resource_cache_ops(resource_type) else {
+            return 0;
+        };
+        let removed = cache.invalidate_prefix(prefix).await;
+        record_invalidation(resource_type, "prefix", removed);
+        info!(
+            "🧹 Invalidated {} {} cache entries with prefix '{}'",
+            removed, resource_type, prefix
+        );
+        removed
+    }
+
+    /// Remove every entry of `resource_type`
+    ///
+    /// Returns the number of entries removed (0 if the type is not registered).
+    pub async fn invalidate_all(&self, resource_type: &str) -> u64 {
+        let Some(cache) = self.resource_cache_ops(resource_type) else {
+            return 0;
+        };
+        let removed = cache.clear().await;
+        record_invalidation(resource_type, "all", removed);
+        info!(
+            "🧹 Invalidated all {} {} cache entries",
+            removed, resource_type
+        );
+        removed
+    }
+}
+
+fn record_invalidation(resource_type: &str, kind: &'static str, count: u64) {
+    counter!("cache_invalidations_total", "resource_type" => resource_type.to_string(), "kind" => kind)
+        .increment(count);
 }
 
 /// Cache statistics
@@ -55,17 +878,58 @@ pub struct CacheStats {
 pub fn init_cache_registry(enabled: bool, max_capacity: u64, ttl_seconds: u64) -> CacheRegistry {
     CacheRegistry {
         caches: Arc::new(RwLock::new(HashMap::new())),
+        loaders: Arc::new(RwLock::new(HashMap::new())),
         enabled,
         ttl_seconds,
         max_capacity,
+        max_bytes: 0,
         creation_time: SystemTime::now(),
+        resource_configs: Arc::new(HashMap::new()),
+        byte_allocations: Arc::new(Mutex::new(HashMap::new())),
     }
 }
 
+/// Counts bytes written through it, to size values without buffering them
+struct ByteCounter(usize);
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Approximate memory weight of a cache entry: its key plus its JSON serialization
+fn entry_weight<T: Serialize>(key: &str, value: &T) -> u32 {
+    let mut counter = ByteCounter(key.len());
+    let _ = serde_json::to_writer(&mut counter, value);
+    u32::try_from(counter.0).unwrap_or(u32::MAX)
+}
+
 /// Register a new resource type with the cache registry
 pub fn register_resource_cache<T: ApiResource + 'static>(
     registry: &CacheRegistry,
     resource_type: &str,
+) -> Result<(), String> {
+    register_resource_cache_with_config::<T>(
+        registry,
+        resource_type,
+        ResourceCacheConfig::default(),
+    )
+}
+
+/// Register a new resource type with its own TTL, TTI and capacity
+///
+/// Unset fields in `settings` use any `cache.resources.<type>` override and then
+/// the registry defaults. A TTL or TTI of 0 disables that expiry.
+pub fn register_resource_cache_with_config<T: ApiResource + 'static>(
+    registry: &CacheRegistry,
+    resource_type: &str,
+    settings: ResourceCacheConfig,
 ) -> Result<(), String> {
     if !registry.enabled {
         debug!(
@@ -75,21 +939,73 @@ pub fn register_resource_cache<T: ApiResource + 'static>(
         return Ok(());
     }
 
-    let ttl = Duration::from_secs(registry.ttl_seconds);
+    let settings = registry.resource_config(resource_type, settings);
+    let ttl_seconds = settings.ttl_seconds.unwrap_or_default();
+    let tti_seconds = settings.tti_seconds.unwrap_or_default();
+    let max_capacity = settings.max_capacity.unwrap_or(registry.max_capacity);
+    let stale_while_revalidate_seconds = settings.stale_while_revalidate_seconds.unwrap_or(0);
+    let stale_if_error_seconds = settings.stale_if_error_seconds.unwrap_or(0);
+    let not_found_ttl_seconds = settings.not_found_ttl_seconds.unwrap_or(0);
+    let max_bytes = registry.allocate_bytes(resource_type, &settings);
     let resource_type_clone = resource_type.to_string();
 
+    // Stale windows extend past the TTL, so they need a TTL to be measured from
+    let stale_window = stale_while_revalidate_seconds.max(stale_if_error_seconds);
+    let keeps_stale = ttl_seconds > 0 && stale_window > 0;
+    // Stale copies duplicate the fresh entries, so the two share the byte budget
+    let stale_bytes = if keeps_stale { max_bytes / 2 } else { 0 };
+    let fresh_bytes = max_bytes - stale_bytes;
+    let stale = keeps_stale.then(|| {
+        let builder = moka::sync::Cache::builder()
+            .time_to_live(Duration::from_secs(ttl_seconds + stale_window));
+        if max_bytes > 0 {
+            builder
+                .weigher(|key: &String, entry: &StaleEntry<T>| entry_weight(key, &entry.value))
+                .max_capacity(stale_bytes)
+                .build()
+        } else {
+            builder.max_capacity(max_capacity).build()
+        }
+    });
+    let stale_clone: Option<moka::sync::Cache<String, StaleEntry<T>>> = stale.clone();
+
+    // Negative entries get their own, usually much shorter, TTL
+    let not_found = (not_found_ttl_seconds > 0).then(|| {
+        moka::sync::Cache::builder()
+            .max_capacity(max_capacity)
+            .time_to_live(Duration::from_secs(not_found_ttl_seconds))
+            .build()
+    });
+
     // Create a ResourceCache that we'll box and store
     // This allows us to access it directly later for the eviction listener
     let active_entries = Arc::new(AtomicU64::new(0));
     let active_entries_clone = active_entries.clone();
+    let tags = Arc::new(Mutex::new(TagIndex::default()));
+    let tags_clone = tags.clone();
+    let usage = Arc::new(Mutex::new(HashMap::new()));
+    let usage_clone: Arc<Mutex<HashMap<String, EntryUsage>>> = usage.clone();
+    let entry_ttls = Arc::new(Mutex::new(HashMap::new()));
 
     // Create the cache with eviction listener
-    let cache_builder = Cache::builder()
-        .max_capacity(registry.max_capacity)
-        .time_to_live(ttl)
-        .time_to_idle(ttl.mul_f32(1.5))
+    let mut cache_builder = Cache::builder()
+        .max_capacity(if max_bytes > 0 {
+            fresh_bytes
+        } else {
+            max_capacity
+        })
         .initial_capacity(100)
-        .eviction_listener(move |_key, _value, cause| {
+        .eviction_listener(move |key: Arc<String>, _value, cause| {
+            // Replaced entries keep the tags and usage recorded for the new value
+            if cause != moka::notification::RemovalCause::Replaced {
+                if let Ok(mut index) = tags_clone.lock() {
+                    index.remove_key(key.as_str());
+                }
+                if let Ok(mut usage) = usage_clone.lock() {
+                    usage.remove(key.as_str());
+                }
+            }
+
             // Track cache evictions in metrics and update counter
             match cause {
                 moka::notification::RemovalCause::Expired => {
@@ -110,6 +1026,10 @@ pub fn register_resource_cache<T: ApiResource + 'static>(
                         "❌ Cache entry explicitly removed for {}",
                         resource_type_clone
                     );
+                    // Invalidated entries must not be served stale later
+                    if let Some(stale) = &stale_clone {
+                        stale.invalidate(key.as_ref());
+                    }
                     // Decrement active entries counter - don't go below 0
                     let current = active_entries_clone.load(Ordering::Relaxed);
                     if current > 0 {
@@ -154,15 +1074,38 @@ pub fn register_resource_cache<T: ApiResource + 'static>(
                     }
                 }
             }
-        })
-        .build();
+        });
+
+    // Byte-sized caches weigh entries by their serialized size
+    if max_bytes > 0 {
+        cache_builder = cache_builder.weigher(|key: &String, value: &T| entry_weight(key, value));
+    }
+    // Entries written with their own TTL (e.g. restored from a snapshot) expire after it
+    cache_builder = cache_builder.expire_after(EntryExpiry(entry_ttls.clone()));
+    if ttl_seconds > 0 {
+        cache_builder = cache_builder.time_to_live(Duration::from_secs(ttl_seconds));
+    }
+    if tti_seconds > 0 {
+        cache_builder = cache_builder.time_to_idle(Duration::from_secs(tti_seconds));
+    }
 
     let resource_cache: ResourceCache<T> = ResourceCache {
-        cache: Arc::new(cache_builder),
+        cache: Arc::new(cache_builder.build()),
         creation_time: SystemTime::now(),
-        ttl_seconds: registry.ttl_seconds,
+        ttl_seconds,
+        tti_seconds,
+        max_capacity,
+        max_bytes,
+        stale_while_revalidate_seconds,
+        stale_if_error_seconds,
+        not_found_ttl_seconds,
         active_entries,
         resource_type: resource_type.to_string(),
+        stale,
+        not_found,
+        tags,
+        usage,
+        entry_ttls,
     };
 
     // Attempt to insert the cache into the registry
@@ -173,10 +1116,61 @@ pub fn register_resource_cache<T: ApiResource + 'static>(
 
     caches.insert(
         resource_type.to_string(),
-        Box::new(resource_cache) as Box<dyn Any + Send + Sync>,
+        Arc::new(resource_cache) as Arc<dyn ResourceCacheOps>,
     );
 
-    info!("✅ Registered cache for resource type: {}", resource_type);
+    if max_bytes > 0 {
+        info!(
+            "✅ Registered cache for resource type: {} (ttl: {}s, tti: {}s, max bytes: {})",
+            resource_type, ttl_seconds, tti_seconds, max_bytes
+        );
+    } else {
+        info!(
+            "✅ Registered cache for resource type: {} (ttl: {}s, tti: {}s, capacity: {})",
+            resource_type, ttl_seconds, tti_seconds, max_capacity
+        );
+    }
+    Ok(())
+}
+
+/// Register the function used to load a resource by ID when pre-warming its cache
+///
+/// Loaded values go through [`get_or_fetch_revalidating`], so they are stored
+/// with the resource type's cache settings and share in-flight fetches.
+pub fn register_resource_loader<T, F, Fut>(
+    registry: &CacheRegistry,
+    resource_type: &str,
+    loader: F,
+) -> Result<(), String>
+where
+    T: ApiResource + 'static,
+    F: Fn(String) -> Fut + Send + Sync + 'static,
+    Fut: std::future::Future<Output = Result<T, AppError>> + Send + 'static,
+{
+    let loader = Arc::new(loader);
+    let resource_type_owned = resource_type.to_string();
+    let load: LoaderFn = Arc::new(move |registry: CacheRegistry, id: String| {
+        let loader = loader.clone();
+        let resource_type = resource_type_owned.clone();
+        Box::pin(async move {
+            let fetch = loader(id.clone());
+            get_or_fetch_revalidating::<T, _, _, _>(&registry, &resource_type, &id, move || fetch)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    });
+
+    let mut loaders = match registry.loaders.write() {
+        Ok(loaders) => loaders,
+        Err(_) => return Err("Failed to acquire write lock on cache loaders".to_string()),
+    };
+    loaders.insert(resource_type.to_string(), ResourceLoader(load));
+
+    debug!(
+        "Registered cache loader for resource type: {}",
+        resource_type
+    );
     Ok(())
 }
 
@@ -200,14 +1194,25 @@ pub fn get_resource_cache<T: ApiResource + 'static>(
     match caches.get(resource_type) {
         Some(cache) => {
             // Attempt to downcast to the appropriate ResourceCache type
-            if let Some(boxed_cache) = cache.downcast_ref::<ResourceCache<T>>() {
+            if let Some(boxed_cache) = cache.as_any().downcast_ref::<ResourceCache<T>>() {
                 // Clone the ResourceCache
                 Some(ResourceCache {
                     cache: boxed_cache.cache.clone(),
                     creation_time: boxed_cache.creation_time,
                     ttl_seconds: boxed_cache.ttl_seconds,
+                    tti_seconds: boxed_cache.tti_seconds,
+                    max_capacity: boxed_cache.max_capacity,
+                    max_bytes: boxed_cache.max_bytes,
+                    stale_while_revalidate_seconds: boxed_cache.stale_while_revalidate_seconds,
+                    stale_if_error_seconds: boxed_cache.stale_if_error_seconds,
+                    not_found_ttl_seconds: boxed_cache.not_found_ttl_seconds,
                     active_entries: boxed_cache.active_entries.clone(),
                     resource_type: boxed_cache.resource_type.clone(),
+                    stale: boxed_cache.stale.clone(),
+                    not_found: boxed_cache.not_found.clone(),
+                    tags: boxed_cache.tags.clone(),
+                    usage: boxed_cache.usage.clone(),
+                    entry_ttls: boxed_cache.entry_ttls.clone(),
                 })
             } else {
                 debug!(
@@ -248,21 +1253,9 @@ pub fn get_cache_stats_with_metrics(
     };
 
     // Look for the cache by resource type
-    if let Some(cache_box) = caches.get(resource_type) {
-        // Try some common resource types by using downcast_ref
-        // This is a bit of a hack, but it's the most direct way to get the entry count
-        // without an abstraction layer that covers all potential cache types
-        if let Some(pet_cache) = cache_box
-            .downcast_ref::<ResourceCache<crate::generated_apis::petstore_api::models::Upet>>()
-        {
-            actual_size = pet_cache.cache.entry_count();
-            debug!(
-                "Found pet cache size for {}: {}",
-                resource_type, actual_size
-            );
-        }
-        // Add other resource types as needed
-        // if let Some(...) = cache_box.downcast_ref::<ResourceCache<OtherType>>() { ... }
+    if let Some(cache) = caches.get(resource_type) {
+        actual_size = cache.entry_count();
+        debug!("Found cache size for {}: {}", resource_type, actual_size);
     }
 
     // Parse metrics text to extract hit and miss counts for this resource type
@@ -339,40 +1332,91 @@ pub fn get_cache_stats_with_metrics(
     })
 }
 
-// Helper function to see if the last fetch was from cache
-thread_local! {
-    static LAST_FETCH_FROM_CACHE: RefCell<bool> = const { RefCell::new(false) };
+/// How a cached lookup was answered
+///
+/// Returned alongside the value by the `get_or_fetch` family so callers can
+/// attribute responses to the cache without relying on task-local state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheOutcome {
+    /// Served from a fresh cache entry
+    Hit,
+    /// Fetched from the source (possibly by a concurrent caller) and stored
+    Miss,
+    /// Served from an expired entry within a stale-while-revalidate or
+    /// stale-if-error window
+    Stale,
+    /// The cache was not consulted (disabled or resource type not registered)
+    Bypass,
 }
 
-/// Get whether the last fetch operation was from cache
-pub fn last_fetch_from_cache() -> bool {
-    let mut result = false;
-    LAST_FETCH_FROM_CACHE.with(|cell| {
-        result = *cell.borrow();
-    });
-    result
+impl CacheOutcome {
+    /// Lowercase name used for metric labels
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CacheOutcome::Hit => "hit",
+            CacheOutcome::Miss => "miss",
+            CacheOutcome::Stale => "stale",
+            CacheOutcome::Bypass => "bypass",
+        }
+    }
+
+    /// Value for the conventional `X-Cache` response header
+    pub fn x_cache(&self) -> &'static str {
+        match self {
+            CacheOutcome::Hit => "HIT",
+            CacheOutcome::Miss => "MISS",
+            CacheOutcome::Stale => "STALE",
+            CacheOutcome::Bypass => "BYPASS",
+        }
+    }
+
+    /// Whether the value came from the cache rather than the source
+    pub fn is_from_cache(&self) -> bool {
+        matches!(self, CacheOutcome::Hit | CacheOutcome::Stale)
+    }
+
+    /// Value for the RFC 9211 `Cache-Status` response header
+    pub fn cache_status(&self, cache_name: &str) -> String {
+        let params = match self {
+            CacheOutcome::Hit => "hit",
+            CacheOutcome::Miss => "fwd=miss; stored",
+            CacheOutcome::Stale => "hit; detail=stale",
+            CacheOutcome::Bypass => "fwd=bypass",
+        };
+        format!("{}; {}", cache_name, params)
+    }
 }
 
 /// Generic function to get or fetch a resource from cache
-pub async fn get_or_fetch<T, F, Fut>(
+///
+/// Returns the resource together with the [`CacheOutcome`] that produced it.
+///
+/// Concurrent misses for the same key are coalesced into a single call to
+/// `fetch_fn`; the other callers wait for and share its result. If the fetch
+/// fails and the resource type has a `stale_if_error_seconds` window, the last
+/// good value is served instead of the error. If the fetch reports "not found"
+/// (see [`FetchError::is_not_found`]) and the resource type has a
+/// `not_found_ttl_seconds`, that error is cached and returned without fetching
+/// until it expires or the resource is written.
+///
+/// Stale-while-revalidate needs a fetch that can run in the background; use
+/// [`get_or_fetch_revalidating`] for that.
+pub async fn get_or_fetch<T, E, F, Fut>(
     registry: &CacheRegistry,
     resource_type: &str,
     id: &str,
     fetch_fn: F,
-) -> Result<T, String>
+) -> Result<(T, CacheOutcome), E>
 where
     T: ApiResource + 'static,
+    E: FetchError,
     F: FnOnce() -> Fut,
-    Fut: std::future::Future<Output = Result<T, String>>,
+    Fut: std::future::Future<Output = Result<T, E>>,
 {
-    // Reset the thread-local at the start of each fetch operation
-    LAST_FETCH_FROM_CACHE.with(|cell| {
-        *cell.borrow_mut() = false;
-    });
-
     if !registry.enabled {
         // Cache is disabled, call fetch function directly
-        return fetch_fn().await;
+        return Ok((fetch_fn().await?, CacheOutcome::Bypass));
     }
 
     // Get cache for this resource type
@@ -380,46 +1424,184 @@ where
         Some(cache) => cache,
         None => {
             debug!("No cache found for resource type: {}", resource_type);
-            return fetch_fn().await;
+            return Ok((fetch_fn().await?, CacheOutcome::Bypass));
         }
     };
 
+    if let Some(resource) = cached_value(&resource_cache, resource_type, id).await {
+        return Ok((resource, CacheOutcome::Hit));
+    }
+    if let Some(error) = cached_not_found(&resource_cache, resource_type, id) {
+        return Err(error);
+    }
+
+    fetch_coalesced(&resource_cache, resource_type, id, fetch_fn).await
+}
+
+/// Return a cached "not found" for `id`, recording a negative hit
+fn cached_not_found<T: ApiResource, E: FetchError>(
+    resource_cache: &ResourceCache<T>,
+    resource_type: &str,
+    id: &str,
+) -> Option<E> {
+    let error = resource_cache.not_found_error(id)?;
+    counter!("cache_negative_hits_total", "resource_type" => resource_type.to_string())
+        .increment(1);
+    debug!("🚫 Cached not found for {} ID: {}", resource_type, id);
+    Some(error)
+}
+
+/// Look up a fresh cached resource without fetching it
+///
+/// Hits are recorded like those of [`get_or_fetch`]; misses are not, since the
+/// caller is expected to fetch them through `get_or_fetch` afterwards.
+pub async fn get_cached<T: ApiResource + 'static>(
+    registry: &CacheRegistry,
+    resource_type: &str,
+    id: &str,
+) -> Option<T> {
+    if !registry.enabled {
+        return None;
+    }
+
+    let resource_cache = get_resource_cache::<T>(registry, resource_type)?;
+    cached_value(&resource_cache, resource_type, id).await
+}
+
+/// Like [`get_or_fetch`], but also serves stale values while revalidating
+///
+/// When an entry has expired less than `stale_while_revalidate_seconds` ago, the
+/// stale value is returned immediately and a single background task refreshes
+/// it. This requires `fetch_fn` to be `Send + 'static`.
+pub async fn get_or_fetch_revalidating<T, E, F, Fut>(
+    registry: &CacheRegistry,
+    resource_type: &str,
+    id: &str,
+    fetch_fn: F,
+) -> Result<(T, CacheOutcome), E>
+where
+    T: ApiResource + 'static,
+    E: FetchError,
+    F: FnOnce() -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = Result<T, E>> + Send + 'static,
+{
+    if !registry.enabled {
+        return Ok((fetch_fn().await?, CacheOutcome::Bypass));
+    }
+
+    let resource_cache = match get_resource_cache::<T>(registry, resource_type) {
+        Some(cache) => cache,
+        None => {
+            debug!("No cache found for resource type: {}", resource_type);
+            return Ok((fetch_fn().await?, CacheOutcome::Bypass));
+        }
+    };
+
+    if let Some(resource) = cached_value(&resource_cache, resource_type, id).await {
+        return Ok((resource, CacheOutcome::Hit));
+    }
+    if let Some(error) = cached_not_found(&resource_cache, resource_type, id) {
+        return Err(error);
+    }
+
+    if let Some(resource) = serve_stale(&resource_cache, resource_type, id, StaleReason::Revalidate)
+    {
+        // Refresh in the background; concurrent refreshes for the key are coalesced
+        let resource_type = resource_type.to_string();
+        let id = id.to_string();
+        tokio::spawn(async move {
+            counter!("cache_revalidations_total", "resource_type" => resource_type.clone())
+                .increment(1);
+            if let Err(e) = fetch_coalesced(&resource_cache, &resource_type, &id, fetch_fn).await {
+                debug!(
+                    "❌ Background refresh failed for {} ID: {}, error: {}",
+                    resource_type, id, e
+                );
+            }
+        });
+
+        return Ok((resource, CacheOutcome::Stale));
+    }
+
+    fetch_coalesced(&resource_cache, resource_type, id, fetch_fn).await
+}
+
+/// Look up a fresh entry, recording hit metrics
+async fn cached_value<T: ApiResource>(
+    resource_cache: &ResourceCache<T>,
+    resource_type: &str,
+    id: &str,
+) -> Option<T> {
     let cache = &resource_cache.cache;
 
     // Debug log the cache size at the start
-    let start_size = cache.entry_count();
     debug!(
         "🔍 Cache size before operation for {} ID {}: {}",
-        resource_type, id, start_size
+        resource_type,
+        id,
+        cache.entry_count()
     );
 
-    // Try to get from cache first
-    if let Some(resource) = cache.get(id).await {
-        counter!("cache_hits_total", "resource_type" => resource_type.to_string()).increment(1);
-        debug!("🔍 Cache hit for {} ID: {}", resource_type, id);
+    let resource = cache.get(id).await?;
+    resource_cache.record_hit(id);
+    counter!("cache_hits_total", "resource_type" => resource_type.to_string()).increment(1);
+    debug!("🔍 Cache hit for {} ID: {}", resource_type, id);
 
-        // Set the thread-local to indicate this was a cache hit
-        LAST_FETCH_FROM_CACHE.with(|cell| {
-            *cell.borrow_mut() = true;
-        });
+    // Update current size metric whenever we access the cache
+    let current_size = cache.entry_count();
+    let active_count = resource_cache.active_entries.load(Ordering::Relaxed);
 
-        // Update current size metric whenever we access the cache
-        let current_size = cache.entry_count();
-        let active_count = resource_cache.active_entries.load(Ordering::Relaxed);
+    gauge!("cache_current_size", "resource_type" => resource_type.to_string())
+        .set(current_size as f64);
+    gauge!("cache_active_entries", "resource_type" => resource_type.to_string())
+        .set(active_count as f64);
 
-        gauge!("cache_current_size", "resource_type" => resource_type.to_string())
-            .set(current_size as f64);
-        gauge!("cache_active_entries", "resource_type" => resource_type.to_string())
-            .set(active_count as f64);
+    debug!(
+        "📊 Cache size after hit for {} ID {}: {} (active: {})",
+        resource_type, id, current_size, active_count
+    );
 
-        debug!(
-            "📊 Cache size after hit for {} ID {}: {} (active: {})",
-            resource_type, id, current_size, active_count
-        );
+    Some(resource)
+}
 
-        return Ok(resource);
-    }
+/// Serve the last good value within the stale window for `reason`, if any
+fn serve_stale<T: ApiResource>(
+    resource_cache: &ResourceCache<T>,
+    resource_type: &str,
+    id: &str,
+    reason: StaleReason,
+) -> Option<T> {
+    let resource = resource_cache.stale_value(id, resource_cache.stale_window(reason))?;
+
+    counter!(
+        "cache_stale_served_total",
+        "resource_type" => resource_type.to_string(),
+        "reason" => reason.as_str()
+    )
+    .increment(1);
+    debug!(
+        "🕰️ Serving stale {} ID: {} ({})",
+        resource_type,
+        id,
+        reason.as_str()
+    );
 
+    Some(resource)
+}
+
+/// Fetch a missing entry, coalescing concurrent fetches for the same key
+async fn fetch_coalesced<T, E, F, Fut>(
+    resource_cache: &ResourceCache<T>,
+    resource_type: &str,
+    id: &str,
+    fetch_fn: F,
+) -> Result<(T, CacheOutcome), E>
+where
+    T: ApiResource + 'static,
+    E: FetchError,
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<T, E>>,
+{
     // Cache miss, fetch from source
     counter!("cache_misses_total", "resource_type" => resource_type.to_string()).increment(1);
     debug!(
@@ -427,12 +1609,20 @@ where
         resource_type, id
     );
 
-    // Fetch the resource
-    match fetch_fn().await {
-        Ok(resource) => {
-            // Store in cache
-            debug!("➕ About to add {} ID: {} to cache", resource_type, id);
-            cache.insert(id.to_string(), resource.clone()).await;
+    let cache = &resource_cache.cache;
+
+    // Only the first caller for a key runs `fetch_fn`; the rest wait for its result
+    let entry = cache
+        .entry(id.to_string())
+        .or_try_insert_with(fetch_fn())
+        .await;
+
+    match entry {
+        Ok(entry) if entry.is_fresh() => {
+            let resource = entry.into_value();
+            resource_cache.forget_not_found(id);
+            resource_cache.remember(id, &resource);
+            resource_cache.tag(id, &resource, &[]);
 
             // Increment our counters
             counter!("cache_entries_created", "resource_type" => resource_type.to_string())
@@ -445,27 +1635,106 @@ where
                 .set(current_size as f64);
             gauge!("cache_active_entries", "resource_type" => resource_type.to_string())
                 .set(new_count as f64);
-
-            debug!(
-                "📊 Cache entry count right after insertion: {} (active: {})",
-                current_size, new_count
-            );
+            if resource_cache.max_bytes > 0 {
+                gauge!("cache_bytes_used", "resource_type" => resource_type.to_string())
+                    .set(cache.weighted_size() as f64);
+            }
 
             debug!(
                 "➕ Added {} ID: {} to cache (current size: {}, active: {})",
                 resource_type, id, current_size, new_count
             );
 
-            Ok(resource)
+            Ok((resource, CacheOutcome::Miss))
+        }
+        Ok(entry) => {
+            // Another caller fetched the value while we waited
+            counter!("cache_coalesced_total", "resource_type" => resource_type.to_string())
+                .increment(1);
+            debug!("🔗 Coalesced fetch for {} ID: {}", resource_type, id);
+            Ok((entry.into_value(), CacheOutcome::Miss))
         }
         Err(e) => {
             debug!(
                 "❌ Failed to fetch {} ID: {}, error: {}",
                 resource_type, id, e
             );
-            Err(e)
+
+            // Callers coalesced onto this fetch share its error
+            let e = Arc::try_unwrap(e).unwrap_or_else(|shared| shared.duplicate());
+
+            // A resource the upstream no longer has must not be served stale
+            if e.is_not_found() {
+                resource_cache.forget_stale(id);
+                if resource_cache.not_found.is_some() {
+                    resource_cache.remember_not_found(id, &e);
+                    counter!("cache_negative_entries_created", "resource_type" => resource_type.to_string())
+                        .increment(1);
+                }
+                return Err(e);
+            }
+
+            match serve_stale(resource_cache, resource_type, id, StaleReason::Error) {
+                Some(resource) => Ok((resource, CacheOutcome::Stale)),
+                None => Err(e),
+            }
+        }
+    }
+}
+
+/// Get a resource through a `CacheProvider`, or fetch and store it on a miss
+///
+/// Used for remote providers (Redis, fallback) that are not backed by the
+/// registry. Cache errors are logged and treated as misses so a cache outage
+/// never fails the request.
+pub async fn get_or_fetch_with_provider<P, T, E, F, Fut>(
+    provider: &P,
+    resource_type: &str,
+    key: &str,
+    ttl_seconds: u64,
+    fetch_fn: F,
+) -> Result<(T, CacheOutcome), E>
+where
+    P: CacheProvider,
+    T: ApiResource + 'static,
+    E: FetchError,
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<T, E>>,
+{
+    match provider.get::<T>(key).await {
+        Ok(Some(resource)) => {
+            counter!("cache_hits_total", "resource_type" => resource_type.to_string()).increment(1);
+            debug!("🔍 Cache hit for {} key: {}", resource_type, key);
+            return Ok((resource, CacheOutcome::Hit));
+        }
+        Ok(None) => {}
+        Err(e) => warn!(
+            "⚠️ Cache read failed for {} key {}: {}",
+            resource_type, key, e
+        ),
+    }
+
+    counter!("cache_misses_total", "resource_type" => resource_type.to_string()).increment(1);
+    debug!(
+        "🔍 Cache miss for {} key: {}, fetching from source",
+        resource_type, key
+    );
+
+    let resource = fetch_fn().await?;
+
+    match provider.set(key, resource.clone(), ttl_seconds).await {
+        Ok(_) => {
+            counter!("cache_entries_created", "resource_type" => resource_type.to_string())
+                .increment(1);
+            debug!("➕ Added {} key: {} to cache", resource_type, key);
         }
+        Err(e) => warn!(
+            "⚠️ Cache write failed for {} key {}: {}",
+            resource_type, key, e
+        ),
     }
+
+    Ok((resource, CacheOutcome::Miss))
 }
 
 /// Start metrics updater to track cache stats for all resource types
@@ -493,35 +1762,26 @@ pub async fn start_metrics_updater(registry: &CacheRegistry) {
                 }
             };
 
-            for (resource_type, cache_box) in caches.iter() {
-                // Try to get the actual entry count from the cache
-
-                // This is a bit of a hack, but it gets the actual cache size
-                // Try to downcast to a known resource type
-                if let Some(pet_cache) = cache_box.downcast_ref::<ResourceCache<crate::generated_apis::petstore_api::models::Upet>>() {
-                    let current_size = pet_cache.cache.entry_count();
+            for (resource_type, cache) in caches.iter() {
+                // Update the metrics with the real cache size
+                let current_size = cache.entry_count();
+                gauge!("cache_current_size", "resource_type" => resource_type.to_string())
+                    .set(current_size as f64);
 
-                    // Update the metrics with the real cache size
-                    gauge!("cache_current_size", "resource_type" => resource_type.to_string())
-                        .set(current_size as f64);
+                // Get active entries from the atomic counter
+                let active_entries = cache.active_entries();
+                gauge!("cache_active_entries", "resource_type" => resource_type.to_string())
+                    .set(active_entries as f64);
 
-                    // Get active entries from the atomic counter
-                    let active_entries = pet_cache.active_entries.load(Ordering::Relaxed);
-                    gauge!("cache_active_entries", "resource_type" => resource_type.to_string())
-                        .set(active_entries as f64);
-
-                    debug!(
-                        "📊 Cache metrics updated for {} - size: {}, active: {}",
-                        resource_type, current_size, active_entries
-                    );
-                } else {
-                    // If we can't get the actual size, set to 0 to avoid showing incorrect data
-                    gauge!("cache_current_size", "resource_type" => resource_type.to_string()).set(0.0);
-                    debug!(
-                        "📊 Cache metrics reset to 0 for {} (couldn't get actual size)",
-                        resource_type
-                    );
+                if let Some(bytes_used) = cache.bytes_used() {
+                    gauge!("cache_bytes_used", "resource_type" => resource_type.to_string())
+                        .set(bytes_used as f64);
                 }
+
+                debug!(
+                    "📊 Cache metrics updated for {} - size: {}, active: {}",
+                    resource_type, current_size, active_entries
+                );
             }
         }
     });
@@ -667,20 +1927,17 @@ mod tests {
 
         // First call will fetch
         let result = get_or_fetch(&registry, "test_resource", "test-2", || async {
-            Ok(resource.clone())
+            Ok::<_, AppError>(resource.clone())
         })
         .await;
 
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), resource);
+        // First fetch wasn't from cache
+        assert_eq!(result.ok(), Some((resource.clone(), CacheOutcome::Miss)));
 
         // Second call should hit cache
-        let was_cached_before = last_fetch_from_cache();
-        assert!(!was_cached_before); // First fetch wasn't from cache
-
         let result2 = get_or_fetch(&registry, "test_resource", "test-2", || async {
             // This should not be called if cache hit
-            Ok(TestResource {
+            Ok::<_, AppError>(TestResource {
                 id: "test-2".to_string(),
                 name: "Different Name".to_string(),
                 value: 999,
@@ -688,12 +1945,8 @@ mod tests {
         })
         .await;
 
-        assert!(result2.is_ok());
-        assert_eq!(result2.unwrap(), resource); // Should be original resource
-
-        // Check if it was from cache
-        let was_cached = last_fetch_from_cache();
-        assert!(was_cached); // Second fetch should be from cache
+        // Should be the original resource, served from cache
+        assert_eq!(result2.ok(), Some((resource, CacheOutcome::Hit)));
     }
 
     #[tokio::test]
@@ -727,6 +1980,556 @@ mod tests {
         assert!(retrieved.is_none());
     }
 
+    #[tokio::test]
+    async fn test_register_resource_cache_with_config() {
+        let registry = init_cache_registry(true, 100, 3600);
+
+        let _ = register_resource_cache_with_config::<TestResource>(
+            &registry,
+            "test_resource",
+            ResourceCacheConfig {
+                ttl_seconds: Some(60),
+                tti_seconds: Some(30),
+                max_capacity: Some(10),
+                ..Default::default()
+            },
+        );
+
+        let cache = get_resource_cache::<TestResource>(&registry, "test_resource").unwrap();
+        assert_eq!(cache.ttl_seconds, 60);
+        assert_eq!(cache.tti_seconds, 30);
+        assert_eq!(cache.max_capacity, 10);
+        assert_eq!(
+            cache.cache.policy().time_to_live(),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            cache.cache.policy().time_to_idle(),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(cache.cache.policy().max_capacity(), Some(10));
+    }
+
+    #[tokio::test]
+    async fn test_resource_config_overrides_take_precedence() {
+        let mut overrides = HashMap::new();
+        overrides.insert(
+            "test_resource".to_string(),
+            ResourceCacheConfig {
+                ttl_seconds: Some(5),
+                ..Default::default()
+            },
+        );
+        let registry = init_cache_registry(true, 100, 3600).with_resource_configs(overrides);
+
+        // The configured TTL wins; capacity comes from code, TTI from the registry
+        let _ = register_resource_cache_with_config::<TestResource>(
+            &registry,
+            "test_resource",
+            ResourceCacheConfig {
+                ttl_seconds: Some(60),
+                max_capacity: Some(10),
+                ..Default::default()
+            },
+        );
+
+        let cache = get_resource_cache::<TestResource>(&registry, "test_resource").unwrap();
+        assert_eq!(cache.ttl_seconds, 5);
+        assert_eq!(cache.tti_seconds, 0);
+        assert_eq!(cache.max_capacity, 10);
+
+        // Other resource types keep the registry defaults
+        let _ = register_resource_cache::<TestResource>(&registry, "other_resource");
+        let other = get_resource_cache::<TestResource>(&registry, "other_resource").unwrap();
+        assert_eq!(other.ttl_seconds, 3600);
+        assert_eq!(other.max_capacity, 100);
+    }
+
+    #[tokio::test]
+    async fn test_zero_ttl_disables_expiry() {
+        let registry = init_cache_registry(true, 100, 1);
+
+        let _ = register_resource_cache_with_config::<TestResource>(
+            &registry,
+            "test_resource",
+            ResourceCacheConfig {
+                ttl_seconds: Some(0),
+                ..Default::default()
+            },
+        );
+
+        let cache = get_resource_cache::<TestResource>(&registry, "test_resource").unwrap();
+        assert_eq!(cache.cache.policy().time_to_live(), None);
+        assert_eq!(cache.cache.policy().time_to_idle(), None);
+
+        let resource = TestResource {
+            id: "test-1".to_string(),
+            name: "Test Resource".to_string(),
+            value: 42,
+        };
+        helper_set_and_get_in_cache(&registry, "test-1", resource.clone()).await;
+
+        // Outlives the registry-wide TTL of 1 second
+        sleep(Duration::from_secs(2)).await;
+        assert_eq!(cache.cache.get("test-1").await, Some(resource));
+    }
+
+    fn stale_registry(stale_while_revalidate: u64, stale_if_error: u64) -> CacheRegistry {
+        let registry = init_cache_registry(true, 100, 3600);
+        let _ = register_resource_cache_with_config::<TestResource>(
+            &registry,
+            "test_resource",
+            ResourceCacheConfig {
+                ttl_seconds: Some(1),
+                stale_while_revalidate_seconds: Some(stale_while_revalidate),
+                stale_if_error_seconds: Some(stale_if_error),
+                ..Default::default()
+            },
+        );
+        registry
+    }
+
+    fn test_resource(value: i32) -> TestResource {
+        TestResource {
+            id: "test-1".to_string(),
+            name: "Test Resource".to_string(),
+            value,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_or_fetch_coalesces_concurrent_misses() {
+        let registry = init_cache_registry(true, 100, 3600);
+        let _ = register_resource_cache::<TestResource>(&registry, "test_resource");
+        let calls = Arc::new(AtomicU64::new(0));
+
+        let requests = (0..10).map(|_| {
+            let calls = calls.clone();
+            get_or_fetch(&registry, "test_resource", "test-1", move || async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                sleep(Duration::from_millis(100)).await;
+                Ok::<_, AppError>(test_resource(1))
+            })
+        });
+        let results = futures::future::join_all(requests).await;
+
+        assert!(
+            results
+                .iter()
+                .all(|r| r.as_ref().ok() == Some(&(test_resource(1), CacheOutcome::Miss)))
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_or_fetch_serves_stale_on_error() {
+        let registry = stale_registry(0, 60);
+
+        let _ = get_or_fetch(&registry, "test_resource", "test-1", || async {
+            Ok::<_, AppError>(test_resource(1))
+        })
+        .await;
+        sleep(Duration::from_millis(1500)).await;
+
+        let result = get_or_fetch(&registry, "test_resource", "test-1", || async {
+            Err::<TestResource, _>(AppError::ExternalServiceError("upstream down".to_string()))
+        })
+        .await;
+        assert_eq!(result.ok(), Some((test_resource(1), CacheOutcome::Stale)));
+    }
+
+    #[tokio::test]
+    async fn test_get_or_fetch_returns_error_without_stale_window() {
+        let registry = stale_registry(0, 0);
+
+        let _ = get_or_fetch(&registry, "test_resource", "test-1", || async {
+            Ok::<_, AppError>(test_resource(1))
+        })
+        .await;
+        sleep(Duration::from_millis(1500)).await;
+
+        let result = get_or_fetch(&registry, "test_resource", "test-1", || async {
+            Err::<TestResource, _>(AppError::ExternalServiceError("upstream down".to_string()))
+        })
+        .await;
+        assert!(matches!(result, Err(AppError::ExternalServiceError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_invalidated_entries_are_not_served_stale() {
+        let registry = stale_registry(60, 60);
+
+        let _ = get_or_fetch(&registry, "test_resource", "test-1", || async {
+            Ok::<_, AppError>(test_resource(1))
+        })
+        .await;
+        let cache = get_resource_cache::<TestResource>(&registry, "test_resource").unwrap();
+        cache.cache.invalidate("test-1").await;
+
+        let result = get_or_fetch(&registry, "test_resource", "test-1", || async {
+            Err::<TestResource, _>(AppError::ExternalServiceError("upstream down".to_string()))
+        })
+        .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_or_fetch_revalidating_serves_stale_and_refreshes() {
+        let registry = stale_registry(60, 0);
+
+        let _ = get_or_fetch_revalidating(&registry, "test_resource", "test-1", || async {
+            Ok::<_, AppError>(test_resource(1))
+        })
+        .await;
+        sleep(Duration::from_millis(1500)).await;
+
+        // The expired value is returned immediately while the refresh runs
+        let result = get_or_fetch_revalidating(&registry, "test_resource", "test-1", || async {
+            sleep(Duration::from_millis(100)).await;
+            Ok::<_, AppError>(test_resource(2))
+        })
+        .await;
+        assert_eq!(result.ok(), Some((test_resource(1), CacheOutcome::Stale)));
+
+        sleep(Duration::from_millis(300)).await;
+        let cache = get_resource_cache::<TestResource>(&registry, "test_resource").unwrap();
+        assert_eq!(cache.cache.get("test-1").await, Some(test_resource(2)));
+    }
+
+    fn not_found_registry(not_found_ttl: u64, stale_if_error: u64) -> CacheRegistry {
+        let registry = init_cache_registry(true, 100, 3600);
+        let _ = register_resource_cache_with_config::<TestResource>(
+            &registry,
+            "test_resource",
+            ResourceCacheConfig {
+                ttl_seconds: Some(1),
+                stale_if_error_seconds: Some(stale_if_error),
+                not_found_ttl_seconds: Some(not_found_ttl),
+                ..Default::default()
+            },
+        );
+        registry
+    }
+
+    fn not_found() -> Result<TestResource, AppError> {
+        Err(AppError::NotFound(
+            "test_resource with ID test-1".to_string(),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_not_found_is_cached() {
+        let registry = not_found_registry(60, 0);
+        let calls = Arc::new(AtomicU64::new(0));
+
+        for _ in 0..3 {
+            let calls = calls.clone();
+            let result = get_or_fetch(&registry, "test_resource", "test-1", move || async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                not_found()
+            })
+            .await;
+            assert!(matches!(result, Err(AppError::NotFound(_))));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Other errors are never cached
+        let result = get_or_fetch(&registry, "test_resource", "test-2", || async {
+            Err::<TestResource, _>(AppError::ExternalServiceError("upstream down".to_string()))
+        })
+        .await;
+        assert!(result.is_err());
+        let cache = get_resource_cache::<TestResource>(&registry, "test_resource").unwrap();
+        assert!(cache.not_found_error::<AppError>("test-2").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_not_found_is_not_cached_without_ttl() {
+        let registry = not_found_registry(0, 0);
+
+        let _ = get_or_fetch(&registry, "test_resource", "test-1", || async {
+            not_found()
+        })
+        .await;
+        let result = get_or_fetch(&registry, "test_resource", "test-1", || async {
+            Ok::<_, AppError>(test_resource(1))
+        })
+        .await;
+        assert_eq!(result.ok(), Some((test_resource(1), CacheOutcome::Miss)));
+    }
+
+    #[tokio::test]
+    async fn test_not_found_is_invalidated_on_write() {
+        let registry = not_found_registry(60, 0);
+
+        let _ = get_or_fetch(&registry, "test_resource", "test-1", || async {
+            not_found()
+        })
+        .await;
+        let cache = get_resource_cache::<TestResource>(&registry, "test_resource").unwrap();
+        assert!(cache.not_found_error::<AppError>("test-1").is_some());
+
+        cache.insert("test-1".to_string(), test_resource(1)).await;
+        assert!(cache.not_found_error::<AppError>("test-1").is_none());
+
+        let result = get_or_fetch(&registry, "test_resource", "test-1", || async {
+            not_found()
+        })
+        .await;
+        assert_eq!(result.ok(), Some((test_resource(1), CacheOutcome::Hit)));
+    }
+
+    #[tokio::test]
+    async fn test_not_found_is_not_served_stale() {
+        let registry = not_found_registry(60, 60);
+
+        let _ = get_or_fetch(&registry, "test_resource", "test-1", || async {
+            Ok::<_, AppError>(test_resource(1))
+        })
+        .await;
+        sleep(Duration::from_millis(1500)).await;
+
+        let result = get_or_fetch(&registry, "test_resource", "test-1", || async {
+            not_found()
+        })
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_entry_weight_is_key_plus_json_length() {
+        let resource = test_resource(1);
+        let json_len = serde_json::to_vec(&resource).unwrap().len();
+        assert_eq!(entry_weight("key", &resource) as usize, 3 + json_len);
+    }
+
+    #[tokio::test]
+    async fn test_byte_sized_cache_evicts_by_weight() {
+        let registry = init_cache_registry(true, 1000, 3600);
+        let _ = register_resource_cache_with_config::<TestResource>(
+            &registry,
+            "test_resource",
+            ResourceCacheConfig {
+                max_bytes: Some(300),
+                ..Default::default()
+            },
+        );
+        let cache = get_resource_cache::<TestResource>(&registry, "test_resource").unwrap();
+        assert_eq!(cache.max_bytes, 300);
+
+        // Each entry weighs about 60 bytes, so 20 of them cannot all fit
+        for i in 0..20 {
+            cache.insert(format!("test-{}", i), test_resource(i)).await;
+        }
+        cache.cache.run_pending_tasks().await;
+
+        let ops = registry.resource_cache_ops("test_resource").unwrap();
+        let bytes_used = ops.bytes_used().unwrap();
+        assert!(bytes_used <= 300, "{} bytes used", bytes_used);
+        assert!(cache.cache.entry_count() < 20);
+    }
+
+    #[tokio::test]
+    async fn test_stale_copies_share_the_byte_budget() {
+        let registry = init_cache_registry(true, 1000, 3600);
+        let _ = register_resource_cache_with_config::<TestResource>(
+            &registry,
+            "test_resource",
+            ResourceCacheConfig {
+                max_bytes: Some(300),
+                stale_if_error_seconds: Some(60),
+                ..Default::default()
+            },
+        );
+        let cache = get_resource_cache::<TestResource>(&registry, "test_resource").unwrap();
+
+        for i in 0..20 {
+            cache.insert(format!("test-{}", i), test_resource(i)).await;
+        }
+        cache.cache.run_pending_tasks().await;
+        cache.stale.as_ref().unwrap().run_pending_tasks();
+
+        let ops = registry.resource_cache_ops("test_resource").unwrap();
+        let bytes_used = ops.bytes_used().unwrap();
+        assert!(bytes_used <= 300, "{} bytes used", bytes_used);
+        assert!(cache.stale.as_ref().unwrap().weighted_size() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_count_sized_cache_reports_no_bytes() {
+        let registry = init_cache_registry(true, 100, 3600);
+        let _ = register_resource_cache::<TestResource>(&registry, "test_resource");
+
+        let ops = registry.resource_cache_ops("test_resource").unwrap();
+        assert_eq!(ops.bytes_used(), None);
+        assert_eq!(ops.settings().max_bytes, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_memory_budget_split_by_weight() {
+        let registry = init_cache_registry(true, 100, 3600)
+            .with_resource_configs(HashMap::from([
+                (
+                    "large".to_string(),
+                    ResourceCacheConfig {
+                        budget_weight: Some(3),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "small".to_string(),
+                    ResourceCacheConfig {
+                        max_bytes: Some(100),
+                        ..Default::default()
+                    },
+                ),
+            ]))
+            .with_memory_budget(1000);
+
+        for resource_type in ["large", "small", "other"] {
+            let _ = register_resource_cache::<TestResource>(&registry, resource_type);
+        }
+        let max_bytes = |resource_type: &str| {
+            get_resource_cache::<TestResource>(&registry, resource_type)
+                .unwrap()
+                .max_bytes
+        };
+
+        // Configured weights 3 + 1: "large" gets 3/4 and "small" is capped by its
+        // own max_bytes. "other" was not configured, so it gets what is left.
+        assert_eq!(max_bytes("large"), 750);
+        assert_eq!(max_bytes("small"), 100);
+        assert_eq!(max_bytes("other"), 150);
+
+        // Re-registering a type replaces its reservation instead of adding to it
+        let _ = register_resource_cache::<TestResource>(&registry, "other");
+        assert_eq!(max_bytes("other"), 150);
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct OwnedResource {
+        id: String,
+        owner: u32,
+    }
+
+    impl ApiResource for OwnedResource {
+        type Id = String;
+
+        fn resource_type() -> &'static str {
+            "owned_resource"
+        }
+
+        fn api_name() -> &'static str {
+            "TestService"
+        }
+
+        fn cache_tags(&self) -> Vec<String> {
+            vec![format!("owner:{}", self.owner)]
+        }
+    }
+
+    fn owned(id: &str, owner: u32) -> OwnedResource {
+        OwnedResource {
+            id: id.to_string(),
+            owner,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_tag_across_resource_types() {
+        let registry = init_cache_registry(true, 100, 3600);
+        let _ = register_resource_cache::<OwnedResource>(&registry, "owned_resource");
+        let _ = register_resource_cache::<TestResource>(&registry, "test_resource");
+
+        // Fetched values are tagged with their own cache_tags
+        for (id, owner) in [("a", 42), ("b", 7)] {
+            let _ = get_or_fetch(&registry, "owned_resource", id, || async move {
+                Ok::<_, AppError>(owned(id, owner))
+            })
+            .await;
+        }
+        let tests = get_resource_cache::<TestResource>(&registry, "test_resource").unwrap();
+        tests
+            .insert_with_tags("x".to_string(), test_resource(1), &["owner:42".to_string()])
+            .await;
+
+        assert_eq!(registry.invalidate_tag("owner:42").await, 2);
+
+        let owned_cache = get_resource_cache::<OwnedResource>(&registry, "owned_resource").unwrap();
+        assert_eq!(owned_cache.cache.get("a").await, None);
+        assert_eq!(owned_cache.cache.get("b").await, Some(owned("b", 7)));
+        assert_eq!(owned_cache.tags("b"), vec!["owner:7".to_string()]);
+        assert_eq!(tests.cache.get("x").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_tags_follow_entry_lifecycle() {
+        let registry = init_cache_registry(true, 100, 3600);
+        let _ = register_resource_cache::<OwnedResource>(&registry, "owned_resource");
+        let cache = get_resource_cache::<OwnedResource>(&registry, "owned_resource").unwrap();
+
+        cache.insert("a".to_string(), owned("a", 1)).await;
+        // A new value replaces the old tags
+        cache.insert("a".to_string(), owned("a", 2)).await;
+        assert_eq!(cache.tags("a"), vec!["owner:2".to_string()]);
+        assert_eq!(registry.invalidate_tag("owner:1").await, 0);
+
+        // Removed entries drop out of the tag index
+        let ops = registry.resource_cache_ops("owned_resource").unwrap();
+        assert!(ops.remove("a").await);
+        assert!(cache.tags("a").is_empty());
+        assert_eq!(registry.invalidate_tag("owner:2").await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_prefix_and_all() {
+        let registry = init_cache_registry(true, 100, 3600);
+        let _ = register_resource_cache::<OwnedResource>(&registry, "owned_resource");
+        let cache = get_resource_cache::<OwnedResource>(&registry, "owned_resource").unwrap();
+        for id in ["dog-1", "dog-2", "cat-1"] {
+            cache.insert(id.to_string(), owned(id, 1)).await;
+        }
+
+        assert_eq!(
+            registry.invalidate_prefix("owned_resource", "dog-").await,
+            2
+        );
+        assert_eq!(cache.cache.get("cat-1").await, Some(owned("cat-1", 1)));
+
+        assert_eq!(registry.invalidate_all("owned_resource").await, 1);
+        assert_eq!(cache.cache.get("cat-1").await, None);
+
+        // Unknown resource types have nothing to invalidate
+        assert_eq!(registry.invalidate_all("unknown").await, 0);
+        assert_eq!(registry.invalidate_prefix("unknown", "").await, 0);
+    }
+
+    #[test]
+    fn test_cache_outcome_headers() {
+        assert_eq!(CacheOutcome::Hit.x_cache(), "HIT");
+        assert_eq!(CacheOutcome::Bypass.as_str(), "bypass");
+        assert_eq!(CacheOutcome::Hit.cache_status("app"), "app; hit");
+        assert_eq!(
+            CacheOutcome::Miss.cache_status("app"),
+            "app; fwd=miss; stored"
+        );
+        assert_eq!(
+            CacheOutcome::Stale.cache_status("app"),
+            "app; hit; detail=stale"
+        );
+        assert!(CacheOutcome::Stale.is_from_cache());
+        assert!(!CacheOutcome::Bypass.is_from_cache());
+    }
+
+    #[test]
+    fn test_is_registered() {
+        let registry = init_cache_registry(true, 100, 3600);
+        assert!(!registry.is_registered("test_resource"));
+
+        let _ = register_resource_cache::<TestResource>(&registry, "test_resource");
+        assert!(registry.is_registered("test_resource"));
+    }
+
     #[tokio::test]
     async fn test_disabled_cache() {
         // Create a disabled cache
@@ -748,15 +2551,11 @@ mod tests {
 
         // get_or_fetch should bypass cache and always call fetch function
         let result = get_or_fetch(&registry, "test_resource", "test-1", || async {
-            Ok(resource.clone())
+            Ok::<_, AppError>(resource.clone())
         })
         .await;
 
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), resource);
-
-        // Check if it was from cache
-        let was_cached = last_fetch_from_cache();
-        assert!(!was_cached); // Should not be from cache since cache is disabled
+        // Should not be from cache since cache is disabled
+        assert_eq!(result.ok(), Some((resource, CacheOutcome::Bypass)));
     }
 }
@@ -1,12 +1,20 @@
 pub mod cache_manager;
 pub mod registry_stats;
+pub mod snapshot;
 
 // Re-export main types and functions from cache_manager
 pub use cache_manager::{
-    CacheRegistry, CacheStats, ResourceCache, get_cache_stats_with_metrics, get_or_fetch,
-    get_resource_cache, init_cache_registry, last_fetch_from_cache, register_resource_cache,
-    start_metrics_updater,
+    CacheOutcome, CacheRegistry, CacheStats, FetchError, ResourceCache, ResourceCacheOps,
+    WarmResult, get_cache_stats_with_metrics, get_cached, get_or_fetch, get_or_fetch_revalidating,
+    get_or_fetch_with_provider, get_resource_cache, init_cache_registry, register_resource_cache,
+    register_resource_cache_with_config, register_resource_loader, start_metrics_updater,
 };
 
 // Re-export from registry_stats
 pub use registry_stats::get_all_cache_stats_with_metrics;
+
+// Re-export snapshot persistence and warm-up
+pub use snapshot::{
+    CacheSnapshot, ResourceSnapshot, SnapshotEntry, load_snapshot, persist_snapshot,
+    restore_and_warm, restore_snapshot, save_snapshot, take_snapshot, warm_up,
+};
@@ -17,22 +17,10 @@ pub fn get_all_cache_stats_with_metrics(registry: &CacheRegistry) -> HashMap<Str
         Err(_) => return result,
     };
 
-    // Get all resource types from the registry by extracting them from metrics
-    // This is a bit of a workaround since we don't have direct access to the caches
-    for line in metrics_text.lines() {
-        if line.starts_with("cache_current_size{resource_type=\"") {
-            // Extract the resource type from the metric name
-            let start = "cache_current_size{resource_type=\"".len();
-            let end_pos = line[start..].find('"').unwrap_or(0);
-            if end_pos > 0 {
-                let resource_type = &line[start..(start + end_pos)];
-                // Get stats for this resource type
-                if let Some(stats) =
-                    get_cache_stats_with_metrics(registry, resource_type, &metrics_text)
-                {
-                    result.insert(resource_type.to_string(), stats);
-                }
-            }
+    // Get stats for every resource type registered in the registry
+    for resource_type in registry.resource_types() {
+        if let Some(stats) = get_cache_stats_with_metrics(registry, &resource_type, &metrics_text) {
+            result.insert(resource_type, stats);
         }
     }
 
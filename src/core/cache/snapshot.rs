@@ -0,0 +1,405 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use tracing::{info, warn};
+
+use super::cache_manager::CacheRegistry;
+use crate::config::{CacheConfig, CacheWarmupConfig};
+
+/// Contents of every registered cache, as written to disk on shutdown
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CacheSnapshot {
+    pub created_at: DateTime<Utc>,
+    /// Snapshot of each cache, keyed by resource type
+    pub caches: HashMap<String, ResourceSnapshot>,
+}
+
+/// Contents of a single resource cache
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ResourceSnapshot {
+    pub entries: Vec<SnapshotEntry>,
+    /// The most hit keys, most hit first
+    #[serde(default)]
+    pub hot_keys: Vec<String>,
+}
+
+/// A cached value and when it expires
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SnapshotEntry {
+    pub key: String,
+    pub value: serde_json::Value,
+    /// `None` for caches without a TTL
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl CacheSnapshot {
+    /// The number of entries across all caches
+    pub fn entry_count(&self) -> usize {
+        self.caches.values().map(|cache| cache.entries.len()).sum()
+    }
+}
+
+/// Capture every registered cache, recording up to `hot_keys` hot keys per cache
+pub async fn take_snapshot(registry: &CacheRegistry, hot_keys: usize) -> CacheSnapshot {
+    let mut caches = HashMap::new();
+    for resource_type in registry.resource_types() {
+        if let Some(cache) = registry.resource_cache_ops(&resource_type) {
+            caches.insert(resource_type, cache.snapshot(hot_keys).await);
+        }
+    }
+
+    CacheSnapshot {
+        created_at: Utc::now(),
+        caches,
+    }
+}
+
+/// Write a snapshot of the registry to `path`, returning the number of entries written
+///
+/// The file is written next to its destination and renamed into place, so a
+/// crash mid-write never leaves a truncated snapshot behind.
+pub async fn save_snapshot(
+    registry: &CacheRegistry,
+    path: &Path,
+    hot_keys: usize,
+) -> Result<usize, String> {
+    let snapshot = take_snapshot(registry, hot_keys).await;
+    let body = serde_json::to_vec(&snapshot)
+        .map_err(|e| format!("Failed to serialize cache snapshot: {}", e))?;
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, body)
+        .map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+    fs::rename(&tmp_path, path)
+        .map_err(|e| format!("Failed to move snapshot to {}: {}", path.display(), e))?;
+
+    Ok(snapshot.entry_count())
+}
+
+/// Read a snapshot from `path`, or `None` if no snapshot has been written
+pub fn load_snapshot(path: &Path) -> Result<Option<CacheSnapshot>, String> {
+    let body = match fs::read(path) {
+        Ok(body) => body,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse cache snapshot {}: {}", path.display(), e))
+}
+
+/// Load the unexpired entries of a snapshot into the registry
+///
+/// Caches that are not registered are skipped. Returns the number of entries restored.
+pub async fn restore_snapshot(registry: &CacheRegistry, snapshot: &CacheSnapshot) -> usize {
+    let mut restored = 0;
+    for (resource_type, cache_snapshot) in &snapshot.caches {
+        match registry.resource_cache_ops(resource_type) {
+            Some(cache) => restored += cache.restore(cache_snapshot).await,
+            None => warn!(
+                "⚠️ Skipping snapshot of unregistered cache: {}",
+                resource_type
+            ),
+        }
+    }
+    restored
+}
+
+/// Preload the configured IDs, and the hot keys recorded in `snapshot`, through
+/// each resource type's loader
+///
+/// Keys that are already cached (e.g. restored from the snapshot) are left as
+/// they are. Returns the number of IDs loaded.
+pub async fn warm_up(
+    registry: &CacheRegistry,
+    config: &CacheWarmupConfig,
+    snapshot: Option<&CacheSnapshot>,
+) -> usize {
+    let mut ids: HashMap<String, Vec<String>> = config.ids.clone();
+    if let Some(snapshot) = snapshot {
+        for (resource_type, cache_snapshot) in &snapshot.caches {
+            let hot_keys = cache_snapshot
+                .hot_keys
+                .iter()
+                .take(config.hot_keys)
+                .cloned();
+            ids.entry(resource_type.clone())
+                .or_default()
+                .extend(hot_keys);
+        }
+    }
+
+    let mut loaded = 0;
+    for (resource_type, mut ids) in ids {
+        let mut seen = HashSet::new();
+        ids.retain(|id| seen.insert(id.clone()));
+        if ids.is_empty() {
+            continue;
+        }
+        if !registry.has_loader(&resource_type) {
+            warn!(
+                "⚠️ No loader registered for {}, skipping cache warm-up",
+                resource_type
+            );
+            continue;
+        }
+
+        match registry.warm(&resource_type, ids, config.concurrency).await {
+            Ok(results) => loaded += results.iter().filter(|r| r.loaded).count(),
+            Err(e) => warn!("⚠️ Failed to warm {} cache: {}", resource_type, e),
+        }
+    }
+    loaded
+}
+
+/// Restore the configured snapshot and run the warm-up phase, if enabled
+///
+/// Called on startup once resource caches and loaders are registered.
+pub async fn restore_and_warm(registry: &CacheRegistry, config: &CacheConfig) {
+    let snapshot = if config.snapshot.enabled {
+        match load_snapshot(Path::new(&config.snapshot.path)) {
+            Ok(Some(snapshot)) => {
+                let restored = restore_snapshot(registry, &snapshot).await;
+                info!(
+                    "💾 Restored {}/{} cache entries from snapshot {} (taken {})",
+                    restored,
+                    snapshot.entry_count(),
+                    config.snapshot.path,
+                    snapshot.created_at
+                );
+                Some(snapshot)
+            }
+            Ok(None) => {
+                info!("💾 No cache snapshot found at {}", config.snapshot.path);
+                None
+            }
+            Err(e) => {
+                warn!("⚠️ Failed to load cache snapshot: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    if config.warmup.enabled {
+        let loaded = warm_up(registry, &config.warmup, snapshot.as_ref()).await;
+        info!("🔥 Cache warm-up loaded {} entries", loaded);
+    }
+}
+
+/// Write the configured snapshot, if enabled
+///
+/// Called on graceful shutdown. Failures are logged, not returned, so they
+/// never block the shutdown.
+pub async fn persist_snapshot(registry: &CacheRegistry, config: &CacheConfig) {
+    if !config.snapshot.enabled {
+        return;
+    }
+
+    let path = Path::new(&config.snapshot.path);
+    match save_snapshot(registry, path, config.warmup.hot_keys).await {
+        Ok(written) => info!(
+            "💾 Wrote {} cache entries to snapshot {}",
+            written, config.snapshot.path
+        ),
+        Err(e) => warn!("⚠️ Failed to write cache snapshot: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::cache::{
+        get_cached, get_resource_cache, init_cache_registry, register_resource_cache,
+        register_resource_loader,
+    };
+    use crate::utils::api_resource::ApiResource;
+    use std::path::PathBuf;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+    use std::time::{Duration, SystemTime};
+    use tokio::time::sleep;
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct TestResource {
+        id: String,
+        value: i32,
+    }
+
+    impl ApiResource for TestResource {
+        type Id = String;
+
+        fn resource_type() -> &'static str {
+            "test_resource"
+        }
+
+        fn api_name() -> &'static str {
+            "TestService"
+        }
+    }
+
+    fn test_resource(id: &str) -> TestResource {
+        TestResource {
+            id: id.to_string(),
+            value: 1,
+        }
+    }
+
+    fn registry() -> CacheRegistry {
+        let registry = init_cache_registry(true, 100, 3600);
+        let _ = register_resource_cache::<TestResource>(&registry, "test_resource");
+        registry
+    }
+
+    fn snapshot_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "cache-snapshot-{}-{}.json",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_round_trip() {
+        let source = registry();
+        let cache = get_resource_cache::<TestResource>(&source, "test_resource").unwrap();
+        cache
+            .insert_with_tags(
+                "1".to_string(),
+                test_resource("1"),
+                &["owner:7".to_string()],
+            )
+            .await;
+        cache.insert("2".to_string(), test_resource("2")).await;
+
+        // "2" is requested more often than "1"
+        for id in ["1", "2", "2"] {
+            let _ = get_cached::<TestResource>(&source, "test_resource", id).await;
+        }
+
+        let path = snapshot_path("round-trip");
+        assert_eq!(save_snapshot(&source, &path, 1).await.unwrap(), 2);
+
+        let snapshot = load_snapshot(&path).unwrap().unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(
+            snapshot.caches["test_resource"].hot_keys,
+            vec!["2".to_string()]
+        );
+
+        let target = registry();
+        assert_eq!(restore_snapshot(&target, &snapshot).await, 2);
+
+        let restored = get_resource_cache::<TestResource>(&target, "test_resource").unwrap();
+        assert_eq!(restored.cache.get("1").await, Some(test_resource("1")));
+        assert_eq!(restored.tags("1"), vec!["owner:7".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_load_missing_snapshot() {
+        let path = snapshot_path("missing");
+        assert_eq!(load_snapshot(&path), Ok(None));
+    }
+
+    #[tokio::test]
+    async fn test_restore_keeps_original_expiry() {
+        let now = Utc::now();
+        let entry = |key: &str, expires_at: Option<DateTime<Utc>>| SnapshotEntry {
+            key: key.to_string(),
+            value: serde_json::to_value(test_resource(key)).unwrap(),
+            expires_at,
+            tags: Vec::new(),
+        };
+        let snapshot = CacheSnapshot {
+            created_at: now,
+            caches: HashMap::from([(
+                "test_resource".to_string(),
+                ResourceSnapshot {
+                    entries: vec![
+                        entry("expired", Some(now - chrono::Duration::seconds(1))),
+                        entry("soon", Some(now + chrono::Duration::seconds(1))),
+                        entry("later", Some(now + chrono::Duration::seconds(600))),
+                    ],
+                    hot_keys: Vec::new(),
+                },
+            )]),
+        };
+
+        let registry = registry();
+        assert_eq!(restore_snapshot(&registry, &snapshot).await, 2);
+
+        let cache = get_resource_cache::<TestResource>(&registry, "test_resource").unwrap();
+        assert_eq!(cache.cache.get("expired").await, None);
+        assert!(cache.cache.get("soon").await.is_some());
+
+        // "soon" expires on its original schedule despite the one-hour TTL
+        sleep(Duration::from_millis(1500)).await;
+        assert_eq!(cache.cache.get("soon").await, None);
+        assert!(cache.cache.get("later").await.is_some());
+
+        // A later snapshot records the original expiry rather than a fresh TTL
+        let expires_at = take_snapshot(&registry, 0).await.caches["test_resource"].entries[0]
+            .expires_at
+            .unwrap();
+        let original = now + chrono::Duration::seconds(600);
+        assert!((expires_at - original).num_seconds().abs() <= 1);
+        assert!(SystemTime::from(expires_at) > SystemTime::now());
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_loads_configured_ids_and_hot_keys() {
+        let registry = registry();
+        let loads = Arc::new(AtomicUsize::new(0));
+        let _ = register_resource_loader::<TestResource, _, _>(&registry, "test_resource", {
+            let loads = loads.clone();
+            move |id: String| {
+                let loads = loads.clone();
+                async move {
+                    loads.fetch_add(1, Ordering::SeqCst);
+                    Ok(test_resource(&id))
+                }
+            }
+        });
+
+        let config = CacheWarmupConfig {
+            enabled: true,
+            ids: HashMap::from([(
+                "test_resource".to_string(),
+                vec!["1".to_string(), "2".to_string()],
+            )]),
+            hot_keys: 1,
+            concurrency: 2,
+        };
+        let snapshot = CacheSnapshot {
+            created_at: Utc::now(),
+            caches: HashMap::from([(
+                "test_resource".to_string(),
+                ResourceSnapshot {
+                    entries: Vec::new(),
+                    hot_keys: vec!["2".to_string(), "3".to_string(), "4".to_string()],
+                },
+            )]),
+        };
+
+        // "2" is both configured and hot; only the hottest key is preloaded
+        assert_eq!(warm_up(&registry, &config, Some(&snapshot)).await, 2);
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+        let cache = get_resource_cache::<TestResource>(&registry, "test_resource").unwrap();
+        assert!(cache.cache.get("1").await.is_some());
+        assert!(cache.cache.get("2").await.is_some());
+        assert_eq!(cache.cache.get("3").await, None);
+    }
+}
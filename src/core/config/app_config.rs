@@ -1,7 +1,9 @@
 use super::constants;
 use config::{Config, ConfigError, Environment, File};
 use dotenvy::dotenv;
+use jsonwebtoken::Algorithm;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 use std::path::Path;
 use std::time::Duration;
@@ -24,8 +26,412 @@ pub struct CacheConfig {
     pub enabled: bool,
     pub ttl_seconds: u64,
     pub max_capacity: u64,
+    /// Memory budget in bytes shared by all resource caches (0 = size by entry count)
+    ///
+    /// Each resource type gets a share proportional to its `budget_weight`.
+    #[serde(default)]
+    pub max_bytes: u64,
     #[serde(default = "default_reconnect_interval")]
     pub reconnect_interval_seconds: u64,
+    /// Which cache backend handlers read and write through
+    #[serde(default)]
+    pub provider: CacheProviderType,
+    /// Redis connection settings, used by the `redis`, `fallback` and `tiered` providers
+    #[serde(default)]
+    pub redis: RedisCacheConfig,
+    /// In-process tier of the `tiered` provider
+    #[serde(default)]
+    pub tiered: TieredCacheConfig,
+    /// Per-resource overrides keyed by resource type (e.g. `cache.resources.pet`)
+    #[serde(default)]
+    pub resources: HashMap<String, ResourceCacheConfig>,
+    /// Preloading of the memory caches on startup
+    #[serde(default)]
+    pub warmup: CacheWarmupConfig,
+    /// On-disk snapshot of the memory caches, written on shutdown and restored on startup
+    #[serde(default)]
+    pub snapshot: CacheSnapshotConfig,
+    /// Broadcast of cache evictions between replicas
+    #[serde(default)]
+    pub invalidation: CacheInvalidationConfig,
+    /// Compression and encryption of values stored in remote caches (Redis)
+    #[serde(default)]
+    pub codec: CacheCodecConfig,
+    /// Caching of database repository lookups
+    #[serde(default)]
+    pub repository: RepositoryCacheConfig,
+}
+
+/// Cache warm-up settings
+///
+/// IDs are loaded through the loader registered for each resource type.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CacheWarmupConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// IDs to preload, keyed by resource type (e.g. `cache.warmup.ids.pet`)
+    #[serde(default)]
+    pub ids: HashMap<String, Vec<String>>,
+    /// How many of the most-requested keys per resource type to record in the
+    /// snapshot and preload on the next startup (0 = none)
+    #[serde(default)]
+    pub hot_keys: usize,
+    /// Maximum number of loads in flight per resource type
+    #[serde(default = "default_warmup_concurrency")]
+    pub concurrency: usize,
+}
+
+impl Default for CacheWarmupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ids: HashMap::new(),
+            hot_keys: 0,
+            concurrency: default_warmup_concurrency(),
+        }
+    }
+}
+
+/// Cache snapshot settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CacheSnapshotConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// File the snapshot is written to and read from
+    #[serde(default = "default_snapshot_path")]
+    pub path: String,
+}
+
+impl Default for CacheSnapshotConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: default_snapshot_path(),
+        }
+    }
+}
+
+/// Cross-replica cache invalidation settings
+///
+/// Writes publish the evicted key on `channel`, and every replica subscribed to
+/// it removes the key from its in-process caches.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CacheInvalidationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Transport used to broadcast invalidations
+    #[serde(default)]
+    pub backend: InvalidationBackend,
+    /// Redis pub/sub channel or Postgres NOTIFY channel
+    #[serde(default = "default_invalidation_channel")]
+    pub channel: String,
+}
+
+impl Default for CacheInvalidationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: InvalidationBackend::default(),
+            channel: default_invalidation_channel(),
+        }
+    }
+}
+
+/// Transport for cross-replica cache invalidation
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum InvalidationBackend {
+    /// Redis pub/sub, using the `cache.redis` connection settings
+    #[default]
+    Redis,
+    /// Postgres LISTEN/NOTIFY, using the `database.url` connection
+    Postgres,
+}
+
+impl std::fmt::Display for InvalidationBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidationBackend::Redis => write!(f, "redis"),
+            InvalidationBackend::Postgres => write!(f, "postgres"),
+        }
+    }
+}
+
+/// Caching of entities loaded through database repositories
+///
+/// `find_by_id` results are kept in the cache registry under the entity's
+/// resource type (e.g. `cache.resources.user`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RepositoryCacheConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// When saved entities reach the database
+    #[serde(default)]
+    pub write_mode: WriteMode,
+    /// How often pending writes are flushed in `write_back` mode
+    #[serde(default = "default_write_back_interval")]
+    pub write_back_interval_ms: u64,
+}
+
+impl Default for RepositoryCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            write_mode: WriteMode::default(),
+            write_back_interval_ms: default_write_back_interval(),
+        }
+    }
+}
+
+/// How a cached repository writes saved entities
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteMode {
+    /// Save to the database before returning, then cache the saved entity
+    #[default]
+    WriteThrough,
+    /// Cache the entity and return; the database write is batched and flushed later
+    WriteBack,
+}
+
+impl std::fmt::Display for WriteMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WriteMode::WriteThrough => write!(f, "write_through"),
+            WriteMode::WriteBack => write!(f, "write_back"),
+        }
+    }
+}
+
+/// Encoding of values written to remote cache providers
+///
+/// Values stay plain JSON unless compression or encryption is enabled.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CacheCodecConfig {
+    /// Compression applied to values of at least `compression_threshold_bytes`
+    #[serde(default)]
+    pub compression: CompressionAlgorithm,
+    #[serde(default = "default_compression_threshold")]
+    pub compression_threshold_bytes: usize,
+    #[serde(default)]
+    pub encryption: CacheEncryptionConfig,
+}
+
+impl Default for CacheCodecConfig {
+    fn default() -> Self {
+        Self {
+            compression: CompressionAlgorithm::default(),
+            compression_threshold_bytes: default_compression_threshold(),
+            encryption: CacheEncryptionConfig::default(),
+        }
+    }
+}
+
+/// Compression algorithm for remote cache values
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionAlgorithm {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl std::fmt::Display for CompressionAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompressionAlgorithm::None => write!(f, "none"),
+            CompressionAlgorithm::Gzip => write!(f, "gzip"),
+            CompressionAlgorithm::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+/// AES-256-GCM encryption of remote cache values
+///
+/// New values are encrypted with `active_key_id`. Each stored value records
+/// the ID of its key, so retired keys can stay in `keys` until the values
+/// written with them have expired.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct CacheEncryptionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// ID of the key used to encrypt new values
+    #[serde(default)]
+    pub active_key_id: String,
+    /// Base64-encoded 32-byte keys by key ID
+    #[serde(default)]
+    pub keys: HashMap<String, String>,
+    /// Also read unencrypted values, such as entries written before encryption was enabled
+    ///
+    /// Anyone able to write to Redis can then inject values, so only set this
+    /// until the unencrypted entries have expired.
+    #[serde(default)]
+    pub accept_plaintext_during_migration: bool,
+}
+
+impl std::fmt::Debug for CacheEncryptionConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut key_ids: Vec<&String> = self.keys.keys().collect();
+        key_ids.sort();
+        f.debug_struct("CacheEncryptionConfig")
+            .field("enabled", &self.enabled)
+            .field("active_key_id", &self.active_key_id)
+            .field("key_ids", &key_ids)
+            .field(
+                "accept_plaintext_during_migration",
+                &self.accept_plaintext_during_migration,
+            )
+            .finish()
+    }
+}
+
+/// Cache settings for a single resource type
+///
+/// Unset fields fall back to the registry-wide `cache` settings. A TTL of 0
+/// disables expiry, keeping entries until they are invalidated or evicted.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct ResourceCacheConfig {
+    /// Time-to-live in seconds (0 = no expiry)
+    #[serde(default)]
+    pub ttl_seconds: Option<u64>,
+    /// Time-to-idle in seconds (0 = no idle expiry)
+    #[serde(default)]
+    pub tti_seconds: Option<u64>,
+    /// Maximum number of entries for this resource type
+    #[serde(default)]
+    pub max_capacity: Option<u64>,
+    /// How long after expiry an entry may be served while one refresh runs in the background
+    #[serde(default)]
+    pub stale_while_revalidate_seconds: Option<u64>,
+    /// How long after expiry an entry may be served when refreshing it fails
+    #[serde(default)]
+    pub stale_if_error_seconds: Option<u64>,
+    /// How long an upstream "not found" is remembered (0 = not cached)
+    #[serde(default)]
+    pub not_found_ttl_seconds: Option<u64>,
+    /// Size the cache by approximate serialized bytes, up to this many (0 = by entry count)
+    ///
+    /// With a global `cache.max_bytes` budget this caps the type's share of it.
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+    /// Relative share of the global `cache.max_bytes` budget (default 1)
+    #[serde(default)]
+    pub budget_weight: Option<u32>,
+}
+
+impl ResourceCacheConfig {
+    /// Fill unset fields from `defaults`
+    pub fn or(self, defaults: ResourceCacheConfig) -> Self {
+        Self {
+            ttl_seconds: self.ttl_seconds.or(defaults.ttl_seconds),
+            tti_seconds: self.tti_seconds.or(defaults.tti_seconds),
+            max_capacity: self.max_capacity.or(defaults.max_capacity),
+            stale_while_revalidate_seconds: self
+                .stale_while_revalidate_seconds
+                .or(defaults.stale_while_revalidate_seconds),
+            stale_if_error_seconds: self
+                .stale_if_error_seconds
+                .or(defaults.stale_if_error_seconds),
+            not_found_ttl_seconds: self
+                .not_found_ttl_seconds
+                .or(defaults.not_found_ttl_seconds),
+            max_bytes: self.max_bytes.or(defaults.max_bytes),
+            budget_weight: self.budget_weight.or(defaults.budget_weight),
+        }
+    }
+}
+
+/// Cache backend selection
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheProviderType {
+    /// In-process Moka cache (per replica)
+    #[default]
+    Memory,
+    /// Shared Redis cache
+    Redis,
+    /// Redis with automatic fallback to the in-process cache when Redis is unavailable
+    Fallback,
+    /// Short-lived in-process cache in front of Redis, written through to both
+    Tiered,
+}
+
+impl std::fmt::Display for CacheProviderType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CacheProviderType::Memory => write!(f, "memory"),
+            CacheProviderType::Redis => write!(f, "redis"),
+            CacheProviderType::Fallback => write!(f, "fallback"),
+            CacheProviderType::Tiered => write!(f, "tiered"),
+        }
+    }
+}
+
+/// Settings for the in-process (L1) tier of the `tiered` cache provider
+///
+/// L1 entries are never kept longer than `l1_ttl_seconds`, which bounds how
+/// long a replica can serve a value after another replica changed it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TieredCacheConfig {
+    #[serde(default = "default_l1_ttl")]
+    pub l1_ttl_seconds: u64,
+    /// Maximum number of entries per resource type in the in-process tier
+    #[serde(default = "default_l1_max_capacity")]
+    pub l1_max_capacity: u64,
+}
+
+impl Default for TieredCacheConfig {
+    fn default() -> Self {
+        Self {
+            l1_ttl_seconds: default_l1_ttl(),
+            l1_max_capacity: default_l1_max_capacity(),
+        }
+    }
+}
+
+/// Redis cache connection configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisCacheConfig {
+    /// Connection URL (`redis://[user:pass@]host[:port][/db]`)
+    #[serde(default = "default_redis_url")]
+    pub url: String,
+    /// Username for Redis ACL authentication (overrides the URL)
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Password (overrides the URL)
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Database index (overrides the URL)
+    #[serde(default)]
+    pub database: Option<i64>,
+    /// Maximum number of pooled connections
+    #[serde(default = "default_redis_pool_size")]
+    pub pool_size: usize,
+    /// Connect timeout in milliseconds
+    #[serde(default = "default_redis_connect_timeout")]
+    pub connect_timeout_ms: u64,
+    /// Timeout for each command in milliseconds; a stalled server fails the operation
+    #[serde(default = "default_redis_command_timeout")]
+    pub command_timeout_ms: u64,
+    /// Prefix applied to every key written by this application
+    #[serde(default = "default_redis_key_prefix")]
+    pub key_prefix: String,
+}
+
+impl Default for RedisCacheConfig {
+    fn default() -> Self {
+        Self {
+            url: default_redis_url(),
+            username: None,
+            password: None,
+            database: None,
+            pool_size: default_redis_pool_size(),
+            connect_timeout_ms: default_redis_connect_timeout(),
+            command_timeout_ms: default_redis_command_timeout(),
+            key_prefix: default_redis_key_prefix(),
+        }
+    }
 }
 
 /// Database configuration
@@ -55,6 +461,19 @@ impl Default for DatabaseConfig {
 pub struct ApiConfig {
     pub petstore_url: String,
     pub api_key: Option<String>,
+    /// Upstream APIs by the key resources name in `ApiResource::base_url_key`
+    #[serde(default)]
+    pub upstreams: HashMap<String, UpstreamConfig>,
+}
+
+/// An upstream API that resources are fetched from
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct UpstreamConfig {
+    /// Base URL that resource endpoints are appended to
+    pub url: String,
+    /// Scope of the Entra token sent as a bearer token (none = no Authorization header)
+    #[serde(default)]
+    pub scope: Option<String>,
 }
 
 /// Entra ID (Azure AD) authentication configuration
@@ -99,19 +518,195 @@ pub struct EntraConfig {
     /// Admin roles (users with these roles can access admin endpoints)
     pub admin_roles: Vec<String>,
 
-    /// Read-only roles (users with these roles can access read-only endpoints)
+    /// Read-only roles (users with these roles can read the `/protected` routes)
     pub read_only_roles: Vec<String>,
 
-    /// Full access roles (users with these roles can access full access endpoints)
+    /// Full access roles (users with these roles can change data through the `/protected` routes)
     pub full_access_roles: Vec<String>,
 }
 
+/// Which kind of identity provider issues the tokens we accept
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthProvider {
+    /// Microsoft Entra ID, configured under `auth.entra`
+    #[default]
+    Entra,
+    /// Any OpenID Connect provider, configured under `auth.oidc`
+    Oidc,
+}
+
+/// Settings for accepting tokens from OpenID Connect providers such as Keycloak or Auth0
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OidcConfig {
+    /// Trusted issuers; a token is validated against the one named by its `iss` claim
+    #[serde(default)]
+    pub issuers: Vec<OidcIssuerConfig>,
+}
+
+/// A trusted OpenID Connect issuer
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OidcIssuerConfig {
+    /// Issuer URL, which must match the token's `iss` claim exactly
+    pub issuer: String,
+
+    /// Audience the token must be issued for
+    pub audience: String,
+
+    /// Discovery document URL, by default `{issuer}/.well-known/openid-configuration`
+    #[serde(default)]
+    pub discovery_url: Option<String>,
+
+    /// Claim holding the caller's roles, with dots for nested claims (e.g. `realm_access.roles`)
+    #[serde(default = "default_roles_claim")]
+    pub roles_claim: String,
+
+    /// Claim holding the caller's scopes, as a list or a space-separated string
+    #[serde(default = "default_scopes_claim")]
+    pub scopes_claim: String,
+}
+
+impl OidcIssuerConfig {
+    /// URL of the issuer's discovery document
+    pub fn discovery_url(&self) -> String {
+        self.discovery_url.clone().unwrap_or_else(|| {
+            format!(
+                "{}/.well-known/openid-configuration",
+                self.issuer.trim_end_matches('/')
+            )
+        })
+    }
+}
+
+fn default_roles_claim() -> String {
+    "roles".to_string()
+}
+
+fn default_scopes_claim() -> String {
+    "scope".to_string()
+}
+
 /// Authentication configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthConfig {
     pub enabled: bool,
     pub debug: bool,
+    /// Identity provider for incoming tokens
+    #[serde(default)]
+    pub provider: AuthProvider,
     pub entra: EntraConfig,
+    /// Issuers accepted when `provider` is `oidc`
+    #[serde(default)]
+    pub oidc: OidcConfig,
+    /// Signing algorithms accepted in incoming tokens; HMAC and `none` are always rejected
+    #[serde(default = "default_allowed_algorithms")]
+    pub allowed_algorithms: Vec<Algorithm>,
+    /// How signing key sets are cached and refreshed
+    #[serde(default)]
+    pub jwks: JwksConfig,
+    /// Per-route authorization rules, checked after the token is validated on top
+    /// of the built-in ones (see [`AuthConfig::effective_policies`])
+    #[serde(default)]
+    pub policies: Vec<AuthPolicyConfig>,
+}
+
+/// An authorization rule for the requests matching `path` and `methods`
+///
+/// Every policy matching a request must allow it. Requests matching no
+/// policy only need a valid token.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AuthPolicyConfig {
+    /// Name used in logs and metrics
+    pub name: String,
+
+    /// Request path, where `{name}` matches one segment and a final `{*name}` the rest
+    pub path: String,
+
+    /// HTTP methods the policy applies to (all when empty)
+    #[serde(default)]
+    pub methods: Vec<String>,
+
+    /// The caller needs at least one of these roles
+    #[serde(default)]
+    pub roles: Vec<String>,
+
+    /// The caller needs at least one of these scopes
+    #[serde(default)]
+    pub scopes: Vec<String>,
+
+    /// Conditions on the token's claims, all of which must hold
+    #[serde(default)]
+    pub claims: Vec<ClaimPredicateConfig>,
+}
+
+/// A condition on one claim of the token
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ClaimPredicateConfig {
+    /// Claim name, with dots for nested claims (e.g. `realm_access.roles`)
+    pub claim: String,
+
+    /// Accepted values; the claim (or, for a list, one of its items) must equal one.
+    /// When empty, the claim only has to be present.
+    #[serde(default)]
+    pub one_of: Vec<String>,
+}
+
+/// Caching and refreshing of identity provider signing keys (JWKS)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct JwksConfig {
+    /// How long keys are cached when the IdP sends no `Cache-Control: max-age`
+    #[serde(default = "default_jwks_max_age_seconds")]
+    pub default_max_age_seconds: u64,
+
+    /// Minimum time between fetches, including refetches for unknown key IDs
+    #[serde(default = "default_jwks_min_refetch_interval_seconds")]
+    pub min_refetch_interval_seconds: u64,
+
+    /// How long expired keys keep being used while the IdP cannot be reached
+    #[serde(default = "default_jwks_max_stale_seconds")]
+    pub max_stale_seconds: u64,
+
+    /// Refresh keys in the background before they expire
+    #[serde(default = "default_true")]
+    pub background_refresh: bool,
+}
+
+impl Default for JwksConfig {
+    fn default() -> Self {
+        Self {
+            default_max_age_seconds: default_jwks_max_age_seconds(),
+            min_refetch_interval_seconds: default_jwks_min_refetch_interval_seconds(),
+            max_stale_seconds: default_jwks_max_stale_seconds(),
+            background_refresh: true,
+        }
+    }
+}
+
+fn default_jwks_max_age_seconds() -> u64 {
+    3600
+}
+
+fn default_jwks_min_refetch_interval_seconds() -> u64 {
+    30
+}
+
+fn default_jwks_max_stale_seconds() -> u64 {
+    86400
+}
+
+/// Asymmetric signing algorithms accepted by default
+pub fn default_allowed_algorithms() -> Vec<Algorithm> {
+    vec![
+        Algorithm::RS256,
+        Algorithm::RS384,
+        Algorithm::RS512,
+        Algorithm::PS256,
+        Algorithm::PS384,
+        Algorithm::PS512,
+        Algorithm::ES256,
+        Algorithm::ES384,
+        Algorithm::EdDSA,
+    ]
 }
 
 impl Default for AuthConfig {
@@ -119,6 +714,11 @@ impl Default for AuthConfig {
         Self {
             enabled: true,
             debug: false,
+            provider: AuthProvider::default(),
+            oidc: OidcConfig::default(),
+            allowed_algorithms: default_allowed_algorithms(),
+            jwks: JwksConfig::default(),
+            policies: Vec::new(),
             entra: EntraConfig {
                 tenant_id: env::var(constants::auth::env_vars::TENANT_ID).unwrap_or_default(),
                 client_id: env::var(constants::auth::env_vars::CLIENT_ID).unwrap_or_default(),
@@ -137,6 +737,40 @@ impl Default for AuthConfig {
     }
 }
 
+impl AuthConfig {
+    /// The policies checked on authenticated routes
+    ///
+    /// Built-in policies come first: reads in the `/protected` group need one of
+    /// `entra.read_only_roles` and writes one of `entra.full_access_roles`. The
+    /// configured `policies` follow and can only narrow these, since a request
+    /// must satisfy every policy matching it.
+    pub fn effective_policies(&self) -> Vec<AuthPolicyConfig> {
+        let protected = |name: &str, methods: &[&str], roles: &[String]| AuthPolicyConfig {
+            name: name.to_string(),
+            path: "/protected/{*rest}".to_string(),
+            methods: methods.iter().map(|m| m.to_string()).collect(),
+            roles: roles.to_vec(),
+            scopes: Vec::new(),
+            claims: Vec::new(),
+        };
+
+        let mut policies = vec![
+            protected(
+                "protected-read",
+                &["GET", "HEAD"],
+                &self.entra.read_only_roles,
+            ),
+            protected(
+                "protected-write",
+                &["POST", "PUT", "PATCH", "DELETE"],
+                &self.entra.full_access_roles,
+            ),
+        ];
+        policies.extend(self.policies.iter().cloned());
+        policies
+    }
+}
+
 /// Logging configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoggingConfig {
@@ -606,6 +1240,35 @@ fn default_health_details() -> bool {
     true
 }
 
+/// Settings for the dependency checks behind the health endpoints
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HealthConfig {
+    /// How long a single dependency check may run before it is reported down
+    #[serde(default = "default_health_check_timeout_ms")]
+    pub check_timeout_ms: u64,
+
+    /// How long check results are reused, so frequent probes do not load upstreams
+    #[serde(default = "default_health_cache_ttl_ms")]
+    pub cache_ttl_ms: u64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            check_timeout_ms: default_health_check_timeout_ms(),
+            cache_ttl_ms: default_health_cache_ttl_ms(),
+        }
+    }
+}
+
+fn default_health_check_timeout_ms() -> u64 {
+    2000
+}
+
+fn default_health_cache_ttl_ms() -> u64 {
+    5000
+}
+
 /// Main application configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
@@ -648,6 +1311,10 @@ pub struct AppConfig {
     /// Endpoint security configuration
     #[serde(default)]
     pub endpoint_security: EndpointSecurityConfig,
+
+    /// Health check configuration
+    #[serde(default)]
+    pub health: HealthConfig,
 }
 
 /// Application metadata configuration
@@ -677,6 +1344,24 @@ impl AppConfig {
         self.api.petstore_url.trim_end_matches('/').to_string()
     }
 
+    /// Get the upstream configured under `api.upstreams.<key>`
+    ///
+    /// `petstore` falls back to `api.petstore_url` when it has no entry of its own.
+    pub fn upstream(&self, key: &str) -> Option<UpstreamConfig> {
+        let upstream = match self.api.upstreams.get(key) {
+            Some(upstream) => upstream.clone(),
+            None if key == "petstore" => UpstreamConfig {
+                url: self.api.petstore_url.clone(),
+                scope: None,
+            },
+            None => return None,
+        };
+        Some(UpstreamConfig {
+            url: upstream.url.trim_end_matches('/').to_string(),
+            ..upstream
+        })
+    }
+
     /// Get the OpenAPI spec file path
     pub fn openapi_spec_path(&self) -> String {
         // Hardcoded directory + filename from config
@@ -762,6 +1447,10 @@ pub fn load_config() -> Result<AppConfig, ConfigError> {
         }
     }
 
+    // Fail startup on malformed authorization policies rather than skip their rules
+    crate::core::auth::policy::PolicyEngine::new(app_config.auth.effective_policies())
+        .map_err(|e| ConfigError::Message(format!("Invalid auth.policies: {}", e)))?;
+
     // Manually set Entra ID configuration from environment variables if they exist
     // This ensures the environment variables are properly mapped to the configuration
     if let Ok(tenant_id) = env::var(constants::auth::env_vars::TENANT_ID) {
@@ -806,3 +1495,51 @@ pub fn load_config() -> Result<AppConfig, ConfigError> {
 fn default_reconnect_interval() -> u64 {
     30
 }
+
+fn default_redis_url() -> String {
+    "redis://127.0.0.1:6379".to_string()
+}
+
+fn default_redis_pool_size() -> usize {
+    8
+}
+
+fn default_redis_connect_timeout() -> u64 {
+    2000
+}
+
+fn default_redis_command_timeout() -> u64 {
+    1000
+}
+
+fn default_redis_key_prefix() -> String {
+    "rust-backend:".to_string()
+}
+
+fn default_warmup_concurrency() -> usize {
+    8
+}
+
+fn default_snapshot_path() -> String {
+    "data/cache-snapshot.json".to_string()
+}
+
+fn default_l1_ttl() -> u64 {
+    30
+}
+
+fn default_l1_max_capacity() -> u64 {
+    10_000
+}
+
+fn default_write_back_interval() -> u64 {
+    500
+}
+
+fn default_compression_threshold() -> usize {
+    1024
+}
+
+fn default_invalidation_channel() -> String {
+    "cache_invalidation".to_string()
+}
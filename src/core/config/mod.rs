@@ -6,8 +6,9 @@ mod tests;
 pub use app_config::AppConfig;
 pub use app_config::load_config;
 use app_config::{
-    ApiConfig, AuthConfig, CacheConfig, DatabaseConfig, LoggingConfig, ReliabilityConfig,
-    ServerConfig,
+    ApiConfig, AuthConfig, CacheCodecConfig, CacheConfig, CacheInvalidationConfig,
+    CacheProviderType, CacheSnapshotConfig, CacheWarmupConfig, DatabaseConfig, LoggingConfig,
+    RedisCacheConfig, ReliabilityConfig, RepositoryCacheConfig, ServerConfig, TieredCacheConfig,
 };
 
 use lazy_static::lazy_static;
@@ -34,6 +35,7 @@ impl Default for AppConfig {
             api: ApiConfig {
                 petstore_url: String::from("https://petstore3.swagger.io/api/v3"),
                 api_key: None,
+                upstreams: Default::default(),
             },
             logging: LoggingConfig {
                 level: String::from("info"),
@@ -42,14 +44,25 @@ impl Default for AppConfig {
             cache: CacheConfig {
                 enabled: true,
                 max_capacity: 100,
+                max_bytes: 0,
                 ttl_seconds: 3600,
                 reconnect_interval_seconds: 30,
+                provider: CacheProviderType::Memory,
+                redis: RedisCacheConfig::default(),
+                tiered: TieredCacheConfig::default(),
+                resources: Default::default(),
+                warmup: CacheWarmupConfig::default(),
+                snapshot: CacheSnapshotConfig::default(),
+                invalidation: CacheInvalidationConfig::default(),
+                codec: CacheCodecConfig::default(),
+                repository: RepositoryCacheConfig::default(),
             },
             database: DatabaseConfig::default(),
             auth: AuthConfig::default(),
             reliability: ReliabilityConfig::default(),
             openapi: app_config::OpenApiConfig::default(),
             endpoint_security: app_config::EndpointSecurityConfig::default(),
+            health: app_config::HealthConfig::default(),
         }
     }
 }
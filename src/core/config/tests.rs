@@ -32,6 +32,220 @@ fn test_default_cache_config() {
     assert_eq!(cache.ttl_seconds, 0);
     assert_eq!(cache.max_capacity, 0);
     assert_eq!(cache.reconnect_interval_seconds, 0); // Updated to match actual default
+    assert_eq!(cache.provider, CacheProviderType::Memory);
+    assert_eq!(cache.redis.url, "redis://127.0.0.1:6379");
+}
+
+#[test]
+fn test_cache_provider_from_yaml() {
+    let yaml = r#"
+enabled: true
+ttl_seconds: 60
+max_capacity: 100
+reconnect_interval_seconds: 10
+provider: fallback
+redis:
+  url: redis://cache:6380/2
+  password: secret
+"#;
+    let cache: CacheConfig = config::Config::builder()
+        .add_source(config::File::from_str(yaml, config::FileFormat::Yaml))
+        .build()
+        .unwrap()
+        .try_deserialize()
+        .unwrap();
+
+    assert_eq!(cache.provider, CacheProviderType::Fallback);
+    assert_eq!(cache.redis.url, "redis://cache:6380/2");
+    assert_eq!(cache.redis.password.as_deref(), Some("secret"));
+    assert_eq!(cache.redis.pool_size, 8);
+    assert_eq!(cache.redis.key_prefix, "rust-backend:");
+    assert!(cache.resources.is_empty());
+}
+
+#[test]
+fn test_tiered_cache_from_yaml() {
+    let yaml = r#"
+enabled: true
+ttl_seconds: 300
+max_capacity: 1000
+provider: tiered
+tiered:
+  l1_ttl_seconds: 5
+"#;
+    let cache: CacheConfig = config::Config::builder()
+        .add_source(config::File::from_str(yaml, config::FileFormat::Yaml))
+        .build()
+        .unwrap()
+        .try_deserialize()
+        .unwrap();
+
+    assert_eq!(cache.provider, CacheProviderType::Tiered);
+    assert_eq!(cache.provider.to_string(), "tiered");
+    assert_eq!(cache.tiered.l1_ttl_seconds, 5);
+    assert_eq!(cache.tiered.l1_max_capacity, 10_000);
+}
+
+#[test]
+fn test_repository_cache_from_yaml() {
+    let yaml = r#"
+enabled: true
+ttl_seconds: 300
+max_capacity: 1000
+repository:
+  enabled: true
+  write_mode: write_back
+"#;
+    let cache: CacheConfig = config::Config::builder()
+        .add_source(config::File::from_str(yaml, config::FileFormat::Yaml))
+        .build()
+        .unwrap()
+        .try_deserialize()
+        .unwrap();
+
+    assert!(cache.repository.enabled);
+    assert_eq!(cache.repository.write_mode, WriteMode::WriteBack);
+    assert_eq!(cache.repository.write_back_interval_ms, 500);
+
+    let defaults = CacheConfig::default();
+    assert!(!defaults.repository.enabled);
+    assert_eq!(defaults.repository.write_mode, WriteMode::WriteThrough);
+}
+
+#[test]
+fn test_cache_warmup_and_snapshot_from_yaml() {
+    let yaml = r#"
+enabled: true
+ttl_seconds: 300
+max_capacity: 1000
+warmup:
+  enabled: true
+  hot_keys: 50
+  ids:
+    pet: ["1", "2"]
+snapshot:
+  enabled: true
+"#;
+    let cache: CacheConfig = config::Config::builder()
+        .add_source(config::File::from_str(yaml, config::FileFormat::Yaml))
+        .build()
+        .unwrap()
+        .try_deserialize()
+        .unwrap();
+
+    assert!(cache.warmup.enabled);
+    assert_eq!(cache.warmup.hot_keys, 50);
+    assert_eq!(cache.warmup.concurrency, 8);
+    assert_eq!(cache.warmup.ids["pet"], vec!["1", "2"]);
+    assert!(cache.snapshot.enabled);
+    assert_eq!(cache.snapshot.path, "data/cache-snapshot.json");
+
+    // Both are off unless configured
+    let defaults = CacheConfig::default();
+    assert!(!defaults.warmup.enabled);
+    assert!(!defaults.snapshot.enabled);
+}
+
+#[test]
+fn test_cache_invalidation_from_yaml() {
+    let yaml = r#"
+enabled: true
+ttl_seconds: 300
+max_capacity: 1000
+invalidation:
+  enabled: true
+  backend: postgres
+"#;
+    let cache: CacheConfig = config::Config::builder()
+        .add_source(config::File::from_str(yaml, config::FileFormat::Yaml))
+        .build()
+        .unwrap()
+        .try_deserialize()
+        .unwrap();
+
+    assert!(cache.invalidation.enabled);
+    assert_eq!(cache.invalidation.backend, InvalidationBackend::Postgres);
+    assert_eq!(cache.invalidation.channel, "cache_invalidation");
+
+    let defaults = CacheConfig::default();
+    assert!(!defaults.invalidation.enabled);
+    assert_eq!(defaults.invalidation.backend, InvalidationBackend::Redis);
+}
+
+#[test]
+fn test_cache_codec_from_yaml() {
+    let yaml = r#"
+enabled: true
+ttl_seconds: 300
+max_capacity: 1000
+codec:
+  compression: zstd
+  encryption:
+    enabled: true
+    active_key_id: "2024-06"
+    keys:
+      "2024-06": "MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY="
+"#;
+    let cache: CacheConfig = config::Config::builder()
+        .add_source(config::File::from_str(yaml, config::FileFormat::Yaml))
+        .build()
+        .unwrap()
+        .try_deserialize()
+        .unwrap();
+
+    assert_eq!(cache.codec.compression, CompressionAlgorithm::Zstd);
+    assert_eq!(cache.codec.compression_threshold_bytes, 1024);
+    assert!(cache.codec.encryption.enabled);
+    assert_eq!(cache.codec.encryption.active_key_id, "2024-06");
+    assert_eq!(cache.codec.encryption.keys.len(), 1);
+    assert!(!cache.codec.encryption.accept_plaintext_during_migration);
+
+    let defaults = CacheConfig::default();
+    assert_eq!(defaults.codec.compression, CompressionAlgorithm::None);
+    assert!(!defaults.codec.encryption.enabled);
+}
+
+#[test]
+fn test_cache_resources_from_yaml() {
+    let yaml = r#"
+enabled: true
+ttl_seconds: 300
+max_capacity: 1000
+resources:
+  pet:
+    ttl_seconds: 60
+    max_capacity: 500
+  user:
+    ttl_seconds: 0
+    tti_seconds: 120
+    stale_if_error_seconds: 600
+    not_found_ttl_seconds: 30
+    max_bytes: 1048576
+    budget_weight: 2
+"#;
+    let cache: CacheConfig = config::Config::builder()
+        .add_source(config::File::from_str(yaml, config::FileFormat::Yaml))
+        .build()
+        .unwrap()
+        .try_deserialize()
+        .unwrap();
+
+    assert_eq!(
+        cache.resources["pet"],
+        ResourceCacheConfig {
+            ttl_seconds: Some(60),
+            tti_seconds: None,
+            max_capacity: Some(500),
+            ..Default::default()
+        }
+    );
+    assert_eq!(cache.resources["user"].ttl_seconds, Some(0));
+    assert_eq!(cache.resources["user"].tti_seconds, Some(120));
+    assert_eq!(cache.resources["user"].stale_if_error_seconds, Some(600));
+    assert_eq!(cache.resources["user"].max_bytes, Some(1048576));
+    assert_eq!(cache.resources["user"].budget_weight, Some(2));
+    assert_eq!(cache.max_bytes, 0);
+    assert_eq!(cache.resources["user"].not_found_ttl_seconds, Some(30));
 }
 
 #[test]
@@ -135,6 +349,101 @@ fn test_environment_type_from_string() {
     ));
 }
 
+#[test]
+fn test_health_config_from_yaml() {
+    let yaml = r#"
+check_timeout_ms: 500
+"#;
+    let health: HealthConfig = config::Config::builder()
+        .add_source(config::File::from_str(yaml, config::FileFormat::Yaml))
+        .build()
+        .unwrap()
+        .try_deserialize()
+        .unwrap();
+
+    assert_eq!(health.check_timeout_ms, 500);
+    assert_eq!(health.cache_ttl_ms, 5000);
+    assert_eq!(AppConfig::default().health, HealthConfig::default());
+}
+
+#[test]
+fn test_oidc_auth_config_from_yaml() {
+    let yaml = r#"
+enabled: true
+debug: false
+provider: oidc
+allowed_algorithms: [ES256, EdDSA]
+jwks:
+  min_refetch_interval_seconds: 5
+policies:
+  - name: delete-pets
+    path: "/pets/{id}"
+    methods: [DELETE]
+    roles: [admin]
+    claims:
+      - claim: tid
+        one_of: [tenant-1]
+entra:
+  admin_roles: []
+  read_only_roles: []
+  full_access_roles: []
+oidc:
+  issuers:
+    - issuer: "https://keycloak.example.com/realms/partners/"
+      audience: backend
+      roles_claim: realm_access.roles
+    - issuer: "https://partner.eu.auth0.com/"
+      audience: "https://api.example.com"
+      discovery_url: "https://partner.eu.auth0.com/.well-known/openid-configuration"
+      scopes_claim: permissions
+"#;
+    let auth: AuthConfig = config::Config::builder()
+        .add_source(config::File::from_str(yaml, config::FileFormat::Yaml))
+        .build()
+        .unwrap()
+        .try_deserialize()
+        .unwrap();
+
+    assert_eq!(auth.provider, AuthProvider::Oidc);
+    let keycloak = &auth.oidc.issuers[0];
+    assert_eq!(keycloak.roles_claim, "realm_access.roles");
+    assert_eq!(keycloak.scopes_claim, "scope");
+    assert_eq!(
+        keycloak.discovery_url(),
+        "https://keycloak.example.com/realms/partners/.well-known/openid-configuration"
+    );
+    assert_eq!(auth.oidc.issuers[1].roles_claim, "roles");
+    assert_eq!(auth.oidc.issuers[1].scopes_claim, "permissions");
+
+    assert_eq!(
+        auth.allowed_algorithms,
+        vec![
+            jsonwebtoken::Algorithm::ES256,
+            jsonwebtoken::Algorithm::EdDSA
+        ]
+    );
+
+    assert_eq!(auth.jwks.min_refetch_interval_seconds, 5);
+    assert_eq!(auth.jwks.default_max_age_seconds, 3600);
+    assert!(auth.jwks.background_refresh);
+
+    let policy = &auth.policies[0];
+    assert_eq!(policy.methods, vec!["DELETE"]);
+    assert_eq!(policy.roles, vec!["admin"]);
+    assert!(policy.scopes.is_empty());
+    assert_eq!(policy.claims[0].claim, "tid");
+    assert!(AppConfig::default().auth.policies.is_empty());
+
+    assert_eq!(AppConfig::default().auth.provider, AuthProvider::Entra);
+    assert!(
+        !AppConfig::default()
+            .auth
+            .allowed_algorithms
+            .contains(&jsonwebtoken::Algorithm::HS256)
+    );
+    assert!(AppConfig::default().auth.oidc.issuers.is_empty());
+}
+
 #[test]
 fn test_server_addr() {
     let mut config = AppConfig {
@@ -182,6 +491,30 @@ fn test_petstore_api_url() {
     );
 }
 
+#[test]
+fn test_upstream_lookup() {
+    let mut config = AppConfig::default();
+    config.api.petstore_url = "https://petstore.example.com/api/v3/".to_string();
+    config.api.upstreams.insert(
+        "inventory".to_string(),
+        UpstreamConfig {
+            url: "https://inventory.example.com/".to_string(),
+            scope: Some("api://inventory/.default".to_string()),
+        },
+    );
+
+    let inventory = config.upstream("inventory").unwrap();
+    assert_eq!(inventory.url, "https://inventory.example.com");
+    assert_eq!(inventory.scope.as_deref(), Some("api://inventory/.default"));
+
+    // The Petstore URL doubles as the "petstore" upstream
+    let petstore = config.upstream("petstore").unwrap();
+    assert_eq!(petstore.url, "https://petstore.example.com/api/v3");
+    assert_eq!(petstore.scope, None);
+
+    assert!(config.upstream("unknown").is_none());
+}
+
 #[test]
 fn test_openapi_spec_path() {
     let config = AppConfig {
@@ -216,6 +216,20 @@ impl MockDatabaseConnection {
         users.iter().find(|u| u.email == email).cloned()
     }
 
+    /// Replace a user only if it was last updated at `expected_updated_at`
+    pub fn update_user_if_unmodified(
+        &self,
+        user: crate::repository::User,
+        expected_updated_at: chrono::DateTime<chrono::Utc>,
+    ) -> Option<crate::repository::User> {
+        let mut users = self.users.lock().unwrap();
+        let existing = users
+            .iter_mut()
+            .find(|u| u.id == user.id && u.updated_at == expected_updated_at)?;
+        *existing = user.clone();
+        Some(user)
+    }
+
     /// Save a user (create or update)
     pub fn save_user(&self, user: crate::repository::User) -> crate::repository::User {
         let mut users = self.users.lock().unwrap();
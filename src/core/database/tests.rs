@@ -478,6 +478,7 @@ async fn test_query_building_for_user_repository() {
         role: UserRole::User,
         created_at: Utc::now(),
         updated_at: Utc::now(),
+        subject: None,
     };
 
     // Test finding the user by ID
@@ -567,6 +568,7 @@ async fn test_add_user_to_mock_db() {
         role: UserRole::User,
         created_at: Utc::now(),
         updated_at: Utc::now(),
+        subject: None,
     };
 
     // Add the user to the mock database
@@ -602,6 +604,7 @@ async fn test_user_queries_by_criteria() {
         role: UserRole::User,
         created_at: Utc::now(),
         updated_at: Utc::now(),
+        subject: None,
     };
 
     let user2 = User {
@@ -613,6 +616,7 @@ async fn test_user_queries_by_criteria() {
         role: UserRole::Admin,
         created_at: Utc::now(),
         updated_at: Utc::now(),
+        subject: None,
     };
 
     mock_conn.add_user(user1.clone());
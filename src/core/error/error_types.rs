@@ -5,11 +5,15 @@ use axum::{
 use config::ConfigError;
 use metrics::counter;
 use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use std::{fmt, result};
 use thiserror::Error;
 use tracing::{error, warn};
 
+use crate::core::cache::FetchError;
+
 pub type Result<T> = result::Result<T, AppError>;
 
 // The struct to be returned from the API in case of an error
@@ -83,6 +87,133 @@ pub enum AppError {
 
     #[error("Internal server error: {0}")]
     InternalError(String),
+
+    #[error("{0}")]
+    Upstream(Box<UpstreamError>),
+}
+
+/// A failed call to an upstream API
+///
+/// Keeps the upstream's status and response headers so callers can decide
+/// whether to retry and honour `Retry-After`. It is reported to our own
+/// clients like the matching [`AppError`] variant: a 404 as not found, 400
+/// and 422 as a bad request, 429 as rate limited, and anything else
+/// (including connection failures) as an external service error.
+#[derive(Debug, Clone)]
+pub struct UpstreamError {
+    /// Name of the upstream API
+    pub api: String,
+
+    /// Status returned by the upstream, or `None` if no response arrived
+    pub status: Option<StatusCode>,
+
+    /// Response headers, e.g. `Retry-After` and rate-limit headers
+    pub headers: HeaderMap,
+
+    /// Description of the failure
+    pub message: String,
+
+    /// Number of attempts made before giving up
+    pub attempts: u32,
+}
+
+impl UpstreamError {
+    /// A failure with `status`, or a connection failure when `status` is `None`
+    pub fn new(
+        api: impl Into<String>,
+        status: Option<StatusCode>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            api: api.into(),
+            status,
+            headers: HeaderMap::new(),
+            message: message.into(),
+            attempts: 1,
+        }
+    }
+
+    /// Attach the upstream's response headers
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// The status we respond with on behalf of the upstream
+    pub fn status_code(&self) -> StatusCode {
+        match self.status {
+            Some(StatusCode::NOT_FOUND) => StatusCode::NOT_FOUND,
+            Some(StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY) => {
+                StatusCode::BAD_REQUEST
+            }
+            Some(StatusCode::TOO_MANY_REQUESTS) => StatusCode::TOO_MANY_REQUESTS,
+            _ => StatusCode::BAD_GATEWAY,
+        }
+    }
+
+    /// How long the upstream asked us to wait before trying again
+    ///
+    /// Reads `Retry-After` (seconds or an HTTP date), falling back to the
+    /// `RateLimit-Reset`/`X-RateLimit-Reset` headers (seconds, or a Unix
+    /// timestamp) when the upstream rate limited us.
+    pub fn retry_after(&self) -> Option<Duration> {
+        if let Some(value) = header_str(&self.headers, RETRY_AFTER.as_str()) {
+            return match value.parse::<u64>() {
+                Ok(seconds) => Some(Duration::from_secs(seconds)),
+                Err(_) => chrono::DateTime::parse_from_rfc2822(value).ok().map(|at| {
+                    (at.with_timezone(&chrono::Utc) - chrono::Utc::now())
+                        .to_std()
+                        .unwrap_or_default()
+                }),
+            };
+        }
+
+        if self.status != Some(StatusCode::TOO_MANY_REQUESTS) {
+            return None;
+        }
+        let reset = header_str(&self.headers, "ratelimit-reset")
+            .or_else(|| header_str(&self.headers, "x-ratelimit-reset"))?
+            .parse::<u64>()
+            .ok()?;
+
+        // Some upstreams send a Unix timestamp rather than a number of seconds
+        const UNIX_TIMESTAMP_MIN: u64 = 1_000_000_000;
+        Some(Duration::from_secs(if reset >= UNIX_TIMESTAMP_MIN {
+            reset.saturating_sub(chrono::Utc::now().timestamp().max(0) as u64)
+        } else {
+            reset
+        }))
+    }
+
+    fn kind(&self) -> &'static str {
+        match self.status_code() {
+            StatusCode::NOT_FOUND => "Not found",
+            StatusCode::BAD_REQUEST => "Bad request",
+            StatusCode::TOO_MANY_REQUESTS => "Rate limited",
+            _ => "External service error",
+        }
+    }
+}
+
+// Prefixed like the message of the matching AppError variant
+impl fmt::Display for UpstreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind(), self.message)?;
+        if self.attempts > 1 {
+            write!(f, " (after {} attempts)", self.attempts)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<UpstreamError> for AppError {
+    fn from(error: UpstreamError) -> Self {
+        AppError::Upstream(Box::new(error))
+    }
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name)?.to_str().ok().map(str::trim)
 }
 
 impl AppError {
@@ -102,6 +233,8 @@ impl AppError {
             AppError::ConfigError(_) => ErrorSeverity::High,
             AppError::IoError(_) => ErrorSeverity::High,
             AppError::InternalError(_) => ErrorSeverity::High,
+            AppError::Upstream(e) if e.status_code().is_client_error() => ErrorSeverity::Medium,
+            AppError::Upstream(_) => ErrorSeverity::High,
         }
     }
 
@@ -121,6 +254,12 @@ impl AppError {
             AppError::CacheError(_) => "cache_error",
             AppError::ValidationError(_) => "validation_error",
             AppError::InternalError(_) => "internal_error",
+            AppError::Upstream(e) => match e.status_code() {
+                StatusCode::NOT_FOUND => "not_found",
+                StatusCode::BAD_REQUEST => "bad_request",
+                StatusCode::TOO_MANY_REQUESTS => "rate_limited",
+                _ => "external_service_error",
+            },
         }
         .to_string()
     }
@@ -141,6 +280,60 @@ impl AppError {
             AppError::CacheError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::ValidationError(_) => StatusCode::UNPROCESSABLE_ENTITY,
             AppError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Upstream(e) => e.status_code(),
+        }
+    }
+
+    /// The upstream failure behind this error, if any
+    pub fn upstream(&self) -> Option<&UpstreamError> {
+        match self {
+            AppError::Upstream(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// Record how many attempts were made before this error was returned
+    pub fn with_attempts(self, attempts: u32) -> Self {
+        match self {
+            AppError::Upstream(mut e) => {
+                e.attempts = attempts;
+                AppError::Upstream(e)
+            }
+            AppError::ExternalServiceError(message) if attempts > 1 => {
+                AppError::ExternalServiceError(format!("{} (after {} attempts)", message, attempts))
+            }
+            other => other,
+        }
+    }
+}
+
+// Lets fetch errors pass through the resource caches unchanged
+impl FetchError for AppError {
+    fn is_not_found(&self) -> bool {
+        match self {
+            AppError::NotFound(_) => true,
+            AppError::Upstream(e) => e.status == Some(StatusCode::NOT_FOUND),
+            _ => false,
+        }
+    }
+
+    // Errors from other libraries cannot be cloned and are copied by message
+    fn duplicate(&self) -> Self {
+        match self {
+            AppError::ConfigError(e) => AppError::ConfigError(ConfigError::Message(e.to_string())),
+            AppError::ClientError(e) => AppError::ExternalServiceError(e.to_string()),
+            AppError::IoError(e) => AppError::IoError(std::io::Error::new(e.kind(), e.to_string())),
+            AppError::NotFound(m) => AppError::NotFound(m.clone()),
+            AppError::BadRequest(m) => AppError::BadRequest(m.clone()),
+            AppError::Unauthorized(m) => AppError::Unauthorized(m.clone()),
+            AppError::Forbidden(m) => AppError::Forbidden(m.clone()),
+            AppError::RateLimited(m) => AppError::RateLimited(m.clone()),
+            AppError::ExternalServiceError(m) => AppError::ExternalServiceError(m.clone()),
+            AppError::DatabaseError(m) => AppError::DatabaseError(m.clone()),
+            AppError::CacheError(m) => AppError::CacheError(m.clone()),
+            AppError::ValidationError(m) => AppError::ValidationError(m.clone()),
+            AppError::InternalError(m) => AppError::InternalError(m.clone()),
+            AppError::Upstream(e) => AppError::Upstream(e.clone()),
         }
     }
 }
@@ -152,6 +345,10 @@ impl IntoResponse for AppError {
         let error_type = self.error_type();
         let error_message = self.to_string();
         let severity = self.severity();
+        let retry_after = self
+            .upstream()
+            .filter(|e| e.status_code() == StatusCode::TOO_MANY_REQUESTS)
+            .and_then(|e| e.retry_after());
 
         // Add detailed error info for internal errors if not in production
         let details = if status.is_server_error() && !cfg!(feature = "production") {
@@ -179,7 +376,7 @@ impl IntoResponse for AppError {
         }
 
         // Return the HTTP response
-        (
+        let mut response = (
             status,
             Json(ErrorResponse {
                 code: status.as_u16(),
@@ -188,7 +385,16 @@ impl IntoResponse for AppError {
                 details,
             }),
         )
-            .into_response()
+            .into_response();
+
+        // Pass the upstream's back-off request on to our client
+        if let Some(retry_after) = retry_after {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(retry_after.as_secs().max(1)));
+        }
+
+        response
     }
 }
 
@@ -301,6 +507,84 @@ mod tests {
         assert_eq!(error.to_string(), "Bad request: invalid input");
     }
 
+    #[test]
+    fn test_upstream_error_mapping() {
+        let not_found = AppError::from(UpstreamError::new(
+            "Petstore",
+            Some(StatusCode::NOT_FOUND),
+            "pet with ID 1 not found (HTTP 404)",
+        ));
+        assert_eq!(not_found.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(not_found.error_type(), "not_found");
+        assert_eq!(
+            not_found.to_string(),
+            "Not found: pet with ID 1 not found (HTTP 404)"
+        );
+
+        let unavailable = UpstreamError::new(
+            "Petstore",
+            Some(StatusCode::SERVICE_UNAVAILABLE),
+            "HTTP 503",
+        );
+        let error = AppError::from(unavailable).with_attempts(3);
+        assert_eq!(error.status_code(), StatusCode::BAD_GATEWAY);
+        assert_eq!(error.severity(), ErrorSeverity::High);
+        assert_eq!(error.upstream().unwrap().attempts, 3);
+        assert_eq!(
+            error.to_string(),
+            "External service error: HTTP 503 (after 3 attempts)"
+        );
+    }
+
+    #[test]
+    fn test_upstream_retry_after() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        let error =
+            UpstreamError::new("Petstore", Some(StatusCode::TOO_MANY_REQUESTS), "slow down")
+                .with_headers(headers);
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(7)));
+
+        let response = AppError::from(error).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[RETRY_AFTER], "7");
+
+        // Rate-limit reset headers count only when rate limited
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("2"));
+        let error =
+            UpstreamError::new("Petstore", Some(StatusCode::TOO_MANY_REQUESTS), "slow down")
+                .with_headers(headers.clone());
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(2)));
+        let error = UpstreamError::new("Petstore", Some(StatusCode::BAD_GATEWAY), "down")
+            .with_headers(headers);
+        assert_eq!(error.retry_after(), None);
+    }
+
+    #[test]
+    fn test_only_not_found_errors_are_cached_as_not_found() {
+        assert!(AppError::NotFound("pet 1".into()).is_not_found());
+        assert!(
+            AppError::from(UpstreamError::new(
+                "Petstore",
+                Some(StatusCode::NOT_FOUND),
+                "HTTP 404"
+            ))
+            .is_not_found()
+        );
+
+        // Other errors are not, whatever their message says
+        assert!(
+            !AppError::from(UpstreamError::new(
+                "Petstore",
+                Some(StatusCode::SERVICE_UNAVAILABLE),
+                "Not found"
+            ))
+            .is_not_found()
+        );
+        assert!(!AppError::ExternalServiceError("Not found: pet 1".into()).is_not_found());
+    }
+
     #[test]
     fn test_error_severity_display() {
         // Test the Display implementation for error severity
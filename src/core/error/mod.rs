@@ -4,7 +4,7 @@ pub mod middleware;
 pub mod result_ext;
 
 // Re-export common types and functions
-pub use error_types::{AppError, ErrorResponse, ErrorSeverity, Result};
+pub use error_types::{AppError, ErrorResponse, ErrorSeverity, Result, UpstreamError};
 pub use logger::{LogInfo, LogLevel, log, log_error};
 pub use middleware::RequestTrackingLayer;
 pub use middleware::{RequestId, RequestIdExt, generate_request_id};
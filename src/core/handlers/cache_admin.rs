@@ -0,0 +1,265 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::{
+    cache::{CacheBackend, CacheProvider},
+    core::cache::{CacheRegistry, get_cache_stats_with_metrics},
+    core::router::AppState,
+    error::{AppError, Result},
+    models::{
+        CacheEntryResponse, CacheEvictionResponse, CacheListResponse, CacheSummary,
+        CacheWarmRequest, CacheWarmResponse,
+    },
+};
+
+/// Maximum number of IDs accepted by a single warm request
+const MAX_WARM_IDS: usize = 1000;
+
+/// Number of IDs loaded concurrently while warming a cache
+const WARM_CONCURRENCY: usize = 8;
+
+fn cache_registry(state: &AppState) -> Result<&CacheRegistry> {
+    state
+        .cache_registry
+        .as_ref()
+        .ok_or_else(|| AppError::NotFound("Cache is disabled".to_string()))
+}
+
+fn cache_summary(
+    registry: &CacheRegistry,
+    resource_type: &str,
+    metrics_text: &str,
+) -> Option<CacheSummary> {
+    let cache = registry.resource_cache_ops(resource_type)?;
+    let settings = cache.settings();
+
+    Some(CacheSummary {
+        resource_type: resource_type.to_string(),
+        ttl_seconds: settings.ttl_seconds.unwrap_or_default(),
+        tti_seconds: settings.tti_seconds.unwrap_or_default(),
+        max_capacity: settings.max_capacity.unwrap_or_default(),
+        stale_while_revalidate_seconds: settings.stale_while_revalidate_seconds.unwrap_or_default(),
+        stale_if_error_seconds: settings.stale_if_error_seconds.unwrap_or_default(),
+        not_found_ttl_seconds: settings.not_found_ttl_seconds.unwrap_or_default(),
+        max_bytes: settings.max_bytes.unwrap_or_default(),
+        bytes_used: cache.bytes_used(),
+        warmable: registry.has_loader(resource_type),
+        stats: get_cache_stats_with_metrics(registry, resource_type, metrics_text),
+    })
+}
+
+fn unknown_resource_type(resource_type: &str) -> AppError {
+    AppError::NotFound(format!(
+        "No cache registered for resource type: {}",
+        resource_type
+    ))
+}
+
+/// List registered resource caches with their settings and statistics
+pub async fn list_caches(State(state): State<Arc<AppState>>) -> Json<CacheListResponse> {
+    let provider = state
+        .cache_provider
+        .as_ref()
+        .map(|provider| provider.provider_type().to_string())
+        .unwrap_or_else(|| "none".to_string());
+
+    let Some(registry) = &state.cache_registry else {
+        return Json(CacheListResponse {
+            enabled: false,
+            provider,
+            caches: Vec::new(),
+        });
+    };
+
+    let metrics_text = state.metrics_handle.render();
+    let caches = registry
+        .resource_types()
+        .iter()
+        .filter_map(|resource_type| cache_summary(registry, resource_type, &metrics_text))
+        .collect();
+
+    Json(CacheListResponse {
+        enabled: true,
+        provider,
+        caches,
+    })
+}
+
+/// Get the settings and statistics for one resource cache
+pub async fn get_cache(
+    State(state): State<Arc<AppState>>,
+    Path(resource_type): Path<String>,
+) -> Result<Json<CacheSummary>> {
+    let registry = cache_registry(&state)?;
+    let metrics_text = state.metrics_handle.render();
+
+    cache_summary(registry, &resource_type, &metrics_text)
+        .map(Json)
+        .ok_or_else(|| unknown_resource_type(&resource_type))
+}
+
+/// The configured cache provider, if it keeps entries outside the registry
+fn remote_provider(state: &AppState) -> Option<&CacheBackend> {
+    state
+        .cache_provider
+        .as_deref()
+        .filter(|provider| !matches!(provider, CacheBackend::Memory(_)))
+}
+
+/// Remove every entry from one resource cache
+///
+/// The entries are also removed from a remote cache provider, if one is
+/// configured, and other replicas are told to clear their copies.
+pub async fn clear_cache(
+    State(state): State<Arc<AppState>>,
+    Path(resource_type): Path<String>,
+) -> Result<Json<CacheEvictionResponse>> {
+    let registry = cache_registry(&state)?;
+    let cache = registry
+        .resource_cache_ops(&resource_type)
+        .ok_or_else(|| unknown_resource_type(&resource_type))?;
+
+    let mut evicted = cache.clear().await;
+
+    if let Some(provider) = remote_provider(&state) {
+        // The provider may hold the same entries, so count the larger set
+        let removed = provider
+            .invalidate_all(&resource_type)
+            .await
+            .map_err(AppError::ExternalServiceError)?;
+        evicted = evicted.max(removed);
+    }
+
+    if let Some(bus) = &state.cache_invalidation
+        && let Err(e) = bus.publish_clear(&resource_type).await
+    {
+        warn!(
+            "⚠️ Failed to publish clear of {} cache: {}",
+            resource_type, e
+        );
+    }
+
+    info!(
+        "🧹 Cleared {} entries from {} cache",
+        evicted, resource_type
+    );
+
+    Ok(Json(CacheEvictionResponse {
+        resource_type,
+        key: None,
+        evicted,
+    }))
+}
+
+/// Get a single cached entry
+pub async fn get_cache_entry(
+    State(state): State<Arc<AppState>>,
+    Path((resource_type, key)): Path<(String, String)>,
+) -> Result<Json<CacheEntryResponse>> {
+    let registry = cache_registry(&state)?;
+    let cache = registry
+        .resource_cache_ops(&resource_type)
+        .ok_or_else(|| unknown_resource_type(&resource_type))?;
+
+    let value = cache.get_json(&key).await.ok_or_else(|| {
+        AppError::NotFound(format!("No cached {} with key: {}", resource_type, key))
+    })?;
+
+    Ok(Json(CacheEntryResponse {
+        resource_type,
+        key,
+        value,
+    }))
+}
+
+/// Evict a single cached entry
+///
+/// The entry is also removed from a remote cache provider, if one is
+/// configured, and other replicas are told to evict it.
+pub async fn evict_cache_entry(
+    State(state): State<Arc<AppState>>,
+    Path((resource_type, key)): Path<(String, String)>,
+) -> Result<Json<CacheEvictionResponse>> {
+    let registry = cache_registry(&state)?;
+    let cache = registry
+        .resource_cache_ops(&resource_type)
+        .ok_or_else(|| unknown_resource_type(&resource_type))?;
+
+    let mut evicted = u64::from(cache.remove(&key).await);
+
+    if let Some(provider) = remote_provider(&state) {
+        let provider_key = CacheBackend::cache_key(&resource_type, &key);
+        if provider.exists(&provider_key).await.unwrap_or(false) {
+            provider
+                .delete(&provider_key)
+                .await
+                .map_err(AppError::ExternalServiceError)?;
+            evicted = 1;
+        }
+    }
+
+    if evicted == 0 {
+        return Err(AppError::NotFound(format!(
+            "No cached {} with key: {}",
+            resource_type, key
+        )));
+    }
+
+    if let Some(bus) = &state.cache_invalidation
+        && let Err(e) = bus.publish(&resource_type, &key).await
+    {
+        warn!(
+            "⚠️ Failed to publish invalidation of {} {}: {}",
+            resource_type, key, e
+        );
+    }
+
+    info!("❌ Evicted {} {} from cache", resource_type, key);
+    Ok(Json(CacheEvictionResponse {
+        resource_type,
+        key: Some(key),
+        evicted,
+    }))
+}
+
+/// Load a list of IDs into a resource cache
+pub async fn warm_cache(
+    State(state): State<Arc<AppState>>,
+    Path(resource_type): Path<String>,
+    Json(request): Json<CacheWarmRequest>,
+) -> Result<Json<CacheWarmResponse>> {
+    let registry = cache_registry(&state)?;
+
+    if !registry.is_registered(&resource_type) {
+        return Err(unknown_resource_type(&resource_type));
+    }
+    if !registry.has_loader(&resource_type) {
+        return Err(AppError::BadRequest(format!(
+            "Resource type {} does not support warming",
+            resource_type
+        )));
+    }
+    if request.ids.len() > MAX_WARM_IDS {
+        return Err(AppError::BadRequest(format!(
+            "At most {} IDs can be warmed per request",
+            MAX_WARM_IDS
+        )));
+    }
+
+    let results = registry
+        .warm(&resource_type, request.ids, WARM_CONCURRENCY)
+        .await
+        .map_err(AppError::BadRequest)?;
+    let loaded = results.iter().filter(|result| result.loaded).count();
+
+    Ok(Json(CacheWarmResponse {
+        resource_type,
+        loaded,
+        failed: results.len() - loaded,
+        results,
+    }))
+}
@@ -1,88 +1,65 @@
-use axum::{Json, extract::State};
+use axum::{Json, extract::State, http::StatusCode};
 use chrono;
 use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 
+use crate::core::auth::JwksManager;
 use crate::core::router::AppState;
-use crate::models::{DependencyStatus, DetailedHealthResponse, HealthCheckResponse};
+use crate::models::{DependencyStatus, DetailedHealthResponse, HealthCheckResponse, HealthStatus};
 
 /// Handler for the simple health check endpoint
 ///
 /// This endpoint is designed for load balancers and monitoring systems.
-/// It returns minimal information, usually just whether the service is running.
-/// It also includes a basic check of the Petstore API's availability.
-pub async fn health_check(State(state): State<Arc<AppState>>) -> Json<HealthCheckResponse> {
+/// It returns minimal information, usually just whether the service is running,
+/// along with the checks of every resource in the API resource registry.
+/// Failing upstreams only degrade the service, so this responds with 200.
+pub async fn health_check(
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<HealthCheckResponse>) {
     // Calculate uptime
     let uptime = SystemTime::now()
         .duration_since(state.start_time)
         .unwrap_or_default();
 
-    // Check Petstore API connectivity
-    let mut deps = Vec::new();
-
-    // Add a quick check of the Petstore API
-    if !state.config.api.petstore_url.is_empty() {
-        let petstore_status = check_petstore_connectivity(&state).await;
-        deps.push(petstore_status);
-    }
+    let deps = registered_dependencies(&state).await;
+    let status = HealthStatus::aggregate(&deps, CRITICAL_DEPENDENCIES);
 
     // Simple health check with dependency info
-    Json(HealthCheckResponse {
-        status: "healthy".to_string(),
-        version: env!("CARGO_PKG_VERSION").to_string(),
-        uptime_seconds: uptime.as_secs(),
-        dependencies: Some(deps),
-    })
+    (
+        status_code(status),
+        Json(HealthCheckResponse {
+            status: status.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            uptime_seconds: uptime.as_secs(),
+            dependencies: Some(deps),
+        }),
+    )
 }
 
-/// Check if the Petstore API is available
-///
-/// Performs a lightweight health check to the Petstore API
-async fn check_petstore_connectivity(state: &Arc<AppState>) -> DependencyStatus {
-    let url = format!("{}/store/inventory", state.config.api.petstore_url);
-
-    // Set a short timeout for health checks (2 seconds)
-    let timeout = std::time::Duration::from_secs(2);
-
-    let client = &state.client;
-    let mut builder = client.get(&url).timeout(timeout);
-
-    // Add API key if configured
-    if let Some(api_key) = &state.config.api.api_key {
-        builder = builder.header("api_key", api_key);
-    }
-
-    // Perform the request
-    let status = match builder.send().await {
-        Ok(response) => {
-            if response.status().is_success() {
-                "up".to_string()
-            } else {
-                format!("degraded (status {})", response.status())
-            }
-        }
-        Err(e) => {
-            let error_message = e.to_string();
-            let truncated_message = if error_message.len() > 100 {
-                format!("{}...", &error_message[..100])
-            } else {
-                error_message
-            };
-            format!("down ({})", truncated_message)
-        }
-    };
-
-    // Add details with timestamp of the health check
-    let mut details = BTreeMap::new();
-    details.insert("checked_at".to_string(), chrono::Utc::now().to_rfc3339());
+/// Dependencies whose failure takes the whole service down
+const CRITICAL_DEPENDENCIES: &[&str] = &["database"];
 
-    details.insert("endpoint".to_string(), "/store/inventory".to_string());
+/// Run the health checks registered in the API resource registry
+///
+/// Results are reused for `health.cache_ttl_ms` so probes cannot flood upstreams.
+async fn registered_dependencies(state: &Arc<AppState>) -> Vec<DependencyStatus> {
+    let health = &state.config.health;
+    state
+        .resource_registry
+        .cached_health_checks(
+            state,
+            Duration::from_millis(health.check_timeout_ms),
+            Duration::from_millis(health.cache_ttl_ms),
+        )
+        .await
+}
 
-    DependencyStatus {
-        name: "petstore_api".to_string(),
-        status,
-        details: Some(details),
+/// The HTTP status for a verdict: 503 when down, so load balancers stop routing here
+fn status_code(status: HealthStatus) -> StatusCode {
+    match status {
+        HealthStatus::Down => StatusCode::SERVICE_UNAVAILABLE,
+        HealthStatus::Up | HealthStatus::Degraded => StatusCode::OK,
     }
 }
 
@@ -93,7 +70,7 @@ async fn check_petstore_connectivity(state: &Arc<AppState>) -> DependencyStatus
 /// This is typically secured in production environments.
 pub async fn detailed_health_check(
     State(state): State<Arc<AppState>>,
-) -> Json<DetailedHealthResponse> {
+) -> (StatusCode, Json<DetailedHealthResponse>) {
     // Calculate uptime
     let uptime = SystemTime::now()
         .duration_since(state.start_time)
@@ -283,12 +260,67 @@ pub async fn detailed_health_check(
         details: auth_details,
     });
 
+    // Freshness of the identity providers' signing keys
+    if state.config.auth.enabled {
+        dependencies.extend(JwksManager::health_statuses());
+    }
+
+    // Registered API resources
+    dependencies.extend(registered_dependencies(&state).await);
+    let status = HealthStatus::aggregate(&dependencies, CRITICAL_DEPENDENCIES);
+
     // Build full response
-    Json(DetailedHealthResponse {
-        status: "healthy".to_string(),
-        version: env!("CARGO_PKG_VERSION").to_string(),
-        uptime_seconds: uptime.as_secs(),
-        environment: state.config.environment.to_string(),
-        dependencies,
-    })
+    (
+        status_code(status),
+        Json(DetailedHealthResponse {
+            status: status.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            uptime_seconds: uptime.as_secs(),
+            environment: state.config.environment.to_string(),
+            dependencies,
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependency(name: &str, status: &str) -> DependencyStatus {
+        DependencyStatus {
+            name: name.to_string(),
+            status: status.to_string(),
+            details: None,
+        }
+    }
+
+    #[test]
+    fn test_verdict_from_dependencies() {
+        let verdict = |deps: &[DependencyStatus]| {
+            let status = HealthStatus::aggregate(deps, CRITICAL_DEPENDENCIES);
+            (status, status_code(status))
+        };
+
+        assert_eq!(
+            verdict(&[
+                dependency("database", "up"),
+                dependency("cache", "disabled")
+            ]),
+            (HealthStatus::Up, StatusCode::OK)
+        );
+        assert_eq!(
+            verdict(&[
+                dependency("database", "up"),
+                dependency("Petstore (pet)", "down (timed out)")
+            ]),
+            (HealthStatus::Degraded, StatusCode::OK)
+        );
+        assert_eq!(
+            verdict(&[
+                dependency("database", "down"),
+                dependency("Petstore (pet)", "degraded")
+            ]),
+            (HealthStatus::Down, StatusCode::SERVICE_UNAVAILABLE)
+        );
+    }
 }
@@ -7,6 +7,9 @@ pub mod health;
 // Debug and management actuator endpoints
 pub mod actuator;
 
+// Cache administration actuator endpoints
+pub mod cache_admin;
+
 // API documentation handlers
 pub mod docs;
 
@@ -213,10 +213,13 @@ mod tests {
             client: Client::new(),
             start_time: SystemTime::now(),
             cache_registry: None,
+            cache_provider: None,
             metrics_handle,
             token_client: None,
             resource_registry: ApiResourceRegistry::new(),
             db_pool: None,
+            cache_invalidation: None,
+            user_repository: None,
         });
 
         // Create a router
@@ -26,6 +26,7 @@ use crate::{
         middleware::{EntraAuthConfig, RoleRequirement},
     },
     core::config::app_config::AppConfig,
+    core::error::AppError,
     handlers::logging,
     models::{ApiResponse, DetailedHealthResponse, HealthCheckResponse},
     reliability,
@@ -39,22 +40,37 @@ pub struct AppState {
     pub config: AppConfig,
     pub start_time: SystemTime,
     pub cache_registry: Option<crate::core::cache::CacheRegistry>,
+    /// Cache backend selected by `cache.provider`; `None` when caching is disabled
+    pub cache_provider: Option<Arc<crate::cache::CacheBackend>>,
     pub metrics_handle: PrometheusHandle,
     pub token_client: Option<EntraTokenClient>,
     pub resource_registry: crate::utils::api_resource::ApiResourceRegistry,
     pub db_pool: Option<Arc<Box<dyn crate::core::database::PgPool>>>,
+    /// Broadcast of cache evictions to other replicas; `None` unless `cache.invalidation` is enabled
+    pub cache_invalidation: Option<Arc<crate::cache::InvalidationBus>>,
+    /// User repository with cached lookups; `None` unless the database and `cache.repository` are enabled
+    pub user_repository: Option<Arc<CachedUserRepository>>,
 }
 
+/// The user repository with `find_by_id` cached in the cache registry
+pub type CachedUserRepository =
+    crate::repository::CachedRepository<crate::repository::UserRepository, crate::repository::User>;
+
 /// Create the core application router with middleware
-pub fn create_core_app_router(state: Arc<AppState>, user_routes: Router) -> Router {
+///
+/// Fails if the authorization policies are malformed.
+pub fn create_core_app_router(
+    state: Arc<AppState>,
+    user_routes: Router,
+) -> Result<Router, AppError> {
     // Create logging middleware
     let logging = middleware::from_fn_with_state(state.clone(), logging::log_request);
 
     // Get the core routes that should not be modified by users
-    let core_routes = CoreRouter::create_core_routes(state.clone());
+    let core_routes = CoreRouter::create_core_routes(state.clone())?;
 
     // Combine core routes with user-defined routes and add all middleware
-    Router::new()
+    Ok(Router::new()
         .merge(core_routes)
         .merge(user_routes)
         .layer(logging)
@@ -90,7 +106,7 @@ pub fn create_core_app_router(state: Arc<AppState>, user_routes: Router) -> Rout
                     next.run(req).await
                 }
             },
-        ))
+        )))
 }
 
 /// Initialize the application state and resources
@@ -116,7 +132,9 @@ pub async fn init_app_state() -> (Arc<AppState>, SocketAddr) {
             true,
             config.cache.max_capacity,
             config.cache.ttl_seconds,
-        );
+        )
+        .with_resource_configs(config.cache.resources.clone())
+        .with_memory_budget(config.cache.max_bytes);
 
         Some(registry)
     } else {
@@ -124,6 +142,17 @@ pub async fn init_app_state() -> (Arc<AppState>, SocketAddr) {
         None
     };
 
+    // Select the cache backend; the memory provider shares the registry above
+    let cache_provider = match &cache_registry {
+        Some(registry) => {
+            let backend = crate::cache::CacheBackend::from_app_config(&config, registry);
+            info!("🔧 Cache provider: {}", backend.provider_type());
+            backend.start().await;
+            Some(Arc::new(backend))
+        }
+        None => None,
+    };
+
     // Initialize database if enabled
     let db_pool = if config.database.enabled {
         match crate::core::init_database(&config.database).await {
@@ -141,6 +170,44 @@ pub async fn init_app_state() -> (Arc<AppState>, SocketAddr) {
         None
     };
 
+    // Share evictions with the other replicas
+    let cache_invalidation =
+        match crate::cache::InvalidationBus::from_app_config(&config, cache_registry.clone()) {
+            Ok(Some(mut bus)) => {
+                if let Some(near_cache) = cache_provider
+                    .as_deref()
+                    .and_then(|provider| provider.near_cache_registry())
+                {
+                    bus = bus.with_near_cache(near_cache);
+                }
+                let bus = Arc::new(bus);
+                info!("🔧 Cache invalidation bus: {}", bus.transport_name());
+                bus.start(Duration::from_secs(config.cache.reconnect_interval_seconds));
+                Some(bus)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                tracing::error!("❌ Failed to set up cache invalidation bus: {}", e);
+                None
+            }
+        };
+
+    // Cache user lookups in front of the database
+    let user_repository = match (&db_pool, &cache_registry) {
+        (Some(pool), Some(registry)) if config.cache.repository.enabled => {
+            let settings = config.cache.repository;
+            let repository = Arc::new(CachedUserRepository::new(
+                Arc::new(crate::repository::UserRepository::new(pool.clone())),
+                registry.clone(),
+                settings.write_mode,
+            ));
+            info!("🔧 User repository cache: {}", settings.write_mode);
+            repository.start_write_back(Duration::from_millis(settings.write_back_interval_ms));
+            Some(repository)
+        }
+        _ => None,
+    };
+
     // Create API resource registry
     let resource_registry = crate::utils::api_resource::ApiResourceRegistry::new();
 
@@ -150,6 +217,7 @@ pub async fn init_app_state() -> (Arc<AppState>, SocketAddr) {
         config: config.clone(),
         start_time,
         cache_registry: cache_registry.clone(),
+        cache_provider,
         metrics_handle,
         token_client: if config.auth.enabled {
             Some(EntraTokenClient::from_config(&config))
@@ -158,6 +226,8 @@ pub async fn init_app_state() -> (Arc<AppState>, SocketAddr) {
         },
         resource_registry,
         db_pool,
+        cache_invalidation,
+        user_repository,
     });
 
     // Register pet resources in the cache registry
@@ -171,6 +241,21 @@ pub async fn init_app_state() -> (Arc<AppState>, SocketAddr) {
             Ok(_) => info!("✅ Successfully registered pet resource type in cache registry"),
             Err(e) => info!("⚠️ Failed to register pet resource: {}", e),
         }
+
+        // Allow the pet cache to be pre-warmed from the admin endpoints
+        if let Err(e) = crate::handlers::examples::pet::register_pet_cache_loader(&state) {
+            info!("⚠️ Failed to register pet cache loader: {}", e);
+        }
+    }
+
+    // Report Petstore connectivity from the health endpoints
+    if let Err(e) = crate::handlers::examples::pet::register_pet_health_check(&state) {
+        info!("⚠️ Failed to register pet health check: {}", e);
+    }
+
+    // Restore the last snapshot and preload configured IDs before serving traffic
+    if let Some(registry) = &cache_registry {
+        crate::core::cache::restore_and_warm(registry, &config.cache).await;
     }
 
     // Start metrics updater for the new cache registry
@@ -186,6 +271,24 @@ pub async fn init_app_state() -> (Arc<AppState>, SocketAddr) {
     (state, addr)
 }
 
+/// Release application resources once the server has stopped
+///
+/// Flushes pending write-back repository writes, then writes the cache
+/// snapshot (if `cache.snapshot` is enabled) so the next instance starts with
+/// warm caches.
+pub async fn shutdown_app_state(state: &AppState) {
+    if let Some(repository) = &state.user_repository {
+        match repository.flush().await {
+            Ok(written) => info!("💾 Flushed {} pending user writes", written),
+            Err(e) => tracing::error!("❌ Failed to flush pending user writes: {}", e),
+        }
+    }
+
+    if let Some(registry) = &state.cache_registry {
+        crate::core::cache::persist_snapshot(registry, &state.config.cache).await;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -224,10 +327,13 @@ mod tests {
             config,
             start_time: SystemTime::now(),
             cache_registry: None,
+            cache_provider: None,
             metrics_handle,
             token_client: None,
             resource_registry: crate::utils::api_resource::ApiResourceRegistry::new(),
             db_pool: None,
+            cache_invalidation: None,
+            user_repository: None,
         })
     }
 
@@ -260,7 +366,7 @@ mod tests {
         let user_routes = Router::new().route("/test", get(test_handler));
 
         // Create app router by merging core and user routes
-        let app_router = create_core_app_router(state, user_routes);
+        let app_router = create_core_app_router(state, user_routes).unwrap();
 
         // Test user route
         let response = send_request(app_router, "/test", Method::GET, None).await;
@@ -293,10 +399,13 @@ mod tests {
                 config,
                 start_time: SystemTime::now(),
                 cache_registry: None,
+                cache_provider: None,
                 metrics_handle,
                 token_client: None,
                 resource_registry: crate::utils::api_resource::ApiResourceRegistry::new(),
                 db_pool: None,
+                cache_invalidation: None,
+                user_repository: None,
             })
         };
 
@@ -311,7 +420,7 @@ mod tests {
         let user_routes = Router::new().route("/slow", get(slow_handler));
 
         // Create app router with the timeout middleware
-        let app_router = create_core_app_router(state, user_routes);
+        let app_router = create_core_app_router(state, user_routes).unwrap();
 
         // Test slow route - should time out
         let response = send_request(app_router, "/slow", Method::GET, None).await;
@@ -329,7 +438,7 @@ mod tests {
         let user_routes = Router::new().route("/echo-headers", get(echo_headers));
 
         // Create app router with middleware
-        let app_router = create_core_app_router(state, user_routes);
+        let app_router = create_core_app_router(state, user_routes).unwrap();
 
         // Create custom headers
         let mut headers = HeaderMap::new();
@@ -1,11 +1,11 @@
 use axum::{
     extract::State,
-    routing::{Router, get},
+    routing::{Router, get, post},
 };
 use std::sync::Arc;
 
 use crate::{
-    core::auth::EntraAuthLayer,
+    core::{auth::EntraAuthLayer, error::AppError, handlers::cache_admin},
     handlers::{self, actuator, health},
 };
 
@@ -16,12 +16,14 @@ pub struct CoreRouter;
 
 impl CoreRouter {
     /// Create the essential core routes that should not be modified by users
-    pub fn create_core_routes(state: Arc<AppState>) -> Router {
+    ///
+    /// Fails if the authorization policies are malformed.
+    pub fn create_core_routes(state: Arc<AppState>) -> Result<Router, AppError> {
         // Define whether auth is enabled
         let auth_enabled = state.config.auth.enabled;
 
         // Create auth middleware for admin access
-        let admin_auth = EntraAuthLayer::from_app_config_require_admin_role(&state.config);
+        let admin_auth = EntraAuthLayer::from_app_config_require_admin_role(&state.config)?;
 
         // Public core routes - accessible without authentication
         let public_routes = Router::new().route("/health", get(health::health_check));
@@ -31,7 +33,17 @@ impl CoreRouter {
             .route("/health", get(health::detailed_health_check))
             .route("/info", get(actuator::info))
             .route("/docs", get(handlers::docs::swagger_ui_handler))
-            .route("/docs/{*file}", get(handlers::docs::openapi_spec_handler));
+            .route("/docs/{*file}", get(handlers::docs::openapi_spec_handler))
+            .route("/cache", get(cache_admin::list_caches))
+            .route(
+                "/cache/{resource_type}",
+                get(cache_admin::get_cache).delete(cache_admin::clear_cache),
+            )
+            .route("/cache/{resource_type}/warm", post(cache_admin::warm_cache))
+            .route(
+                "/cache/{resource_type}/{key}",
+                get(cache_admin::get_cache_entry).delete(cache_admin::evict_cache_entry),
+            );
 
         // Apply authentication layers if enabled
         let actuator_routes = if auth_enabled {
@@ -41,10 +53,10 @@ impl CoreRouter {
         };
 
         // Return only the core routes
-        Router::new()
+        Ok(Router::new()
             .merge(public_routes)
             .nest("/actuator", actuator_routes)
-            .with_state(state)
+            .with_state(state))
     }
 }
 
@@ -80,10 +92,13 @@ mod tests {
             config,
             start_time: SystemTime::now(),
             cache_registry: None,
+            cache_provider: None,
             metrics_handle,
             token_client: None,
             resource_registry: ApiResourceRegistry::new(),
             db_pool: None,
+            cache_invalidation: None,
+            user_repository: None,
         })
     }
 
@@ -104,7 +119,7 @@ mod tests {
         let state = create_test_state(false);
 
         // Create the core routes
-        let router = CoreRouter::create_core_routes(state);
+        let router = CoreRouter::create_core_routes(state).unwrap();
 
         // Test the public health endpoint
         let response = send_request(router, "/health", Method::GET).await;
@@ -115,7 +130,7 @@ mod tests {
             .await
             .unwrap();
         let health_response: HealthCheckResponse = serde_json::from_slice(&body).unwrap();
-        assert_eq!(health_response.status, "healthy");
+        assert_eq!(health_response.status, "UP");
     }
 
     #[tokio::test]
@@ -124,7 +139,7 @@ mod tests {
         let state = create_test_state(false);
 
         // Create the core routes
-        let router = CoreRouter::create_core_routes(state);
+        let router = CoreRouter::create_core_routes(state).unwrap();
 
         // Test the actuator health endpoint
         let response = send_request(router.clone(), "/actuator/health", Method::GET).await;
@@ -135,20 +150,50 @@ mod tests {
             .await
             .unwrap();
         let health_response: DetailedHealthResponse = serde_json::from_slice(&body).unwrap();
-        assert_eq!(health_response.status, "healthy");
+        assert_eq!(health_response.status, "UP");
 
         // Test actuator info endpoint
         let response = send_request(router, "/actuator/info", Method::GET).await;
         assert_eq!(response.status(), StatusCode::OK);
     }
 
+    #[tokio::test]
+    async fn test_health_reports_registered_resource_checks() {
+        let state = create_test_state(false);
+        state
+            .resource_registry
+            .register::<Widget, _>(|_| {
+                Box::pin(async {
+                    crate::models::DependencyStatus {
+                        name: "WidgetService (widget)".to_string(),
+                        status: "degraded (status 500 Internal Server Error)".to_string(),
+                        details: None,
+                    }
+                })
+            })
+            .unwrap();
+        let router = CoreRouter::create_core_routes(state).unwrap();
+
+        // A failing upstream degrades the service but keeps it in rotation
+        let response = send_request(router.clone(), "/health", Method::GET).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let health = json_body(response).await;
+        assert_eq!(health["status"], "DEGRADED");
+        assert_eq!(health["dependencies"][0]["name"], "WidgetService (widget)");
+
+        let response = send_request(router, "/actuator/health", Method::GET).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let health = json_body(response).await;
+        assert_eq!(health["status"], "DEGRADED");
+    }
+
     #[tokio::test]
     async fn test_actuator_routes_with_auth_enabled() {
         // Create state with auth enabled
         let state = create_test_state(true);
 
         // Create the core routes
-        let router = CoreRouter::create_core_routes(state);
+        let router = CoreRouter::create_core_routes(state).unwrap();
 
         // Test the actuator health endpoint without auth token
         // This should fail with 401 Unauthorized because auth is enabled
@@ -158,13 +203,162 @@ mod tests {
         assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
 
+    #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+    struct Widget {
+        id: String,
+        size: u32,
+    }
+
+    impl crate::utils::api_resource::ApiResource for Widget {
+        type Id = String;
+
+        fn resource_type() -> &'static str {
+            "widget"
+        }
+
+        fn api_name() -> &'static str {
+            "WidgetService"
+        }
+    }
+
+    // Helper function to create a test state with a widget cache and loader
+    fn create_cache_test_state(auth_enabled: bool) -> Arc<AppState> {
+        use crate::core::cache::{
+            init_cache_registry, register_resource_cache, register_resource_loader,
+        };
+
+        let registry = init_cache_registry(true, 100, 3600);
+        register_resource_cache::<Widget>(&registry, "widget").unwrap();
+        register_resource_loader::<Widget, _, _>(&registry, "widget", |id| async move {
+            match id.parse::<u32>() {
+                Ok(size) => Ok(Widget { id, size }),
+                Err(_) => Err(AppError::BadRequest(format!("Invalid widget ID: {}", id))),
+            }
+        })
+        .unwrap();
+
+        let mut config = AppConfig::default();
+        config.auth.enabled = auth_enabled;
+
+        Arc::new(AppState {
+            client: Client::new(),
+            config,
+            start_time: SystemTime::now(),
+            cache_registry: Some(registry),
+            cache_provider: None,
+            metrics_handle: PrometheusBuilder::new().build_recorder().handle(),
+            token_client: None,
+            resource_registry: ApiResourceRegistry::new(),
+            db_pool: None,
+            cache_invalidation: None,
+            user_repository: None,
+        })
+    }
+
+    async fn json_body(response: Response) -> serde_json::Value {
+        let body = body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    async fn warm_widgets(router: Router, ids: serde_json::Value) -> Response {
+        let req = Request::builder()
+            .uri("/actuator/cache/widget/warm")
+            .method(Method::POST)
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::json!({ "ids": ids }).to_string()))
+            .unwrap();
+
+        router.oneshot(req).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_cache_admin_routes() {
+        let router = CoreRouter::create_core_routes(create_cache_test_state(false)).unwrap();
+
+        // Registered caches are listed with their settings
+        let response = send_request(router.clone(), "/actuator/cache", Method::GET).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let list = json_body(response).await;
+        assert_eq!(list["enabled"], true);
+        assert_eq!(list["caches"][0]["resource_type"], "widget");
+        assert_eq!(list["caches"][0]["ttl_seconds"], 3600);
+        assert_eq!(list["caches"][0]["warmable"], true);
+
+        // Pre-warm two IDs; the invalid one is reported per ID
+        let response = warm_widgets(router.clone(), serde_json::json!(["1", "2", "x"])).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let warm = json_body(response).await;
+        assert_eq!(warm["loaded"], 2);
+        assert_eq!(warm["failed"], 1);
+        assert_eq!(warm["results"][2]["id"], "x");
+        assert_eq!(warm["results"][2]["loaded"], false);
+
+        // Fetch a single entry
+        let response = send_request(router.clone(), "/actuator/cache/widget/2", Method::GET).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let entry = json_body(response).await;
+        assert_eq!(entry["value"], serde_json::json!({ "id": "2", "size": 2 }));
+
+        // Evict it
+        let response =
+            send_request(router.clone(), "/actuator/cache/widget/2", Method::DELETE).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = send_request(router.clone(), "/actuator/cache/widget/2", Method::GET).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Clear the remaining entry
+        let response = send_request(router.clone(), "/actuator/cache/widget", Method::DELETE).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["evicted"], 1);
+        let response = send_request(router, "/actuator/cache/widget/1", Method::GET).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_cache_admin_unknown_resource_type() {
+        let router = CoreRouter::create_core_routes(create_cache_test_state(false)).unwrap();
+
+        let response = send_request(router.clone(), "/actuator/cache/gadget", Method::GET).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = send_request(router, "/actuator/cache/gadget", Method::DELETE).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_cache_admin_with_cache_disabled() {
+        let router = CoreRouter::create_core_routes(create_test_state(false)).unwrap();
+
+        let response = send_request(router.clone(), "/actuator/cache", Method::GET).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let list = json_body(response).await;
+        assert_eq!(list["enabled"], false);
+        assert_eq!(list["caches"], serde_json::json!([]));
+
+        let response = send_request(router, "/actuator/cache/widget", Method::DELETE).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_cache_admin_requires_auth() {
+        let router = CoreRouter::create_core_routes(create_cache_test_state(true)).unwrap();
+
+        let response = send_request(router.clone(), "/actuator/cache", Method::GET).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = send_request(router, "/actuator/cache/widget", Method::DELETE).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
     #[tokio::test]
     async fn test_route_not_found() {
         // Create state with auth disabled
         let state = create_test_state(false);
 
         // Create the core routes
-        let router = CoreRouter::create_core_routes(state);
+        let router = CoreRouter::create_core_routes(state).unwrap();
 
         // Test a non-existent route
         let response = send_request(router, "/not-found", Method::GET).await;
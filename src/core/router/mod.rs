@@ -1,5 +1,5 @@
 mod app_router;
 mod core_router;
 
-pub use app_router::{AppState, create_core_app_router, init_app_state};
+pub use app_router::{AppState, create_core_app_router, init_app_state, shutdown_app_state};
 pub use core_router::CoreRouter;
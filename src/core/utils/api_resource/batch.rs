@@ -0,0 +1,464 @@
+//! Batch fetching for API resources
+//!
+//! A companion to [`create_api_handler`](super::create_api_handler) that serves
+//! many IDs in one request, e.g. `GET /pet?ids=1,2,3`.
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{debug, info};
+
+use super::core::{
+    ApiHandlerOptions, ApiResource, fetch_with_retry, record_cache_outcome, register_on_first_use,
+};
+use crate::{
+    core::cache::{CacheOutcome, get_cached, get_or_fetch_revalidating},
+    core::router::AppState,
+    error::{AppError, Result},
+};
+
+/// Query parameters of a batch request
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchQuery {
+    /// Comma-separated resource IDs
+    pub ids: String,
+}
+
+/// The result for one ID of a batch request
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchItem<R> {
+    /// The requested ID
+    pub id: String,
+
+    /// HTTP status this ID would have had as a single request
+    pub status: u16,
+
+    /// The resource, if it was found
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<R>,
+
+    /// Why the resource could not be returned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// How the cache answered, if the ID was looked up
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheOutcome>,
+}
+
+impl<R> BatchItem<R> {
+    fn found(id: String, resource: R, outcome: CacheOutcome) -> Self {
+        Self {
+            id,
+            status: StatusCode::OK.as_u16(),
+            data: Some(resource),
+            error: None,
+            cache: Some(outcome),
+        }
+    }
+
+    fn failed(id: String, status: StatusCode, error: String) -> Self {
+        Self {
+            id,
+            status: status.as_u16(),
+            data: None,
+            error: Some(error),
+            cache: None,
+        }
+    }
+}
+
+/// Response of a batch request; results are in request order
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchResponse<R> {
+    pub resource_type: String,
+    pub found: usize,
+    pub failed: usize,
+    pub results: Vec<BatchItem<R>>,
+}
+
+/// The future returned by handlers built with [`create_batch_api_handler`]
+pub type BatchHandlerFuture<R> =
+    futures::future::BoxFuture<'static, Result<Json<BatchResponse<R>>>>;
+
+/// Split a comma-separated ID list, dropping blanks and duplicates
+fn parse_batch_ids(ids: &str, max_batch_size: usize) -> Result<Vec<String>> {
+    let mut parsed: Vec<String> = Vec::new();
+    for id in ids.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+        if !parsed.iter().any(|existing| existing == id) {
+            parsed.push(id.to_string());
+        }
+    }
+
+    if parsed.is_empty() {
+        return Err(AppError::BadRequest("No IDs provided".to_string()));
+    }
+    if parsed.len() > max_batch_size {
+        return Err(AppError::BadRequest(format!(
+            "Too many IDs: {} (maximum {})",
+            parsed.len(),
+            max_batch_size
+        )));
+    }
+
+    Ok(parsed)
+}
+
+/// Fetch one ID through the configured cache, like the single-resource handler
+async fn fetch_one<R, F, Fut>(
+    state: &Arc<AppState>,
+    id_str: &str,
+    id: R::Id,
+    fetch_fn: &F,
+    options: &ApiHandlerOptions,
+) -> Result<(R, CacheOutcome)>
+where
+    R: ApiResource,
+    F: Fn(&Arc<AppState>, R::Id) -> Fut + Clone + Send + Sync + 'static,
+    Fut: std::future::Future<Output = Result<R>> + Send + 'static,
+{
+    let fetch = {
+        let state = state.clone();
+        let fetch_fn = fetch_fn.clone();
+        let options = options.clone();
+        move || async move {
+            if options.use_retries {
+                fetch_with_retry(
+                    &state,
+                    &id,
+                    &fetch_fn,
+                    &options.retry_policy(),
+                    options.detailed_logging,
+                )
+                .await
+            } else {
+                fetch_fn(&state, id).await
+            }
+        }
+    };
+
+    if !options.use_cache {
+        return fetch()
+            .await
+            .map(|resource| (resource, CacheOutcome::Bypass));
+    }
+
+    if let Some(backend) = &state.cache_provider {
+        backend
+            .get_or_fetch::<R, AppError, _, _>(
+                R::resource_type(),
+                id_str,
+                options.cache_ttl_seconds,
+                fetch,
+            )
+            .await
+    } else if let Some(registry) = &state.cache_registry {
+        get_or_fetch_revalidating::<R, AppError, _, _>(registry, R::resource_type(), id_str, fetch)
+            .await
+    } else {
+        fetch()
+            .await
+            .map(|resource| (resource, CacheOutcome::Bypass))
+    }
+}
+
+/// Creates a batch handler for an API resource
+///
+/// The handler accepts `?ids=1,2,3`, resolves every ID that is already in the
+/// resource cache up front, and fetches only the misses, at most
+/// `options.batch_concurrency` at a time. Misses go through the same cache path
+/// as [`create_api_handler`](super::create_api_handler) (coalescing, stale
+/// windows, `fetch_with_retry` when `use_retries` is set).
+///
+/// Failures are reported per ID, so the response is `200 OK` even when some IDs
+/// could not be fetched; only an empty or oversized ID list is rejected with
+/// `400 Bad Request`.
+pub fn create_batch_api_handler<R, F, Fut>(
+    fetch_fn: F,
+    options: ApiHandlerOptions,
+) -> impl Fn(State<Arc<AppState>>, Query<BatchQuery>) -> BatchHandlerFuture<R>
++ Clone
++ Send
++ Sync
++ 'static
+where
+    R: ApiResource,
+    F: Fn(&Arc<AppState>, R::Id) -> Fut + Clone + Send + Sync + 'static,
+    Fut: std::future::Future<Output = Result<R>> + Send + 'static,
+    R::Id: std::str::FromStr + Clone,
+{
+    move |State(state), Query(query)| {
+        let fetch_fn = fetch_fn.clone();
+        let options = options.clone();
+
+        Box::pin(async move {
+            let resource_type = R::resource_type();
+            let ids = parse_batch_ids(&query.ids, options.max_batch_size)?;
+
+            if options.detailed_logging {
+                info!("🔍 Getting {} {}s in batch", ids.len(), resource_type);
+            }
+
+            // Register the resource type on first use with this handler's TTL
+            if options.use_cache {
+                register_on_first_use::<R>(&state, options.cache_ttl_seconds);
+            }
+
+            // Resolve invalid IDs and cache hits without fetching
+            let mut results: Vec<Option<BatchItem<R>>> = Vec::with_capacity(ids.len());
+            let mut misses = Vec::new();
+            for (index, id_str) in ids.into_iter().enumerate() {
+                let Ok(id) = id_str.parse::<R::Id>() else {
+                    let error = format!("Invalid ID format for {}: {}", resource_type, id_str);
+                    results.push(Some(BatchItem::failed(
+                        id_str,
+                        StatusCode::BAD_REQUEST,
+                        error,
+                    )));
+                    continue;
+                };
+
+                let cached = match (&state.cache_registry, options.use_cache) {
+                    (Some(registry), true) => {
+                        get_cached::<R>(registry, resource_type, &id_str).await
+                    }
+                    _ => None,
+                };
+
+                match cached {
+                    Some(resource) => {
+                        record_cache_outcome(resource_type, CacheOutcome::Hit);
+                        results.push(Some(BatchItem::found(id_str, resource, CacheOutcome::Hit)));
+                    }
+                    None => {
+                        results.push(None);
+                        misses.push((index, id_str, id));
+                    }
+                }
+            }
+
+            debug!(
+                "Batch of {}s: {} cached, {} to fetch",
+                resource_type,
+                results.len() - misses.len(),
+                misses.len()
+            );
+
+            // Fetch the misses with bounded concurrency
+            let fetched: Vec<(usize, BatchItem<R>)> = stream::iter(misses)
+                .map(|(index, id_str, id)| {
+                    let state = state.clone();
+                    let fetch_fn = fetch_fn.clone();
+                    let options = options.clone();
+                    async move {
+                        let item = match fetch_one(&state, &id_str, id, &fetch_fn, &options).await {
+                            Ok((resource, outcome)) => {
+                                record_cache_outcome(resource_type, outcome);
+                                BatchItem::found(id_str, resource, outcome)
+                            }
+                            Err(e) => BatchItem::failed(id_str, e.status_code(), e.to_string()),
+                        };
+                        (index, item)
+                    }
+                })
+                .buffer_unordered(options.batch_concurrency.max(1))
+                .collect()
+                .await;
+
+            for (index, item) in fetched {
+                results[index] = Some(item);
+            }
+
+            let results: Vec<BatchItem<R>> = results.into_iter().flatten().collect();
+            let found = results.iter().filter(|item| item.data.is_some()).count();
+
+            if options.detailed_logging {
+                info!(
+                    "✅ Batch of {}s: {} found, {} failed",
+                    resource_type,
+                    found,
+                    results.len() - found
+                );
+            }
+
+            Ok(Json(BatchResponse {
+                resource_type: resource_type.to_string(),
+                found,
+                failed: results.len() - found,
+                results,
+            }))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        cache::{init_cache_registry, register_resource_cache},
+        config::app_config::AppConfig,
+    };
+    use crate::error::UpstreamError;
+    use crate::utils::api_resource::ApiResourceRegistry;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use reqwest::Client;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, SystemTime};
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct Widget {
+        id: u32,
+    }
+
+    impl ApiResource for Widget {
+        type Id = u32;
+
+        fn resource_type() -> &'static str {
+            "widget"
+        }
+
+        fn api_name() -> &'static str {
+            "WidgetService"
+        }
+    }
+
+    fn create_test_state() -> Arc<AppState> {
+        let registry = init_cache_registry(true, 100, 3600);
+        register_resource_cache::<Widget>(&registry, "widget").unwrap();
+
+        Arc::new(AppState {
+            client: Client::new(),
+            config: AppConfig::default(),
+            start_time: SystemTime::now(),
+            cache_registry: Some(registry),
+            cache_provider: None,
+            metrics_handle: PrometheusBuilder::new().build_recorder().handle(),
+            token_client: None,
+            resource_registry: ApiResourceRegistry::new(),
+            db_pool: None,
+            cache_invalidation: None,
+            user_repository: None,
+        })
+    }
+
+    // Widgets 0-99 exist; 404 is reported as not found, 400 and 429 as the upstream's
+    // status and anything else as an upstream failure
+    async fn get_widgets(
+        state: Arc<AppState>,
+        ids: &str,
+        calls: Arc<AtomicUsize>,
+    ) -> Result<Json<BatchResponse<Widget>>> {
+        let handler = create_batch_api_handler(
+            move |_state: &Arc<AppState>, id: u32| {
+                let calls = calls.clone();
+                async move {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    match id {
+                        0..100 => Ok(Widget { id }),
+                        404 => Err(AppError::NotFound(format!("widget {} not found", id))),
+                        400 | 429 => Err(UpstreamError::new(
+                            "WidgetService",
+                            StatusCode::from_u16(id as u16).ok(),
+                            format!("HTTP {}", id),
+                        )
+                        .into()),
+                        _ => Err(AppError::ExternalServiceError("upstream down".to_string())),
+                    }
+                }
+            },
+            ApiHandlerOptions {
+                use_retries: false,
+                detailed_logging: false,
+                max_batch_size: 5,
+                batch_concurrency: 2,
+                ..Default::default()
+            },
+        );
+
+        handler(
+            State(state),
+            Query(BatchQuery {
+                ids: ids.to_string(),
+            }),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_batch_reports_per_id_results() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let Json(response) = get_widgets(create_test_state(), "1, 404,x,500,1", calls.clone())
+            .await
+            .unwrap();
+
+        // Duplicates are dropped and results keep the request order
+        let statuses: Vec<(&str, u16)> = response
+            .results
+            .iter()
+            .map(|item| (item.id.as_str(), item.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![("1", 200), ("404", 404), ("x", 400), ("500", 502)]
+        );
+        assert_eq!(response.results[0].data, Some(Widget { id: 1 }));
+        assert_eq!(response.results[0].cache, Some(CacheOutcome::Miss));
+        assert_eq!(response.found, 1);
+        assert_eq!(response.failed, 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_batch_reports_upstream_client_errors_with_their_status() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let Json(response) = get_widgets(create_test_state(), "400,429", calls)
+            .await
+            .unwrap();
+
+        let statuses: Vec<(&str, u16)> = response
+            .results
+            .iter()
+            .map(|item| (item.id.as_str(), item.status))
+            .collect();
+        assert_eq!(statuses, vec![("400", 400), ("429", 429)]);
+    }
+
+    #[tokio::test]
+    async fn test_batch_fetches_only_misses() {
+        let state = create_test_state();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let _ = get_widgets(state.clone(), "1,2", calls.clone()).await;
+        let Json(response) = get_widgets(state, "1,2,3", calls.clone()).await.unwrap();
+
+        let outcomes: Vec<Option<CacheOutcome>> =
+            response.results.iter().map(|item| item.cache).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                Some(CacheOutcome::Hit),
+                Some(CacheOutcome::Hit),
+                Some(CacheOutcome::Miss)
+            ]
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_batch_rejects_empty_and_oversized_requests() {
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let result = get_widgets(create_test_state(), " , ", calls.clone()).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+
+        let result = get_widgets(create_test_state(), "1,2,3,4,5,6", calls.clone()).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+}
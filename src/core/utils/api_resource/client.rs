@@ -0,0 +1,503 @@
+//! Generic upstream client for API resources
+//!
+//! A resource that declares an [`ApiResource::endpoint`] and
+//! [`ApiResource::base_url_key`] can be read and written through
+//! [`ResourceClient`] without a hand-written fetch function.
+
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Method, StatusCode};
+use std::marker::PhantomData;
+use tracing::debug;
+
+use super::{ApiResource, RetryPolicy};
+use crate::{
+    core::{auth::EntraTokenClient, router::AppState},
+    error::{AppError, Result, UpstreamError},
+    utils::api_logger,
+};
+
+/// Placeholder for the resource ID in endpoint templates
+const ID_PLACEHOLDER: &str = "{id}";
+
+/// Typed client for the upstream API a resource comes from
+///
+/// Requests carry a bearer token from [`EntraTokenClient`] when the upstream
+/// has a `scope`. Idempotent requests (everything but `create`) are retried
+/// according to a [`RetryPolicy`].
+///
+/// Upstream errors are returned as [`AppError::Upstream`] with the upstream's
+/// status and headers, and reported as not found (404), bad request (400 and
+/// 422), rate limited (429) or an external service error (anything else,
+/// including 401 and 403, which concern this service's own credentials).
+#[derive(Clone)]
+pub struct ResourceClient<T: ApiResource> {
+    http: Client,
+    base_url: String,
+    endpoint: &'static str,
+    auth: Option<(EntraTokenClient, String)>,
+    retry: RetryPolicy,
+    _resource: PhantomData<fn() -> T>,
+}
+
+impl<T: ApiResource> ResourceClient<T> {
+    /// Create a client for `T`'s endpoint under `base_url`
+    ///
+    /// Fails if `T` does not declare an endpoint.
+    pub fn new(http: Client, base_url: impl Into<String>) -> Result<Self> {
+        let endpoint = T::endpoint().ok_or_else(|| {
+            AppError::InternalError(format!("{} has no upstream endpoint", T::resource_type()))
+        })?;
+
+        Ok(Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            endpoint,
+            auth: None,
+            retry: RetryPolicy::default(),
+            _resource: PhantomData,
+        })
+    }
+
+    /// Create a client from the upstream named by `T::base_url_key`
+    ///
+    /// Uses the shared HTTP client, `server.max_retries`, and the token client
+    /// when the upstream has a `scope`.
+    pub fn from_state(state: &AppState) -> Result<Self> {
+        let key = T::base_url_key().ok_or_else(|| {
+            AppError::InternalError(format!(
+                "{} has no upstream base URL key",
+                T::resource_type()
+            ))
+        })?;
+        let upstream = state.config.upstream(key).ok_or_else(|| {
+            AppError::InternalError(format!("No upstream configured for api.upstreams.{}", key))
+        })?;
+
+        let mut client = Self::new(state.client.clone(), upstream.url)?
+            .with_max_retries(state.config.server.max_retries);
+        if let Some(scope) = upstream.scope {
+            let token_client = state.token_client.clone().ok_or_else(|| {
+                AppError::InternalError(format!(
+                    "Upstream {} requires a token but auth is disabled",
+                    key
+                ))
+            })?;
+            client = client.with_token(token_client, scope);
+        }
+        Ok(client)
+    }
+
+    /// Send a bearer token for `scope` with every request
+    pub fn with_token(mut self, token_client: EntraTokenClient, scope: impl Into<String>) -> Self {
+        self.auth = Some((token_client, scope.into()));
+        self
+    }
+
+    /// Retry idempotent requests up to `max_retries` times
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.retry.max_retries = max_retries;
+        self
+    }
+
+    /// Retry idempotent requests according to `policy`
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// URL of the resource with `id`
+    pub fn item_url(&self, id: &T::Id) -> String {
+        let path = self.endpoint.replace(ID_PLACEHOLDER, &id.to_string());
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// URL of the resource collection
+    pub fn collection_url(&self) -> String {
+        let path = self
+            .endpoint
+            .replace(&format!("/{}", ID_PLACEHOLDER), "")
+            .replace(ID_PLACEHOLDER, "");
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Fetch the resource with `id`
+    pub async fn get(&self, id: &T::Id) -> Result<T> {
+        let url = self.item_url(id);
+        let body = self.send(Method::GET, &url, None, Some(id)).await?;
+        self.parse(&url, &body)
+    }
+
+    /// Fetch every resource in the collection
+    pub async fn list(&self) -> Result<Vec<T>> {
+        self.list_with_query(None).await
+    }
+
+    /// Fetch the collection, passing a raw query string (e.g. `"status=sold"`) through
+    pub async fn list_with_query(&self, query: Option<&str>) -> Result<Vec<T>> {
+        let url = match query.filter(|query| !query.is_empty()) {
+            Some(query) => format!("{}?{}", self.collection_url(), query),
+            None => self.collection_url(),
+        };
+        let body = self.send(Method::GET, &url, None, None).await?;
+        self.parse(&url, &body)
+    }
+
+    /// Create a resource, returning the upstream's copy
+    ///
+    /// Not retried, since the upstream may have created it before failing.
+    pub async fn create(&self, resource: &T) -> Result<T> {
+        let url = self.collection_url();
+        let body = self.send(Method::POST, &url, Some(resource), None).await?;
+        self.parse_or(&url, &body, resource)
+    }
+
+    /// Replace the resource with `id`, returning the upstream's copy
+    pub async fn update(&self, id: &T::Id, resource: &T) -> Result<T> {
+        let url = self.item_url(id);
+        let body = self
+            .send(Method::PUT, &url, Some(resource), Some(id))
+            .await?;
+        self.parse_or(&url, &body, resource)
+    }
+
+    /// Delete the resource with `id`
+    pub async fn delete(&self, id: &T::Id) -> Result<()> {
+        let url = self.item_url(id);
+        self.send(Method::DELETE, &url, None, Some(id)).await?;
+        Ok(())
+    }
+
+    /// Send a request, retrying idempotent ones, and return the response body
+    async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        resource: Option<&T>,
+        id: Option<&T::Id>,
+    ) -> Result<String> {
+        let policy = if method == Method::POST {
+            RetryPolicy::none()
+        } else {
+            self.retry
+        };
+
+        policy
+            .run(T::api_name(), |_| {
+                self.send_once(method.clone(), url, resource, id)
+            })
+            .await
+    }
+
+    /// Send a request once
+    async fn send_once(
+        &self,
+        method: Method,
+        url: &str,
+        resource: Option<&T>,
+        id: Option<&T::Id>,
+    ) -> Result<String> {
+        api_logger::log_request_start(T::api_name(), url);
+
+        let mut request = self.http.request(method, url);
+        if let Some((token_client, scope)) = &self.auth {
+            let token = token_client.get_token(scope).await.map_err(|e| {
+                AppError::ExternalServiceError(format!(
+                    "Failed to get token for {} API: {}",
+                    T::api_name(),
+                    e
+                ))
+            })?;
+            request = request.bearer_auth(token);
+        }
+        if let Some(resource) = resource {
+            request = request.json(resource);
+        }
+
+        let response = request.send().await.map_err(|e| {
+            api_logger::log_request_error(T::api_name(), url, &e.to_string());
+            AppError::from(UpstreamError::new(
+                T::api_name(),
+                None,
+                format!("Failed to fetch data from {}: {}", url, e),
+            ))
+        })?;
+
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.text().await.map_err(|e| {
+            AppError::from(UpstreamError::new(
+                T::api_name(),
+                None,
+                format!("Failed to read {} API response: {}", T::api_name(), e),
+            ))
+        })?;
+
+        if status.is_success() {
+            debug!("📊 {} API returned {} for {}", T::api_name(), status, url);
+            return Ok(body);
+        }
+
+        api_logger::log_response_error(T::api_name(), status);
+        Err(upstream_error::<T>(status, headers, id, &body))
+    }
+
+    fn parse<R: serde::de::DeserializeOwned>(&self, url: &str, body: &str) -> Result<R> {
+        serde_json::from_str(body).map_err(|e| {
+            AppError::ExternalServiceError(format!(
+                "Failed to parse {} API response from {}: {}",
+                T::api_name(),
+                url,
+                e
+            ))
+        })
+    }
+
+    /// Parse a returned resource, or use the one sent if the body is empty
+    fn parse_or(&self, url: &str, body: &str, sent: &T) -> Result<T> {
+        if body.trim().is_empty() {
+            Ok(sent.clone())
+        } else {
+            self.parse(url, body)
+        }
+    }
+}
+
+/// Describe an unsuccessful upstream response as an [`AppError::Upstream`]
+fn upstream_error<T: ApiResource>(
+    status: StatusCode,
+    headers: HeaderMap,
+    id: Option<&T::Id>,
+    body: &str,
+) -> AppError {
+    let code = status.as_u16();
+    let message = match status {
+        StatusCode::NOT_FOUND => match id {
+            Some(id) => format!(
+                "{} with ID {} not found (HTTP {})",
+                T::resource_type(),
+                id.to_string(),
+                code
+            ),
+            None => format!(
+                "{} collection not found (HTTP {})",
+                T::resource_type(),
+                code
+            ),
+        },
+        StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => format!(
+            "{} API rejected the request (HTTP {}): {}",
+            T::api_name(),
+            code,
+            body
+        ),
+        StatusCode::TOO_MANY_REQUESTS => format!(
+            "{} API is rate limiting requests (HTTP {})",
+            T::api_name(),
+            code
+        ),
+        _ => format!("{} API returned error status: HTTP {}", T::api_name(), code),
+    };
+    UpstreamError::new(T::api_name(), Some(status), message)
+        .with_headers(headers)
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct Widget {
+        id: i64,
+        name: String,
+    }
+
+    impl ApiResource for Widget {
+        type Id = i64;
+
+        fn resource_type() -> &'static str {
+            "widget"
+        }
+
+        fn api_name() -> &'static str {
+            "Widgets"
+        }
+
+        fn endpoint() -> Option<&'static str> {
+            Some("/widgets/{id}")
+        }
+
+        fn base_url_key() -> Option<&'static str> {
+            Some("widgets")
+        }
+    }
+
+    fn widget(id: i64) -> Widget {
+        Widget {
+            id,
+            name: format!("widget {}", id),
+        }
+    }
+
+    fn client(server: &MockServer) -> ResourceClient<Widget> {
+        ResourceClient::<Widget>::new(Client::new(), format!("{}/", server.uri()))
+            .unwrap()
+            .with_max_retries(2)
+    }
+
+    #[test]
+    fn test_urls_from_endpoint_template() {
+        let client =
+            ResourceClient::<Widget>::new(Client::new(), "https://api.example.com/v1/").unwrap();
+        assert_eq!(client.item_url(&7), "https://api.example.com/v1/widgets/7");
+        assert_eq!(
+            client.collection_url(),
+            "https://api.example.com/v1/widgets"
+        );
+
+        // Resources without an endpoint can't have a client
+        assert!(
+            ResourceClient::<crate::repository::User>::new(Client::new(), "http://localhost")
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_crud_requests() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/widgets/1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(widget(1)))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/widgets"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(vec![widget(1), widget(2)]))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/widgets"))
+            .and(body_json(widget(3)))
+            .respond_with(ResponseTemplate::new(201).set_body_json(widget(3)))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/widgets/3"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/widgets/3"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = client(&server);
+        assert_eq!(client.get(&1).await.unwrap(), widget(1));
+        assert_eq!(client.list().await.unwrap(), vec![widget(1), widget(2)]);
+        assert_eq!(client.create(&widget(3)).await.unwrap(), widget(3));
+        // An empty update response returns what was sent
+        let renamed = Widget {
+            name: "renamed".to_string(),
+            ..widget(3)
+        };
+        assert_eq!(client.update(&3, &renamed).await.unwrap(), renamed);
+        client.delete(&3).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_retries_server_errors_but_not_not_found() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/widgets/1"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/widgets/1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(widget(1)))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/widgets/2"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = client(&server);
+        assert_eq!(client.get(&1).await.unwrap(), widget(1));
+
+        let error = client.get(&2).await.unwrap_err();
+        assert_eq!(error.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(error.upstream().unwrap().attempts, 1);
+        assert!(error.to_string().contains("not found (HTTP 404)"));
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_is_honoured() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/widgets/1"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/widgets/1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(widget(1)))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/widgets/2"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "60"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = client(&server);
+        let started = std::time::Instant::now();
+        assert_eq!(client.get(&1).await.unwrap(), widget(1));
+        assert!(started.elapsed() >= std::time::Duration::from_secs(1));
+
+        // Waiting as asked would pass the deadline, so the 429 is returned with its headers
+        let error = client.get(&2).await.unwrap_err();
+        assert_eq!(error.status_code(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            error.upstream().and_then(UpstreamError::retry_after),
+            Some(std::time::Duration::from_secs(60))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_is_not_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/widgets"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/widgets/1"))
+            .respond_with(ResponseTemplate::new(422).set_body_string("name is required"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = client(&server);
+        let error = client.create(&widget(1)).await.unwrap_err();
+        assert_eq!(error.error_type(), "external_service_error");
+        assert_eq!(error.upstream().unwrap().attempts, 1);
+
+        // A 4xx other than 429 is never retried
+        let error = client.update(&1, &widget(1)).await.unwrap_err();
+        assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+        assert!(error.to_string().contains("name is required"));
+    }
+}
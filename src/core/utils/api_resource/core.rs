@@ -1,6 +1,8 @@
 use axum::{
     Json,
     extract::{Path, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
 };
 use metrics::{counter, gauge};
 use serde::{Serialize, de::DeserializeOwned};
@@ -9,10 +11,11 @@ use std::{any::Any, fmt::Debug, sync::Arc};
 use tracing::{debug, info, warn};
 
 use crate::{
-    core::router::AppState,
+    config::ResourceCacheConfig,
+    core::{cache::CacheOutcome, router::AppState},
     error::{AppError, Result},
     generated_apis::petstore_api::models::Upet,
-    utils::api_logger,
+    utils::{api_logger, api_resource::RetryPolicy, conditional},
 };
 
 /// Trait for API resources that can be cached and retrieved
@@ -34,6 +37,30 @@ pub trait ApiResource:
     ///
     /// This helps identify which external API a resource comes from
     fn api_name() -> &'static str;
+
+    /// Tags the cached entry for this value carries (e.g. `"owner:42"`)
+    ///
+    /// Tagged entries can be invalidated as a group with `invalidate_tag`.
+    /// Resources have no tags by default.
+    fn cache_tags(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Upstream path of a single resource, with `{id}` standing for the ID
+    /// (e.g. `"/pet/{id}"`)
+    ///
+    /// The collection path is the template with its `/{id}` segment removed.
+    /// Resources with an endpoint and a [`ApiResource::base_url_key`] can be
+    /// fetched with `ResourceClient`.
+    fn endpoint() -> Option<&'static str> {
+        None
+    }
+
+    /// Key of the upstream the endpoint is relative to, looked up in
+    /// `api.upstreams` (e.g. `"petstore"`)
+    fn base_url_key() -> Option<&'static str> {
+        None
+    }
 }
 
 /// Options for configuring the API handler's behavior
@@ -62,12 +89,30 @@ pub struct ApiHandlerOptions {
     ///
     /// Default is 300 seconds (5 minutes)
     /// Set to 0 to disable TTL (cache until explicitly invalidated)
+    ///
+    /// Applied when the handler registers the resource type on first use; a
+    /// `cache.resources.<type>.ttl_seconds` entry in the config takes precedence
     pub cache_ttl_seconds: u64,
 
     /// Whether to log detailed information about the request/response
     ///
     /// Set to false to reduce log verbosity for high-volume endpoints
     pub detailed_logging: bool,
+
+    /// Maximum number of IDs accepted by a batch handler
+    ///
+    /// Default is 100
+    pub max_batch_size: usize,
+
+    /// Number of cache misses a batch handler fetches concurrently
+    ///
+    /// Default is 8
+    pub batch_concurrency: usize,
+
+    /// Time after which no further retry is started, in milliseconds
+    ///
+    /// Default is 10000 (10 seconds)
+    pub retry_deadline_ms: u64,
 }
 
 impl Default for ApiHandlerOptions {
@@ -78,10 +123,83 @@ impl Default for ApiHandlerOptions {
             max_retry_attempts: 3,
             cache_ttl_seconds: 300, // 5 minutes
             detailed_logging: true,
+            max_batch_size: 100,
+            batch_concurrency: 8,
+            retry_deadline_ms: 10_000,
         }
     }
 }
 
+impl ApiHandlerOptions {
+    /// The retry policy for upstream fetches, which never retries when `use_retries` is off
+    pub fn retry_policy(&self) -> RetryPolicy {
+        if !self.use_retries {
+            return RetryPolicy::none();
+        }
+        RetryPolicy::new(self.max_retry_attempts)
+            .with_deadline(std::time::Duration::from_millis(self.retry_deadline_ms))
+    }
+}
+
+/// Name this service reports in `Cache-Status` response headers
+const CACHE_STATUS_NAME: &str = env!("CARGO_PKG_NAME");
+
+/// A resource returned by an API handler, together with how the cache answered
+///
+/// Responds with the resource as JSON plus a strong `ETag` and `X-Cache` and
+/// RFC 9211 `Cache-Status` headers describing the [`CacheOutcome`]. When the
+/// request's `If-None-Match` matches, the response is `304 Not Modified`.
+#[derive(Debug, Clone)]
+pub struct CachedJson<R> {
+    pub resource: R,
+    pub outcome: CacheOutcome,
+    /// Strong ETag of the resource's JSON representation
+    pub etag: Option<String>,
+    /// Whether the request's validators matched, so no body is sent
+    pub not_modified: bool,
+}
+
+impl<R: Serialize> CachedJson<R> {
+    /// Wrap `resource`, evaluating the conditional headers of the request
+    pub fn new(resource: R, outcome: CacheOutcome, request_headers: &HeaderMap) -> Self {
+        let etag = conditional::strong_etag(&resource);
+        let not_modified = conditional::is_not_modified(request_headers, etag.as_deref(), None);
+
+        Self {
+            resource,
+            outcome,
+            etag,
+            not_modified,
+        }
+    }
+}
+
+impl<R: Serialize> IntoResponse for CachedJson<R> {
+    fn into_response(self) -> Response {
+        let mut response = if self.not_modified {
+            StatusCode::NOT_MODIFIED.into_response()
+        } else {
+            Json(self.resource).into_response()
+        };
+        let headers = response.headers_mut();
+
+        conditional::set_validators(headers, self.etag.as_deref(), None);
+
+        headers.insert(
+            HeaderName::from_static("x-cache"),
+            HeaderValue::from_static(self.outcome.x_cache()),
+        );
+        if let Ok(value) = HeaderValue::from_str(&self.outcome.cache_status(CACHE_STATUS_NAME)) {
+            headers.insert(HeaderName::from_static("cache-status"), value);
+        }
+
+        response
+    }
+}
+
+/// The future returned by handlers built with [`create_api_handler`]
+pub type ApiHandlerFuture<R> = futures::future::BoxFuture<'static, Result<CachedJson<R>>>;
+
 /// Creates a handler function for an API resource.
 ///
 /// This function creates an async function that can be used as an Axum handler,
@@ -91,6 +209,10 @@ impl Default for ApiHandlerOptions {
 /// - Error handling
 /// - Logging and metrics
 ///
+/// Responses carry `ETag` and `X-Cache`/`Cache-Status` headers (see
+/// [`CachedJson`]), are answered with 304 when `If-None-Match` matches, and
+/// are counted in `cache_requests_total` labelled with the cache outcome.
+///
 /// # Type Parameters
 ///
 /// - `R`: The resource type that implements ApiResource
@@ -104,7 +226,7 @@ impl Default for ApiHandlerOptions {
 pub fn create_api_handler<R, F, Fut>(
     fetch_fn: F,
     options: ApiHandlerOptions,
-) -> impl Fn(State<Arc<AppState>>, Path<String>) -> futures::future::BoxFuture<'static, Result<Json<R>>>
+) -> impl Fn(State<Arc<AppState>>, Path<String>, HeaderMap) -> ApiHandlerFuture<R>
 + Clone
 + Send
 + Sync
@@ -115,7 +237,7 @@ where
     Fut: std::future::Future<Output = Result<R>> + Send + 'static,
     R::Id: std::str::FromStr + Clone,
 {
-    move |State(state), Path(id_str)| {
+    move |State(state), Path(id_str), headers: HeaderMap| {
         let fetch_fn = fetch_fn.clone();
         let options = options.clone();
         let state = state.clone();
@@ -138,49 +260,72 @@ where
 
             // Check cache first if enabled
             if options.use_cache {
-                if let Some(registry) = &state.cache_registry {
-                    // Use the resource type from the ApiResource trait
-                    let resource_type = R::resource_type();
-
-                    // Convert ID to string for cache key
-                    let cache_key = id.to_string();
-
-                    // Try to fetch from cache using the generic get_or_fetch function
-                    let fetch_closure = || async {
-                        // Call the original fetch function and convert AppError to String
-                        fetch_fn(&state, id.clone())
+                // Use the resource type from the ApiResource trait
+                let resource_type = R::resource_type();
+
+                // Convert ID to string for cache key
+                let cache_key = id.to_string();
+
+                // Register the resource type on first use with this handler's TTL
+                register_on_first_use::<R>(&state, options.cache_ttl_seconds);
+
+                // Try to fetch from cache using the generic get_or_fetch function; the
+                // closure owns its captures so a stale entry can be refreshed in the background
+                let fetch_closure = {
+                    let fetch_fn = fetch_fn.clone();
+                    let state = state.clone();
+                    let id = id.clone();
+                    let options = options.clone();
+                    move || async move {
+                        if options.use_retries {
+                            fetch_with_retry(
+                                &state,
+                                &id,
+                                &fetch_fn,
+                                &options.retry_policy(),
+                                options.detailed_logging,
+                            )
                             .await
-                            .map_err(|e| e.to_string())
-                    };
-
-                    match crate::core::cache::get_or_fetch::<R, _, _>(
-                        registry,
-                        resource_type,
-                        &cache_key,
-                        fetch_closure,
-                    )
-                    .await
-                    {
-                        Ok(resource) => {
-                            // Remove the generic logging here as it's redundant with pet_handler
-                            // The fetch_pet_handler will log with more specific info
-                            return Ok(Json(resource));
-                        }
-                        Err(e) => {
-                            // Convert the string error back to an AppError
-                            return Err(AppError::ExternalServiceError(format!(
-                                "Failed to fetch {} {} from {}: {}",
-                                resource_type,
-                                id_str,
-                                if crate::core::cache::last_fetch_from_cache() {
-                                    "cache"
-                                } else {
-                                    "API"
-                                },
-                                e
-                            )));
+                        } else {
+                            fetch_fn(&state, id).await
                         }
                     }
+                };
+
+                // Prefer the configured cache provider, falling back to the registry
+                let cached = if let Some(backend) = &state.cache_provider {
+                    Some(
+                        backend
+                            .get_or_fetch::<R, AppError, _, _>(
+                                resource_type,
+                                &cache_key,
+                                options.cache_ttl_seconds,
+                                fetch_closure,
+                            )
+                            .await,
+                    )
+                } else if let Some(registry) = &state.cache_registry {
+                    Some(
+                        crate::core::cache::get_or_fetch_revalidating::<R, AppError, _, _>(
+                            registry,
+                            resource_type,
+                            &cache_key,
+                            fetch_closure,
+                        )
+                        .await,
+                    )
+                } else {
+                    None
+                };
+
+                // Errors only come from the upstream fetch (or a cached "not found"),
+                // never from the cache itself, and are returned as the fetch reported them
+                if let Some(result) = cached {
+                    // Remove the generic logging here as it's redundant with pet_handler
+                    // The fetch_pet_handler will log with more specific info
+                    let (resource, outcome) = result?;
+                    record_cache_outcome(resource_type, outcome);
+                    return Ok(CachedJson::new(resource, outcome, &headers));
                 }
             }
 
@@ -190,7 +335,7 @@ where
                     &state,
                     &id,
                     &fetch_fn,
-                    options.max_retry_attempts,
+                    &options.retry_policy(),
                     options.detailed_logging,
                 )
                 .await?
@@ -220,10 +365,7 @@ where
                             // Create a closure to store the resource
                             let store_fn = || async {
                                 // Store directly in the cache
-                                cache
-                                    .cache
-                                    .insert(cache_key.clone(), resource.clone())
-                                    .await;
+                                cache.insert(cache_key.clone(), resource.clone()).await;
 
                                 // Increment counters
                                 counter!("cache_entries_created", "resource_type" => resource_type.to_string()).increment(1);
@@ -278,7 +420,6 @@ where
 
                                             // Store directly in the cache
                                             new_cache
-                                                .cache
                                                 .insert(cache_key.clone(), resource.clone())
                                                 .await;
 
@@ -347,11 +488,40 @@ where
                 }
             }
 
-            Ok(Json(resource))
+            record_cache_outcome(R::resource_type(), CacheOutcome::Bypass);
+            Ok(CachedJson::new(resource, CacheOutcome::Bypass, &headers))
         })
     }
 }
 
+/// Register `R` in the cache registry with `ttl_seconds` unless it already is
+///
+/// A `cache.resources.<type>` entry in the config still takes precedence.
+pub fn register_on_first_use<R: ApiResource>(state: &Arc<AppState>, ttl_seconds: u64) {
+    if let Some(registry) = &state.cache_registry
+        && !registry.is_registered(R::resource_type())
+    {
+        let _ = crate::utils::api_resource::register_resource_with_config::<R>(
+            state,
+            None,
+            ResourceCacheConfig {
+                ttl_seconds: Some(ttl_seconds),
+                ..Default::default()
+            },
+        );
+    }
+}
+
+/// Count a handler response by how the cache answered it
+pub fn record_cache_outcome(resource_type: &str, outcome: CacheOutcome) {
+    counter!(
+        "cache_requests_total",
+        "resource_type" => resource_type.to_string(),
+        "outcome" => outcome.as_str()
+    )
+    .increment(1);
+}
+
 // Static counters for cache hits and misses
 static CACHE_HITS: AtomicU64 = AtomicU64::new(0);
 static CACHE_MISSES: AtomicU64 = AtomicU64::new(0);
@@ -371,8 +541,11 @@ fn cache_hit_ratio() -> f64 {
 
 /// Fetch a resource with retries on failure
 ///
-/// This function will retry the fetch operation with exponential backoff
-/// if it fails. It will not retry if the error is a not found error.
+/// Failures are retried as `policy` allows: only connection errors, 429 and
+/// 5xx responses, waiting as long as the upstream's `Retry-After` asks or
+/// with full-jitter backoff, and never past the policy's deadline. A 404 is
+/// returned as `NotFound` straight away. The returned error records how many
+/// attempts were made.
 ///
 /// # Type Parameters
 ///
@@ -386,7 +559,7 @@ fn cache_hit_ratio() -> f64 {
 /// - `state`: The application state
 /// - `id`: The resource ID
 /// - `fetch_fn`: A function that fetches the resource
-/// - `max_retries`: Maximum number of retry attempts
+/// - `policy`: When and how long to wait before retrying
 /// - `detailed_logging`: Whether to log detailed information
 ///
 /// # Returns
@@ -396,7 +569,7 @@ pub async fn fetch_with_retry<R: ApiResource, F, Fut, S>(
     state: &Arc<S>,
     id: &R::Id,
     fetch_fn: &F,
-    max_retries: u32,
+    policy: &RetryPolicy,
     detailed_logging: bool,
 ) -> Result<R>
 where
@@ -404,57 +577,33 @@ where
     Fut: std::future::Future<Output = Result<R>>,
     S: 'static,
 {
-    let mut last_error = None;
-
-    for attempt in 0..=max_retries {
-        if attempt > 0 && detailed_logging {
-            info!(
-                "🔄 Retry attempt {} for {} ID: {}",
-                attempt,
-                R::resource_type(),
-                id.to_string()
-            );
-        }
-
-        match fetch_fn(state, id.clone()).await {
-            Ok(resource) => return Ok(resource),
-            Err(err) => {
-                // Don't log attempt number or retry on 404 Not Found errors
-                if err.to_string().contains("not found (HTTP 404)") {
-                    if detailed_logging {
-                        warn!("❓ {} not found: {}", R::resource_type(), err);
-                    }
-                    return Err(AppError::NotFound(format!(
-                        "{} with ID {} not found",
-                        R::resource_type(),
-                        id.to_string()
-                    )));
-                }
-
-                if detailed_logging {
-                    warn!("❌ Attempt {} failed: {}", attempt + 1, err);
-                } else {
-                    debug!("Attempt {} failed: {}", attempt + 1, err);
-                }
-
-                last_error = Some(err);
-
-                // Don't sleep on the last attempt
-                if attempt < max_retries {
-                    tokio::time::sleep(std::time::Duration::from_millis(100 * 2u64.pow(attempt)))
-                        .await;
-                }
+    let result = policy
+        .run(R::api_name(), |attempt| {
+            if attempt > 1 && detailed_logging {
+                info!(
+                    "🔄 Retry attempt {} for {} ID: {}",
+                    attempt - 1,
+                    R::resource_type(),
+                    id.to_string()
+                );
             }
-        }
-    }
+            fetch_fn(state, id.clone())
+        })
+        .await;
 
-    Err(last_error.unwrap_or_else(|| {
-        AppError::InternalError(format!(
-            "Unknown error fetching {} with ID {}",
+    result.map_err(|err| {
+        if err.status_code() != StatusCode::NOT_FOUND {
+            return err;
+        }
+        if detailed_logging {
+            warn!("❓ {} not found: {}", R::resource_type(), err);
+        }
+        AppError::NotFound(format!(
+            "{} with ID {} not found",
             R::resource_type(),
             id.to_string()
         ))
-    }))
+    })
 }
 
 // Specialized implementations for our specific cache
@@ -501,3 +650,177 @@ fn convert_to_cache_value<R: ApiResource>(resource: &R) -> Option<Upet> {
 fn to_cache_key<R: ApiResource>(id: &R::Id) -> Option<i64> {
     id.to_string().parse::<i64>().ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        cache::{init_cache_registry, register_resource_cache},
+        config::app_config::AppConfig,
+    };
+    use crate::error::UpstreamError;
+    use crate::utils::api_resource::ApiResourceRegistry;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use reqwest::Client;
+    use serde::Deserialize;
+    use std::time::SystemTime;
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct Widget {
+        id: String,
+    }
+
+    impl ApiResource for Widget {
+        type Id = String;
+
+        fn resource_type() -> &'static str {
+            "widget"
+        }
+
+        fn api_name() -> &'static str {
+            "WidgetService"
+        }
+    }
+
+    fn create_test_state(cache_enabled: bool) -> Arc<AppState> {
+        let registry = cache_enabled.then(|| {
+            let registry = init_cache_registry(true, 100, 3600);
+            register_resource_cache::<Widget>(&registry, "widget").unwrap();
+            registry
+        });
+
+        Arc::new(AppState {
+            client: Client::new(),
+            config: AppConfig::default(),
+            start_time: SystemTime::now(),
+            cache_registry: registry,
+            cache_provider: None,
+            metrics_handle: PrometheusBuilder::new().build_recorder().handle(),
+            token_client: None,
+            resource_registry: ApiResourceRegistry::new(),
+            db_pool: None,
+            cache_invalidation: None,
+            user_repository: None,
+        })
+    }
+
+    // Build a widget handler and call it, as axum would for GET /widget/{id}
+    async fn get_widget(
+        state: Arc<AppState>,
+        id: &str,
+        headers: HeaderMap,
+    ) -> Result<CachedJson<Widget>> {
+        let handler = create_api_handler(
+            |_state: &Arc<AppState>, id: String| async move { Ok(Widget { id }) },
+            ApiHandlerOptions {
+                use_retries: false,
+                detailed_logging: false,
+                ..Default::default()
+            },
+        );
+
+        handler(State(state), Path(id.to_string()), headers).await
+    }
+
+    #[tokio::test]
+    async fn test_handler_reports_cache_outcome() {
+        let state = create_test_state(true);
+
+        let first = get_widget(state.clone(), "1", HeaderMap::new())
+            .await
+            .unwrap();
+        assert_eq!(first.outcome, CacheOutcome::Miss);
+
+        let second = get_widget(state, "1", HeaderMap::new()).await.unwrap();
+        assert_eq!(second.outcome, CacheOutcome::Hit);
+        assert_eq!(
+            second.resource,
+            Widget {
+                id: "1".to_string()
+            }
+        );
+
+        let response = second.into_response();
+        assert_eq!(response.headers()["x-cache"], "HIT");
+        assert_eq!(
+            response.headers()["cache-status"],
+            format!("{}; hit", CACHE_STATUS_NAME).as_str()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cached_handler_retries_and_keeps_upstream_errors() {
+        let calls = Arc::new(AtomicU64::new(0));
+        let handler = create_api_handler(
+            {
+                let calls = calls.clone();
+                move |_state: &Arc<AppState>, _id: String| {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    async move {
+                        let mut headers = HeaderMap::new();
+                        headers.insert("retry-after", HeaderValue::from_static("0"));
+                        Err::<Widget, _>(
+                            UpstreamError::new(
+                                "WidgetService",
+                                Some(StatusCode::TOO_MANY_REQUESTS),
+                                "HTTP 429",
+                            )
+                            .with_headers(headers)
+                            .into(),
+                        )
+                    }
+                }
+            },
+            ApiHandlerOptions {
+                max_retry_attempts: 2,
+                detailed_logging: false,
+                ..Default::default()
+            },
+        );
+
+        let error = handler(
+            State(create_test_state(true)),
+            Path("1".to_string()),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(error.upstream().map(|e| e.attempts), Some(3));
+
+        let response = error.into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key("retry-after"));
+    }
+
+    #[tokio::test]
+    async fn test_handler_bypasses_without_cache() {
+        let response = get_widget(create_test_state(false), "1", HeaderMap::new())
+            .await
+            .unwrap();
+        assert_eq!(response.outcome, CacheOutcome::Bypass);
+        assert_eq!(response.into_response().headers()["x-cache"], "BYPASS");
+    }
+
+    #[tokio::test]
+    async fn test_handler_answers_not_modified() {
+        let state = create_test_state(true);
+
+        let first = get_widget(state.clone(), "1", HeaderMap::new())
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(first.status(), StatusCode::OK);
+        let etag = first.headers()["etag"].clone();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("if-none-match", etag.clone());
+        let second = get_widget(state, "1", headers)
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(second.headers()["etag"], etag);
+        assert_eq!(second.headers()["x-cache"], "HIT");
+    }
+}
@@ -0,0 +1,496 @@
+//! CRUD proxy handlers for API resources
+//!
+//! Siblings of [`create_api_handler`](super::create_api_handler) that list,
+//! create, update and delete resources through [`ResourceClient`], keeping
+//! the resource cache in step with the writes. [`route_resource`] mounts all
+//! five on a router.
+
+use axum::{
+    Json, Router,
+    extract::{Path, RawQuery, State},
+    http::StatusCode,
+    routing::get,
+};
+use futures::future::BoxFuture;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+use super::client::ResourceClient;
+use super::core::{ApiHandlerOptions, ApiResource, create_api_handler, register_on_first_use};
+use crate::{
+    cache::{CacheBackend, providers::CacheProvider},
+    core::cache::get_resource_cache,
+    core::router::AppState,
+    error::{AppError, Result},
+};
+
+/// The state extractor taken by the handlers in this module
+type AppStateArg = State<Arc<AppState>>;
+
+/// The future returned by the handlers in this module
+type HandlerFuture<T> = BoxFuture<'static, Result<T>>;
+
+/// The client for `R`, retrying as `options` allow
+fn resource_client<R: ApiResource>(
+    state: &AppState,
+    options: &ApiHandlerOptions,
+) -> Result<ResourceClient<R>> {
+    Ok(ResourceClient::<R>::from_state(state)?.with_retry_policy(options.retry_policy()))
+}
+
+fn parse_id<R>(id_str: &str) -> Result<R::Id>
+where
+    R: ApiResource,
+    R::Id: FromStr,
+{
+    id_str.parse().map_err(|_| {
+        AppError::BadRequest(format!(
+            "Invalid ID format for {}: {}",
+            R::resource_type(),
+            id_str
+        ))
+    })
+}
+
+/// Drop the cached `id` here and, through the invalidation bus, on other replicas
+async fn evict_cached<R: ApiResource>(state: &AppState, id: &str) {
+    let resource_type = R::resource_type();
+    if let Some(registry) = &state.cache_registry {
+        registry.invalidate_key(resource_type, id).await;
+    }
+    // The memory provider shares the registry; other providers keep their own copy
+    if let Some(provider) = state.cache_provider.as_deref()
+        && !matches!(provider, CacheBackend::Memory(_))
+        && let Err(e) = provider
+            .delete(&CacheBackend::cache_key(resource_type, id))
+            .await
+    {
+        warn!(
+            "⚠️ Failed to evict {} {} from cache: {}",
+            resource_type, id, e
+        );
+    }
+    if let Some(bus) = &state.cache_invalidation
+        && let Err(e) = bus.publish(resource_type, id).await
+    {
+        warn!(
+            "⚠️ Failed to publish invalidation of {} {}: {}",
+            resource_type, id, e
+        );
+    }
+}
+
+/// Replace the cached `id` with `resource`, evicting it on other replicas
+async fn store_cached<R: ApiResource>(
+    state: &Arc<AppState>,
+    id: &str,
+    resource: &R,
+    options: &ApiHandlerOptions,
+) {
+    evict_cached::<R>(state, id).await;
+
+    let resource_type = R::resource_type();
+    register_on_first_use::<R>(state, options.cache_ttl_seconds);
+    if let Some(registry) = &state.cache_registry
+        && let Some(cache) = get_resource_cache::<R>(registry, resource_type)
+    {
+        cache.insert(id.to_string(), resource.clone()).await;
+    }
+    if let Some(provider) = state.cache_provider.as_deref()
+        && !matches!(provider, CacheBackend::Memory(_))
+        && let Err(e) = provider
+            .set(
+                &CacheBackend::cache_key(resource_type, id),
+                resource.clone(),
+                options.cache_ttl_seconds,
+            )
+            .await
+    {
+        warn!("⚠️ Failed to cache {} {}: {}", resource_type, id, e);
+    }
+    debug!("➕ Cached updated {} {}", resource_type, id);
+}
+
+/// Creates a handler that lists resources from the upstream, e.g. `GET /pet?status=sold`
+///
+/// The query string is passed through unchanged. Lists are not cached.
+pub fn create_list_handler<R>(
+    options: ApiHandlerOptions,
+) -> impl Fn(AppStateArg, RawQuery) -> HandlerFuture<Json<Vec<R>>> + Clone + Send + Sync + 'static
+where
+    R: ApiResource,
+{
+    move |State(state), RawQuery(query)| {
+        let options = options.clone();
+        Box::pin(async move {
+            if options.detailed_logging {
+                info!("🔍 Listing {} ({:?})", R::resource_type(), query);
+            }
+            let resources = resource_client::<R>(&state, &options)?
+                .list_with_query(query.as_deref())
+                .await?;
+            Ok(Json(resources))
+        })
+    }
+}
+
+/// Creates a handler that creates a resource upstream, e.g. `POST /pet`
+///
+/// Responds with `201 Created` and the upstream's copy. Nothing is cached,
+/// since the new resource's ID is only known to the upstream.
+pub fn create_create_handler<R>(
+    options: ApiHandlerOptions,
+) -> impl Fn(AppStateArg, Json<R>) -> HandlerFuture<(StatusCode, Json<R>)> + Clone + Send + Sync + 'static
+where
+    R: ApiResource,
+{
+    move |State(state), Json(resource)| {
+        let options = options.clone();
+        Box::pin(async move {
+            if options.detailed_logging {
+                info!("➕ Creating {}", R::resource_type());
+            }
+            let created = resource_client::<R>(&state, &options)?
+                .create(&resource)
+                .await?;
+            Ok((StatusCode::CREATED, Json(created)))
+        })
+    }
+}
+
+/// Creates a handler that replaces a resource upstream, e.g. `PUT /pet/{id}`
+///
+/// With `use_cache`, the cached copy is replaced by the updated resource and
+/// evicted on other replicas.
+pub fn create_update_handler<R>(
+    options: ApiHandlerOptions,
+) -> impl Fn(AppStateArg, Path<String>, Json<R>) -> HandlerFuture<Json<R>> + Clone + Send + Sync + 'static
+where
+    R: ApiResource,
+    R::Id: FromStr,
+{
+    move |State(state), Path(id_str), Json(resource)| {
+        let options = options.clone();
+        Box::pin(async move {
+            if options.detailed_logging {
+                info!("✏️ Updating {} with ID: {}", R::resource_type(), id_str);
+            }
+            let id = parse_id::<R>(&id_str)?;
+            let updated = resource_client::<R>(&state, &options)?
+                .update(&id, &resource)
+                .await?;
+            if options.use_cache {
+                store_cached::<R>(&state, &id.to_string(), &updated, &options).await;
+            }
+            Ok(Json(updated))
+        })
+    }
+}
+
+/// Creates a handler that deletes a resource upstream, e.g. `DELETE /pet/{id}`
+///
+/// Responds with `204 No Content`. With `use_cache`, the cached copy is
+/// evicted here and on other replicas.
+pub fn create_delete_handler<R>(
+    options: ApiHandlerOptions,
+) -> impl Fn(AppStateArg, Path<String>) -> HandlerFuture<StatusCode> + Clone + Send + Sync + 'static
+where
+    R: ApiResource,
+    R::Id: FromStr,
+{
+    move |State(state), Path(id_str)| {
+        let options = options.clone();
+        Box::pin(async move {
+            if options.detailed_logging {
+                info!("❌ Deleting {} with ID: {}", R::resource_type(), id_str);
+            }
+            let id = parse_id::<R>(&id_str)?;
+            resource_client::<R>(&state, &options)?.delete(&id).await?;
+            if options.use_cache {
+                evict_cached::<R>(&state, &id.to_string()).await;
+            }
+            Ok(StatusCode::NO_CONTENT)
+        })
+    }
+}
+
+/// Mount list, get, create, update and delete handlers for `R` under `path`
+///
+/// `GET`/`POST` are routed on `path` and `GET`/`PUT`/`DELETE` on `{path}/{id}`.
+/// The get handler is [`create_api_handler`] fetching through [`ResourceClient`].
+/// The handler applies `options`' retry policy on both its cached and uncached
+/// paths, so the client makes a single attempt per fetch.
+pub fn route_resource<R>(
+    router: Router<Arc<AppState>>,
+    path: &str,
+    options: ApiHandlerOptions,
+) -> Router<Arc<AppState>>
+where
+    R: ApiResource,
+    R::Id: FromStr,
+{
+    let fetch_fn = |state: &Arc<AppState>, id: R::Id| -> HandlerFuture<R> {
+        let client =
+            ResourceClient::<R>::from_state(state).map(|client| client.with_max_retries(0));
+        Box::pin(async move { client?.get(&id).await })
+    };
+
+    let path = path.trim_end_matches('/');
+    router
+        .route(
+            path,
+            get(create_list_handler::<R>(options.clone()))
+                .post(create_create_handler::<R>(options.clone())),
+        )
+        .route(
+            &format!("{}/{{id}}", path),
+            get(create_api_handler(fetch_fn, options.clone()))
+                .put(create_update_handler::<R>(options.clone()))
+                .delete(create_delete_handler::<R>(options)),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::UpstreamConfig;
+    use crate::core::cache::{get_cached, init_cache_registry};
+    use crate::core::config::app_config::AppConfig;
+    use crate::utils::api_resource::ApiResourceRegistry;
+    use axum::body::{self, Body};
+    use axum::http::Request;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use reqwest::Client;
+    use serde::{Deserialize, Serialize};
+    use std::time::SystemTime;
+    use tower::ServiceExt;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct Gadget {
+        id: i64,
+        name: String,
+    }
+
+    impl ApiResource for Gadget {
+        type Id = i64;
+
+        fn resource_type() -> &'static str {
+            "gadget"
+        }
+
+        fn api_name() -> &'static str {
+            "Gadgets"
+        }
+
+        fn endpoint() -> Option<&'static str> {
+            Some("/gadgets/{id}")
+        }
+
+        fn base_url_key() -> Option<&'static str> {
+            Some("gadgets")
+        }
+    }
+
+    fn gadget(id: i64, name: &str) -> Gadget {
+        Gadget {
+            id,
+            name: name.to_string(),
+        }
+    }
+
+    fn create_test_state(upstream: &MockServer) -> Arc<AppState> {
+        let mut config = AppConfig::default();
+        config.api.upstreams.insert(
+            "gadgets".to_string(),
+            UpstreamConfig {
+                url: upstream.uri(),
+                scope: None,
+            },
+        );
+
+        Arc::new(AppState {
+            client: Client::new(),
+            config,
+            start_time: SystemTime::now(),
+            cache_registry: Some(init_cache_registry(true, 100, 3600)),
+            cache_provider: None,
+            metrics_handle: PrometheusBuilder::new().build_recorder().handle(),
+            token_client: None,
+            resource_registry: ApiResourceRegistry::new(),
+            db_pool: None,
+            cache_invalidation: None,
+            user_repository: None,
+        })
+    }
+
+    fn app(state: Arc<AppState>) -> Router {
+        let options = ApiHandlerOptions {
+            use_retries: false,
+            detailed_logging: false,
+            ..Default::default()
+        };
+        route_resource::<Gadget>(Router::new(), "/gadgets", options).with_state(state)
+    }
+
+    async fn send(
+        app: &Router,
+        method: &str,
+        uri: &str,
+        body: Option<&Gadget>,
+    ) -> (StatusCode, String) {
+        let mut request = Request::builder().method(method).uri(uri);
+        let body = match body {
+            Some(gadget) => {
+                request = request.header("content-type", "application/json");
+                Body::from(serde_json::to_vec(gadget).unwrap())
+            }
+            None => Body::empty(),
+        };
+        let response = app
+            .clone()
+            .oneshot(request.body(body).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_routes_proxy_all_five_operations() {
+        let upstream = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/gadgets"))
+            .and(query_param("status", "sold"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(vec![gadget(1, "one")]))
+            .mount(&upstream)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/gadgets/1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(gadget(1, "one")))
+            .mount(&upstream)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/gadgets"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(gadget(2, "two")))
+            .mount(&upstream)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/gadgets/1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(gadget(1, "renamed")))
+            .mount(&upstream)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/gadgets/1"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&upstream)
+            .await;
+        let app = app(create_test_state(&upstream));
+
+        let (status, body) = send(&app, "GET", "/gadgets?status=sold", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            serde_json::from_str::<Vec<Gadget>>(&body).unwrap(),
+            vec![gadget(1, "one")]
+        );
+
+        let (status, body) = send(&app, "GET", "/gadgets/1", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            serde_json::from_str::<Gadget>(&body).unwrap(),
+            gadget(1, "one")
+        );
+
+        let (status, body) = send(&app, "POST", "/gadgets", Some(&gadget(0, "two"))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(
+            serde_json::from_str::<Gadget>(&body).unwrap(),
+            gadget(2, "two")
+        );
+
+        let (status, _) = send(&app, "PUT", "/gadgets/1", Some(&gadget(1, "renamed"))).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _) = send(&app, "DELETE", "/gadgets/1", None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (status, _) = send(&app, "DELETE", "/gadgets/abc", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_cached_get_is_retried_by_the_handler() {
+        let upstream = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/gadgets/1"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&upstream)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/gadgets/1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(gadget(1, "one")))
+            .mount(&upstream)
+            .await;
+        let options = ApiHandlerOptions {
+            max_retry_attempts: 1,
+            detailed_logging: false,
+            ..Default::default()
+        };
+        let app = route_resource::<Gadget>(Router::new(), "/gadgets", options)
+            .with_state(create_test_state(&upstream));
+
+        let (status, body) = send(&app, "GET", "/gadgets/1", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            serde_json::from_str::<Gadget>(&body).unwrap(),
+            gadget(1, "one")
+        );
+        assert_eq!(upstream.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_writes_keep_the_cache_in_step() {
+        let upstream = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/gadgets/1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(gadget(1, "one")))
+            .expect(1)
+            .mount(&upstream)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/gadgets/1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(gadget(1, "renamed")))
+            .mount(&upstream)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/gadgets/1"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&upstream)
+            .await;
+        let state = create_test_state(&upstream);
+        let registry = state.cache_registry.clone().unwrap();
+        let app = app(state);
+
+        // Cache the original, then update it: the cache holds the updated copy
+        send(&app, "GET", "/gadgets/1", None).await;
+        send(&app, "PUT", "/gadgets/1", Some(&gadget(1, "renamed"))).await;
+        assert_eq!(
+            get_cached::<Gadget>(&registry, "gadget", "1").await,
+            Some(gadget(1, "renamed"))
+        );
+        let (_, body) = send(&app, "GET", "/gadgets/1", None).await;
+        assert_eq!(
+            serde_json::from_str::<Gadget>(&body).unwrap(),
+            gadget(1, "renamed")
+        );
+
+        send(&app, "DELETE", "/gadgets/1", None).await;
+        assert_eq!(get_cached::<Gadget>(&registry, "gadget", "1").await, None);
+    }
+}
@@ -3,14 +3,26 @@
 //! This module provides a high-level abstraction for API resources that
 //! handles common concerns like caching, retries, and error handling.
 
+mod batch;
+mod client;
 mod core;
+mod crud;
 mod registry;
+mod retry;
 
 // Re-export public items
-pub use core::{ApiHandlerOptions, ApiResource, create_api_handler, fetch_with_retry};
+pub use batch::{BatchItem, BatchQuery, BatchResponse, create_batch_api_handler};
+pub use client::ResourceClient;
+pub use core::{ApiHandlerOptions, ApiResource, CachedJson, create_api_handler, fetch_with_retry};
+pub use crud::{
+    create_create_handler, create_delete_handler, create_list_handler, create_update_handler,
+    route_resource,
+};
 pub use registry::*;
+pub use retry::RetryPolicy;
 
-use crate::core::cache::cache_manager::register_resource_cache;
+use crate::config::ResourceCacheConfig;
+use crate::core::cache::cache_manager::register_resource_cache_with_config;
 use crate::core::router::AppState;
 use std::sync::Arc;
 use tracing::info;
@@ -36,6 +48,25 @@ use tracing::info;
 pub fn register_resource<T: ApiResource + 'static>(
     state: &Arc<AppState>,
     resource_type: Option<&str>,
+) -> Result<(), String> {
+    register_resource_with_config::<T>(state, resource_type, ResourceCacheConfig::default())
+}
+
+/// Register a resource type with its own cache settings
+///
+/// Like [`register_resource`], but with a TTL, TTI and capacity for this resource
+/// type. Unset fields use the `cache.resources.<type>` section of the config and
+/// then the registry-wide defaults; a TTL of 0 keeps entries until invalidated.
+///
+/// # Arguments
+///
+/// - `state`: The application state
+/// - `resource_type`: The resource type to register (optional, defaults to T::resource_type())
+/// - `settings`: Cache settings for this resource type
+pub fn register_resource_with_config<T: ApiResource + 'static>(
+    state: &Arc<AppState>,
+    resource_type: Option<&str>,
+    settings: ResourceCacheConfig,
 ) -> Result<(), String> {
     // Skip if cache is disabled
     let Some(registry) = &state.cache_registry else {
@@ -45,7 +76,7 @@ pub fn register_resource<T: ApiResource + 'static>(
     let resource_name = resource_type.unwrap_or_else(|| T::resource_type());
 
     // Register the resource type in the cache registry
-    match register_resource_cache::<T>(registry, resource_name) {
+    match register_resource_cache_with_config::<T>(registry, resource_name, settings) {
         Ok(_) => {
             info!(
                 "✅ Registered resource type {} in cache registry",
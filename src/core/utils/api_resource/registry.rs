@@ -1,7 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
 use std::sync::{Arc, RwLock};
-use tracing::{debug, info};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
 
 use crate::core::router::AppState;
 use crate::models::DependencyStatus;
@@ -29,6 +30,9 @@ pub struct ResourceRegistration {
 pub struct ApiResourceRegistry {
     /// Map of resource type names to registrations
     resources: RwLock<HashMap<String, ResourceRegistration>>,
+
+    /// Results of the last health check run and when it finished
+    last_health: tokio::sync::Mutex<Option<(Instant, Vec<DependencyStatus>)>>,
 }
 
 impl ApiResourceRegistry {
@@ -36,6 +40,7 @@ impl ApiResourceRegistry {
     pub fn new() -> Self {
         Self {
             resources: RwLock::new(HashMap::new()),
+            last_health: tokio::sync::Mutex::new(None),
         }
     }
 
@@ -122,23 +127,71 @@ impl ApiResourceRegistry {
     }
 
     /// Run health checks for all registered resources
-    pub async fn run_all_health_checks(&self, state: &Arc<AppState>) -> Vec<DependencyStatus> {
-        let resources = match self.resources.read() {
-            Ok(resources) => resources,
+    ///
+    /// Checks run concurrently, and one that takes longer than `timeout` is
+    /// reported down. Results are sorted by dependency name.
+    pub async fn run_all_health_checks(
+        &self,
+        state: &Arc<AppState>,
+        timeout: Duration,
+    ) -> Vec<DependencyStatus> {
+        // Start the checks under the lock, but await them after releasing it
+        let checks: Vec<_> = match self.resources.read() {
+            Ok(resources) => resources
+                .values()
+                .map(|registration| {
+                    let name =
+                        format!("{} ({})", registration.api_name, registration.resource_type);
+                    (name, (registration.health_check_fn)(state))
+                })
+                .collect(),
             Err(_) => {
                 debug!("Failed to acquire read lock on resource registry");
                 return Vec::new();
             }
         };
 
-        let mut results = Vec::new();
+        let mut results =
+            futures::future::join_all(checks.into_iter().map(|(name, check)| async move {
+                match tokio::time::timeout(timeout, check).await {
+                    Ok(status) => status,
+                    Err(_) => {
+                        warn!("⚠️ Health check for {} timed out", name);
+                        let mut details = BTreeMap::new();
+                        details.insert("checked_at".to_string(), chrono::Utc::now().to_rfc3339());
+                        DependencyStatus {
+                            name,
+                            status: format!("down (timed out after {}ms)", timeout.as_millis()),
+                            details: Some(details),
+                        }
+                    }
+                }
+            }))
+            .await;
+
+        results.sort_by(|a, b| a.name.cmp(&b.name));
+        results
+    }
 
-        for (_, registration) in resources.iter() {
-            let health_check = &registration.health_check_fn;
-            let status = health_check(state).await;
-            results.push(status);
+    /// Like [`run_all_health_checks`](Self::run_all_health_checks), reusing results up to `max_age` old
+    ///
+    /// Concurrent callers wait for a single run rather than each checking the
+    /// upstreams, so frequent health probes cannot flood them.
+    pub async fn cached_health_checks(
+        &self,
+        state: &Arc<AppState>,
+        timeout: Duration,
+        max_age: Duration,
+    ) -> Vec<DependencyStatus> {
+        let mut last_health = self.last_health.lock().await;
+        if let Some((checked_at, results)) = last_health.as_ref()
+            && checked_at.elapsed() < max_age
+        {
+            return results.clone();
         }
 
+        let results = self.run_all_health_checks(state, timeout).await;
+        *last_health = Some((Instant::now(), results.clone()));
         results
     }
 }
@@ -153,3 +206,136 @@ impl std::fmt::Debug for ResourceRegistration {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::app_config::AppConfig;
+    use futures::future::BoxFuture;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use reqwest::Client;
+    use serde::{Deserialize, Serialize};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::SystemTime;
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Fast;
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Slow;
+
+    impl ApiResource for Fast {
+        type Id = i64;
+
+        fn resource_type() -> &'static str {
+            "fast"
+        }
+
+        fn api_name() -> &'static str {
+            "FastService"
+        }
+    }
+
+    impl ApiResource for Slow {
+        type Id = i64;
+
+        fn resource_type() -> &'static str {
+            "slow"
+        }
+
+        fn api_name() -> &'static str {
+            "SlowService"
+        }
+    }
+
+    fn create_test_state() -> Arc<AppState> {
+        Arc::new(AppState {
+            client: Client::new(),
+            config: AppConfig::default(),
+            start_time: SystemTime::now(),
+            cache_registry: None,
+            cache_provider: None,
+            metrics_handle: PrometheusBuilder::new().build_recorder().handle(),
+            token_client: None,
+            resource_registry: ApiResourceRegistry::new(),
+            db_pool: None,
+            cache_invalidation: None,
+            user_repository: None,
+        })
+    }
+
+    /// A check that reports "up" after `delay`, counting its runs
+    fn check(
+        name: &'static str,
+        delay: Duration,
+        runs: Arc<AtomicUsize>,
+    ) -> impl Fn(&Arc<AppState>) -> BoxFuture<'static, DependencyStatus> + Send + Sync + 'static
+    {
+        move |_| {
+            runs.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move {
+                tokio::time::sleep(delay).await;
+                DependencyStatus {
+                    name: name.to_string(),
+                    status: "up".to_string(),
+                    details: None,
+                }
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_health_checks_run_concurrently_with_timeouts() {
+        let state = create_test_state();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let registry = ApiResourceRegistry::new();
+        registry
+            .register::<Fast, _>(check("fast", Duration::from_millis(100), runs.clone()))
+            .unwrap();
+        registry
+            .register::<Slow, _>(check("slow", Duration::from_secs(5), runs.clone()))
+            .unwrap();
+
+        let started = Instant::now();
+        let results = registry
+            .run_all_health_checks(&state, Duration::from_millis(300))
+            .await;
+
+        // The slow check is cut off at the timeout rather than holding up the run
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].name, "SlowService (slow)");
+        assert_eq!(results[0].status, "down (timed out after 300ms)");
+        assert_eq!(results[1].name, "fast");
+        assert_eq!(results[1].status, "up");
+    }
+
+    #[tokio::test]
+    async fn test_cached_health_checks_reuse_recent_results() {
+        let state = create_test_state();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let registry = ApiResourceRegistry::new();
+        registry
+            .register::<Fast, _>(check("fast", Duration::from_millis(50), runs.clone()))
+            .unwrap();
+
+        let timeout = Duration::from_secs(1);
+        let max_age = Duration::from_millis(300);
+
+        // Concurrent probes share a single run
+        let (a, b) = tokio::join!(
+            registry.cached_health_checks(&state, timeout, max_age),
+            registry.cached_health_checks(&state, timeout, max_age)
+        );
+        assert_eq!(a.len(), 1);
+        assert_eq!(b.len(), 1);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        // Results older than max_age are refreshed
+        tokio::time::sleep(max_age).await;
+        registry
+            .cached_health_checks(&state, timeout, max_age)
+            .await;
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,258 @@
+//! Retry policy for upstream calls
+
+use metrics::{counter, histogram};
+use rand::Rng;
+use reqwest::StatusCode;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::error::{AppError, Result, UpstreamError};
+
+/// When, and after how long, a failed upstream call is retried
+///
+/// Only failures that may succeed on another try are retried: connection
+/// errors, 429 and 5xx responses. Other 4xx responses are final. A
+/// `Retry-After` from the upstream is honoured; otherwise the delay is drawn
+/// uniformly between zero and an exponentially growing cap ("full jitter").
+/// A retry that would start after the deadline is not attempted.
+///
+/// Non-idempotent requests should use a policy with no retries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+
+    /// Backoff cap for the first retry, doubled for each retry after it
+    pub base_delay: Duration,
+
+    /// Upper bound on the backoff cap
+    pub max_delay: Duration,
+
+    /// Total time, from the first attempt, after which no retry is started
+    pub deadline: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            deadline: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy retrying up to `max_retries` times with the default backoff and deadline
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..Default::default()
+        }
+    }
+
+    /// A policy that never retries
+    pub fn none() -> Self {
+        Self::new(0)
+    }
+
+    /// Set the backoff cap for the first retry and its upper bound
+    pub fn with_backoff(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Set the total time after which no retry is started
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Whether `error` may succeed on another try
+    pub fn is_retryable(error: &AppError) -> bool {
+        match error {
+            AppError::Upstream(upstream) => match upstream.status {
+                Some(status) => status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error(),
+                None => true,
+            },
+            AppError::ExternalServiceError(_) | AppError::ClientError(_) => true,
+            AppError::RateLimited(_) => true,
+            _ => false,
+        }
+    }
+
+    /// How long to wait before retry number `retry` (from 1) after `error`, or `None` to give up
+    pub fn retry_delay(&self, error: &AppError, retry: u32, elapsed: Duration) -> Option<Duration> {
+        if retry > self.max_retries || !Self::is_retryable(error) {
+            return None;
+        }
+
+        let delay = error
+            .upstream()
+            .and_then(UpstreamError::retry_after)
+            .unwrap_or_else(|| self.backoff(retry));
+        (elapsed + delay < self.deadline).then_some(delay)
+    }
+
+    /// Full-jitter backoff for retry number `retry`
+    fn backoff(&self, retry: u32) -> Duration {
+        let cap = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_delay);
+        let millis = cap.as_millis() as u64;
+        Duration::from_millis(rand::rng().random_range(0..=millis))
+    }
+
+    /// Run `operation` until it succeeds or the policy gives up
+    ///
+    /// `operation` receives the attempt number, from 1. The number of
+    /// attempts is recorded in the `upstream_request_attempts` histogram and
+    /// on the returned error.
+    pub async fn run<T, F, Fut>(&self, api_name: &str, mut operation: F) -> Result<T>
+    where
+        F: FnMut(u32) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let started = Instant::now();
+        let mut attempt = 1;
+        loop {
+            let error = match operation(attempt).await {
+                Ok(value) => {
+                    record_attempts(api_name, attempt, "success");
+                    return Ok(value);
+                }
+                Err(error) => error,
+            };
+
+            match self.retry_delay(&error, attempt, started.elapsed()) {
+                Some(delay) => {
+                    warn!(
+                        "❌ {} API attempt {} failed, retrying in {}ms: {}",
+                        api_name,
+                        attempt,
+                        delay.as_millis(),
+                        error
+                    );
+                    counter!("upstream_retries_total", "api" => api_name.to_string()).increment(1);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                None => {
+                    record_attempts(api_name, attempt, "failure");
+                    return Err(error.with_attempts(attempt));
+                }
+            }
+        }
+    }
+}
+
+fn record_attempts(api_name: &str, attempts: u32, outcome: &'static str) {
+    histogram!(
+        "upstream_request_attempts",
+        "api" => api_name.to_string(),
+        "outcome" => outcome
+    )
+    .record(attempts as f64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn upstream(status: Option<StatusCode>) -> AppError {
+        UpstreamError::new("Test", status, "failed").into()
+    }
+
+    #[test]
+    fn test_only_transient_failures_are_retried() {
+        assert!(RetryPolicy::is_retryable(&upstream(None)));
+        assert!(RetryPolicy::is_retryable(&upstream(Some(
+            StatusCode::TOO_MANY_REQUESTS
+        ))));
+        assert!(RetryPolicy::is_retryable(&upstream(Some(
+            StatusCode::SERVICE_UNAVAILABLE
+        ))));
+        assert!(!RetryPolicy::is_retryable(&upstream(Some(
+            StatusCode::NOT_FOUND
+        ))));
+        assert!(!RetryPolicy::is_retryable(&upstream(Some(
+            StatusCode::FORBIDDEN
+        ))));
+        assert!(!RetryPolicy::is_retryable(&AppError::BadRequest(
+            "bad".to_string()
+        )));
+    }
+
+    #[test]
+    fn test_retry_delay_honours_retry_after_and_deadline() {
+        let policy = RetryPolicy::new(3).with_deadline(Duration::from_secs(5));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("2"));
+        let limited = AppError::from(
+            UpstreamError::new("Test", Some(StatusCode::TOO_MANY_REQUESTS), "slow down")
+                .with_headers(headers),
+        );
+        assert_eq!(
+            policy.retry_delay(&limited, 1, Duration::ZERO),
+            Some(Duration::from_secs(2))
+        );
+
+        // Waiting would run past the deadline, so give up instead
+        assert_eq!(
+            policy.retry_delay(&limited, 1, Duration::from_secs(4)),
+            None
+        );
+
+        // Out of retries
+        assert_eq!(policy.retry_delay(&limited, 4, Duration::ZERO), None);
+    }
+
+    #[test]
+    fn test_backoff_is_jittered_below_the_cap() {
+        let policy = RetryPolicy::new(10)
+            .with_backoff(Duration::from_millis(100), Duration::from_millis(300));
+
+        for retry in 1..=10 {
+            let cap = Duration::from_millis(match retry {
+                1 => 100,
+                2 => 200,
+                _ => 300,
+            });
+            assert!(policy.backoff(retry) <= cap);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_records_attempts_on_the_error() {
+        let policy =
+            RetryPolicy::new(2).with_backoff(Duration::from_millis(1), Duration::from_millis(1));
+        let calls = AtomicU32::new(0);
+
+        let result: Result<()> = policy
+            .run("Test", |_| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async { Err(upstream(Some(StatusCode::BAD_GATEWAY))) }
+            })
+            .await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(result.unwrap_err().upstream().unwrap().attempts, 3);
+
+        // A 4xx failure is returned straight away
+        calls.store(0, Ordering::SeqCst);
+        let result: Result<()> = policy
+            .run("Test", |_| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async { Err(upstream(Some(StatusCode::NOT_FOUND))) }
+            })
+            .await;
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(result.unwrap_err().upstream().unwrap().attempts, 1);
+    }
+}
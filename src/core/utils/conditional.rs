@@ -0,0 +1,223 @@
+//! HTTP conditional request support (RFC 9110 section 13)
+//!
+//! Computes strong entity tags from a resource's JSON representation and
+//! evaluates `If-None-Match`, `If-Modified-Since` and `If-Match` against them,
+//! so handlers can answer `304 Not Modified` and `412 Precondition Failed`.
+
+use axum::{
+    Json,
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, SubsecRound, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Compute a strong ETag (quoted) from the JSON serialization of `value`
+///
+/// Returns `None` if the value cannot be serialized.
+pub fn strong_etag<T: Serialize>(value: &T) -> Option<String> {
+    let body = serde_json::to_vec(value).ok()?;
+    let digest = Sha256::digest(&body);
+    let hex: String = digest[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    Some(format!("\"{}\"", hex))
+}
+
+/// Format a timestamp as an HTTP-date (IMF-fixdate)
+pub fn http_date(time: DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// Parse an HTTP-date header value
+pub fn parse_http_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(value)
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// Split an entity-tag list header into its tags (`*` is returned as-is)
+fn entity_tags(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+}
+
+/// Strip the weak indicator so tags can be compared weakly
+fn opaque_tag(tag: &str) -> &str {
+    tag.strip_prefix("W/").unwrap_or(tag)
+}
+
+/// Whether `If-None-Match` matches `etag` (weak comparison)
+///
+/// Returns `None` if the request has no `If-None-Match` header.
+pub fn if_none_match(headers: &HeaderMap, etag: &str) -> Option<bool> {
+    let value = headers.get(header::IF_NONE_MATCH)?.to_str().ok()?;
+    Some(entity_tags(value).any(|tag| tag == "*" || opaque_tag(tag) == opaque_tag(etag)))
+}
+
+/// Whether `If-Match` matches `etag` (strong comparison)
+///
+/// Returns `None` if the request has no `If-Match` header. Weak tags never
+/// match, as required for state-changing requests.
+pub fn if_match(headers: &HeaderMap, etag: &str) -> Option<bool> {
+    let value = headers.get(header::IF_MATCH)?.to_str().ok()?;
+    Some(entity_tags(value).any(|tag| tag == "*" || (!tag.starts_with("W/") && tag == etag)))
+}
+
+/// Whether a GET for a representation with these validators is answered with 304
+///
+/// `If-None-Match` takes precedence; `If-Modified-Since` is only evaluated when
+/// it is absent (RFC 9110 section 13.2.2).
+pub fn is_not_modified(
+    headers: &HeaderMap,
+    etag: Option<&str>,
+    last_modified: Option<DateTime<Utc>>,
+) -> bool {
+    if headers.contains_key(header::IF_NONE_MATCH) {
+        return etag
+            .and_then(|etag| if_none_match(headers, etag))
+            .unwrap_or(false);
+    }
+
+    let since = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_http_date);
+
+    match (since, last_modified) {
+        // HTTP-dates have one-second resolution
+        (Some(since), Some(modified)) => modified.trunc_subsecs(0) <= since,
+        _ => false,
+    }
+}
+
+/// Add `ETag` and `Last-Modified` headers to a response
+pub fn set_validators(
+    headers: &mut HeaderMap,
+    etag: Option<&str>,
+    last_modified: Option<DateTime<Utc>>,
+) {
+    if let Some(value) = etag.and_then(|etag| HeaderValue::from_str(etag).ok()) {
+        headers.insert(header::ETAG, value);
+    }
+    if let Some(value) = last_modified.and_then(|time| HeaderValue::from_str(&http_date(time)).ok())
+    {
+        headers.insert(header::LAST_MODIFIED, value);
+    }
+}
+
+/// Respond with `value` as JSON, or with 304 if the request's validators match
+///
+/// Both responses carry `ETag` (and `Last-Modified` when known).
+pub fn conditional_json<T: Serialize>(
+    request_headers: &HeaderMap,
+    value: &T,
+    last_modified: Option<DateTime<Utc>>,
+) -> Response {
+    let etag = strong_etag(value);
+
+    let mut response = if is_not_modified(request_headers, etag.as_deref(), last_modified) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        Json(value).into_response()
+    };
+
+    set_validators(response.headers_mut(), etag.as_deref(), last_modified);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn headers(name: header::HeaderName, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_strong_etag_is_stable_and_quoted() {
+        let a = strong_etag(&serde_json::json!({ "id": 1 })).unwrap();
+        let b = strong_etag(&serde_json::json!({ "id": 1 })).unwrap();
+        let c = strong_etag(&serde_json::json!({ "id": 2 })).unwrap();
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert!(a.starts_with('"') && a.ends_with('"'));
+    }
+
+    #[test]
+    fn test_http_date_round_trip() {
+        let time = Utc.with_ymd_and_hms(1994, 11, 6, 8, 49, 37).unwrap();
+        assert_eq!(http_date(time), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), Some(time));
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let etag = "\"abc\"";
+        assert_eq!(if_none_match(&HeaderMap::new(), etag), None);
+        assert_eq!(
+            if_none_match(&headers(header::IF_NONE_MATCH, "\"x\", W/\"abc\""), etag),
+            Some(true)
+        );
+        assert_eq!(
+            if_none_match(&headers(header::IF_NONE_MATCH, "*"), etag),
+            Some(true)
+        );
+        assert_eq!(
+            if_none_match(&headers(header::IF_NONE_MATCH, "\"x\""), etag),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_if_match_uses_strong_comparison() {
+        let etag = "\"abc\"";
+        assert_eq!(if_match(&HeaderMap::new(), etag), None);
+        assert_eq!(
+            if_match(&headers(header::IF_MATCH, "\"abc\""), etag),
+            Some(true)
+        );
+        assert_eq!(
+            if_match(&headers(header::IF_MATCH, "W/\"abc\""), etag),
+            Some(false)
+        );
+        assert_eq!(if_match(&headers(header::IF_MATCH, "*"), etag), Some(true));
+    }
+
+    #[test]
+    fn test_is_not_modified() {
+        let modified = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let since = headers(header::IF_MODIFIED_SINCE, &http_date(modified));
+        assert!(is_not_modified(&since, None, Some(modified)));
+
+        let earlier = headers(
+            header::IF_MODIFIED_SINCE,
+            &http_date(modified - chrono::Duration::seconds(1)),
+        );
+        assert!(!is_not_modified(&earlier, None, Some(modified)));
+
+        // If-None-Match takes precedence over If-Modified-Since
+        let mut both = since.clone();
+        both.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"other\""));
+        assert!(!is_not_modified(&both, Some("\"abc\""), Some(modified)));
+    }
+
+    #[test]
+    fn test_conditional_json() {
+        let value = serde_json::json!({ "id": 1 });
+        let etag = strong_etag(&value).unwrap();
+
+        let response = conditional_json(&HeaderMap::new(), &value, None);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::ETAG], etag.as_str());
+
+        let response = conditional_json(&headers(header::IF_NONE_MATCH, &etag), &value, None);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], etag.as_str());
+    }
+}
@@ -1,4 +1,5 @@
 pub mod api_client;
 pub mod api_logger;
 pub mod api_resource;
+pub mod conditional;
 pub mod openapi;
@@ -4,7 +4,9 @@
 //! It serves as a wrapper around the core error handling system located in `src/core/error`.
 
 // Re-export all error types and functions from core
-pub use crate::core::error::error_types::{AppError, ErrorResponse, ErrorSeverity, Result};
+pub use crate::core::error::error_types::{
+    AppError, ErrorResponse, ErrorSeverity, Result, UpstreamError,
+};
 pub use crate::core::error::logger::{LogInfo, LogLevel, log, log_error};
 pub use crate::core::error::middleware::{
     RequestId, RequestIdExt, RequestTrackingLayer, generate_request_id,
@@ -1,17 +1,21 @@
 use axum::{
-    Json,
-    extract::{Path, State},
+    Json, Router,
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    routing::{delete, post},
 };
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use tracing::info;
 
 use crate::{
-    core::router::AppState,
+    core::{cache::register_resource_loader, router::AppState},
     error::{AppError, Result},
     generated_apis::petstore_api::models::Upet,
-    utils::{
-        api_logger,
-        api_resource::{ApiHandlerOptions, ApiResource, create_api_handler},
+    models::DependencyStatus,
+    utils::api_resource::{
+        ApiHandlerOptions, ApiResource, BatchQuery, BatchResponse, CachedJson, ResourceClient,
+        create_api_handler, create_batch_api_handler, create_create_handler, create_delete_handler,
     },
 };
 
@@ -26,13 +30,22 @@ impl ApiResource for Upet {
     fn api_name() -> &'static str {
         "Petstore"
     }
+
+    fn endpoint() -> Option<&'static str> {
+        Some("/pet/{id}")
+    }
+
+    fn base_url_key() -> Option<&'static str> {
+        Some("petstore")
+    }
 }
 
 /// A simple handler for fetching a pet by ID
 pub async fn fetch_pet_handler(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
-) -> Result<Json<Upet>> {
+    headers: HeaderMap,
+) -> Result<CachedJson<Upet>> {
     // Convert the numeric ID to a string for the path parameter
     let id_str = id.to_string();
 
@@ -48,15 +61,7 @@ pub async fn fetch_pet_handler(
         // Log external API call
         info!("🌐 Calling external Petstore API for pet ID: {}", id);
 
-        Box::pin(async move {
-            let url = format!("{}/pet/{}", state.config.petstore_api_url(), id);
-
-            // Create a closure that returns the actual request future
-            let fetch_fn = || async { state.client.get(&url).send().await };
-
-            // Make the API call using the common logger/handler
-            api_logger::api_call("Petstore", &url, fetch_fn, "Pet", id).await
-        })
+        Box::pin(async move { fetch_pet(&state, id).await })
     };
 
     // Create an API handler with enhanced options
@@ -68,19 +73,28 @@ pub async fn fetch_pet_handler(
             max_retry_attempts: 3,
             cache_ttl_seconds: state.config.cache.ttl_seconds, // Use configured TTL instead of hardcoded value
             detailed_logging: true,
+            ..Default::default()
         },
     );
 
     // Execute the handler with proper path extraction
-    let result = handler(State(state), Path(id_str)).await;
+    let result = handler(State(state), Path(id_str), headers).await;
 
     // Log the result of the operation with specific source information
     match &result {
-        Ok(_) => {
-            // Check if it was retrieved from cache using our thread-local
-            let from_cache = crate::core::cache::last_fetch_from_cache();
-            let source = if from_cache { "from cache" } else { "from API" };
-            info!("✅ Successfully retrieved pet ID: {} ({})", id, source)
+        Ok(response) => {
+            // The handler reports whether the pet was served from cache
+            let source = if response.outcome.is_from_cache() {
+                "from cache"
+            } else {
+                "from API"
+            };
+            info!(
+                "✅ Successfully retrieved pet ID: {} ({}, cache {})",
+                id,
+                source,
+                response.outcome.as_str()
+            )
         }
         Err(e) => info!("❌ Failed to retrieve pet ID: {}, error: {}", id, e),
     }
@@ -88,13 +102,127 @@ pub async fn fetch_pet_handler(
     result
 }
 
+/// Fetch several pets at once, e.g. `GET /pet?ids=1,2,3`
+///
+/// Cached pets are returned directly and only the misses are fetched from the
+/// Petstore API; failures are reported per ID.
+pub async fn fetch_pets_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<BatchQuery>,
+) -> Result<Json<BatchResponse<Upet>>> {
+    info!("🔍 Pet batch lookup requested for IDs: {}", query.ids);
+
+    let fetch_fn =
+        |state: &Arc<AppState>, id: i64| -> futures::future::BoxFuture<'static, Result<Upet>> {
+            let state = state.clone();
+            Box::pin(async move { fetch_pet(&state, id).await })
+        };
+
+    let handler = create_batch_api_handler(
+        fetch_fn,
+        ApiHandlerOptions {
+            cache_ttl_seconds: state.config.cache.ttl_seconds,
+            ..Default::default()
+        },
+    );
+
+    handler(State(state), Query(query)).await
+}
+
+/// Routes that add and remove pets through the Petstore API
+///
+/// `POST /pet` and `DELETE /pet/{id}`; deleting a pet also evicts it from the
+/// pet cache. These change upstream data, so mount them behind an auth layer.
+pub fn pet_write_routes(state: &AppState) -> Router<Arc<AppState>> {
+    let options = ApiHandlerOptions {
+        cache_ttl_seconds: state.config.cache.ttl_seconds,
+        ..Default::default()
+    };
+
+    Router::new()
+        .route("/pet", post(create_create_handler::<Upet>(options.clone())))
+        .route("/pet/{id}", delete(create_delete_handler::<Upet>(options)))
+}
+
 /// The core function that does the actual API call to fetch a pet
+///
+/// Makes a single attempt: the get and batch handlers wrap every fetch, cached or
+/// not, in their own retry policy, and retrying here as well would multiply attempts.
 async fn fetch_pet(state: &Arc<AppState>, id: i64) -> Result<Upet> {
-    let url = format!("{}/pet/{}", state.config.petstore_api_url(), id);
+    ResourceClient::<Upet>::from_state(state)?
+        .with_max_retries(0)
+        .get(&id)
+        .await
+}
+
+/// Register the loader used to pre-warm the pet cache through `/actuator/cache/pet/warm`
+pub fn register_pet_cache_loader(state: &Arc<AppState>) -> std::result::Result<(), String> {
+    let Some(registry) = &state.cache_registry else {
+        return Ok(());
+    };
 
-    // Create a closure that returns the actual request future
-    let fetch_fn = || async { state.client.get(&url).send().await };
+    // Capture a client rather than the state, which owns the registry
+    let client = ResourceClient::<Upet>::from_state(state).map_err(|e| e.to_string())?;
 
-    // Make the API call using the common logger/handler
-    api_logger::api_call("Petstore", &url, fetch_fn, "Pet", id).await
+    register_resource_loader::<Upet, _, _>(registry, Upet::resource_type(), move |id| {
+        let client = client.clone();
+
+        async move {
+            let id: i64 = id
+                .parse()
+                .map_err(|_| AppError::BadRequest(format!("Invalid pet ID: {}", id)))?;
+            client.get(&id).await
+        }
+    })
+}
+
+/// Register the Petstore connectivity check reported by the health endpoints
+pub fn register_pet_health_check(state: &Arc<AppState>) -> std::result::Result<(), String> {
+    let Some(upstream) = state
+        .config
+        .upstream("petstore")
+        .filter(|upstream| !upstream.url.is_empty())
+    else {
+        return Ok(());
+    };
+
+    let name = format!("{} ({})", Upet::api_name(), Upet::resource_type());
+    let url = format!("{}/store/inventory", upstream.url);
+    state.resource_registry.register::<Upet, _>(
+        move |state: &Arc<AppState>| -> futures::future::BoxFuture<'static, DependencyStatus> {
+            let name = name.clone();
+            let mut builder = state.client.get(&url);
+
+            // Add API key if configured
+            if let Some(api_key) = &state.config.api.api_key {
+                builder = builder.header("api_key", api_key);
+            }
+
+            Box::pin(async move {
+                let status = match builder.send().await {
+                    Ok(response) if response.status().is_success() => "up".to_string(),
+                    Ok(response) => format!("degraded (status {})", response.status()),
+                    Err(e) => {
+                        let error_message = e.to_string();
+                        let truncated_message = if error_message.chars().count() > 100 {
+                            format!("{}...", error_message.chars().take(100).collect::<String>())
+                        } else {
+                            error_message
+                        };
+                        format!("down ({})", truncated_message)
+                    }
+                };
+
+                let mut details = BTreeMap::new();
+                details.insert("checked_at".to_string(), chrono::Utc::now().to_rfc3339());
+                details.insert("endpoint".to_string(), "/store/inventory".to_string());
+
+                DependencyStatus {
+                    name,
+                    status,
+                    details: Some(details),
+                }
+            })
+        },
+    )
 }
@@ -20,8 +20,12 @@ pub mod app {
 
 /// Caching functionality
 pub mod cache {
+    pub use crate::cache::invalidation::{
+        InvalidationBus, InvalidationMessage, InvalidationTransport,
+    };
     pub use crate::cache::providers::*;
     pub use crate::cache::registry_stats::*;
+    pub mod invalidation;
     pub mod providers;
     pub mod registry_stats;
 }
@@ -32,7 +32,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
 async fn run_app() -> Result<(), AppError> {
     // Initialize the application
-    let (app, addr) = app::init().await;
+    let (app, state, addr) = app::init_with_state().await?;
 
     // Load configuration
     let config = config::app_config::load_config()?;
@@ -53,10 +53,47 @@ async fn run_app() -> Result<(), AppError> {
     // Bind the TCP listener
     let listener = tokio::net::TcpListener::bind(addr).await?;
 
-    // Run the server with our app
+    // Run the server with our app until a shutdown signal arrives
     axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
         .await
         .map_err(|e| AppError::InternalError(format!("Server error: {}", e)))?;
 
+    // Persist caches and release resources
+    app::shutdown(&state).await;
+
     Ok(())
 }
+
+/// Resolve on Ctrl+C, or SIGTERM on Unix (as sent by orchestrators on rollout)
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    info!("Shutdown signal received, stopping server");
+}
@@ -13,7 +13,7 @@ pub struct HealthCheckResponse {
 }
 
 /// Dependency status for detailed health checks
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DependencyStatus {
     pub name: String,
     pub status: String,
@@ -21,6 +21,62 @@ pub struct DependencyStatus {
     pub details: Option<BTreeMap<String, String>>,
 }
 
+impl DependencyStatus {
+    /// The health this status reports, or `None` for informational statuses like "disabled"
+    ///
+    /// Checks report "up", "degraded (...)" or "down (...)".
+    pub fn health(&self) -> Option<HealthStatus> {
+        if self.status.starts_with("up") {
+            Some(HealthStatus::Up)
+        } else if self.status.starts_with("degraded") {
+            Some(HealthStatus::Degraded)
+        } else if self.status.starts_with("down") {
+            Some(HealthStatus::Down)
+        } else {
+            None
+        }
+    }
+}
+
+/// Overall verdict of a health check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HealthStatus {
+    Up,
+    Degraded,
+    Down,
+}
+
+impl HealthStatus {
+    /// Combine dependency statuses into a verdict for the service
+    ///
+    /// A `critical` dependency that is down takes the whole service down; any
+    /// other failing dependency only degrades it.
+    pub fn aggregate(dependencies: &[DependencyStatus], critical: &[&str]) -> Self {
+        dependencies
+            .iter()
+            .filter_map(|dependency| match dependency.health()? {
+                HealthStatus::Up => None,
+                HealthStatus::Down if critical.contains(&dependency.name.as_str()) => {
+                    Some(HealthStatus::Down)
+                }
+                _ => Some(HealthStatus::Degraded),
+            })
+            .max_by_key(|status| *status == HealthStatus::Down)
+            .unwrap_or(HealthStatus::Up)
+    }
+}
+
+impl std::fmt::Display for HealthStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HealthStatus::Up => write!(f, "UP"),
+            HealthStatus::Degraded => write!(f, "DEGRADED"),
+            HealthStatus::Down => write!(f, "DOWN"),
+        }
+    }
+}
+
 /// Detailed health check response structure
 ///
 /// For administrators and detailed monitoring
@@ -54,3 +110,62 @@ pub struct ApiResponse {
     pub r#type: Option<String>,
     pub message: Option<String>,
 }
+
+/// Settings and statistics for one registered resource cache
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheSummary {
+    pub resource_type: String,
+    pub ttl_seconds: u64,
+    pub tti_seconds: u64,
+    pub max_capacity: u64,
+    pub stale_while_revalidate_seconds: u64,
+    pub stale_if_error_seconds: u64,
+    pub not_found_ttl_seconds: u64,
+    /// Byte budget when the cache is sized by weight (0 = sized by entry count)
+    pub max_bytes: u64,
+    /// Approximate bytes held, for caches sized by weight
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes_used: Option<u64>,
+    pub warmable: bool,
+    pub stats: Option<crate::core::cache::CacheStats>,
+}
+
+/// Response for listing registered resource caches
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheListResponse {
+    pub enabled: bool,
+    pub provider: String,
+    pub caches: Vec<CacheSummary>,
+}
+
+/// A single cached entry
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheEntryResponse {
+    pub resource_type: String,
+    pub key: String,
+    pub value: serde_json::Value,
+}
+
+/// Result of evicting entries from a resource cache
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheEvictionResponse {
+    pub resource_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub evicted: u64,
+}
+
+/// Request to pre-warm a resource cache
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheWarmRequest {
+    pub ids: Vec<String>,
+}
+
+/// Result of pre-warming a resource cache
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheWarmResponse {
+    pub resource_type: String,
+    pub loaded: usize,
+    pub failed: usize,
+    pub results: Vec<crate::core::cache::WarmResult>,
+}
@@ -0,0 +1,279 @@
+//! Caching decorator for repositories
+//!
+//! [`CachedRepository`] wraps any [`Repository`] and keeps `find_by_id` results in
+//! the shared cache registry, so repeated lookups skip the database. Saved
+//! entities reach the database immediately (write-through) or are batched and
+//! flushed in the background (write-back).
+
+use async_trait::async_trait;
+use metrics::counter;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::Repository;
+use crate::config::WriteMode;
+use crate::core::cache::{CacheRegistry, get_cached, get_resource_cache, register_resource_cache};
+use crate::core::error::AppError;
+use crate::utils::api_resource::ApiResource;
+
+/// An entity that can be cached by [`CachedRepository`]
+///
+/// Entries are cached under [`ApiResource::resource_type`] and keyed by the
+/// string form of the entity's ID.
+pub trait CacheableEntity: ApiResource {
+    /// The ID the entity is stored under
+    fn entity_id(&self) -> Self::Id;
+}
+
+/// Repository decorator that caches lookups and optionally defers writes
+///
+/// Reads check pending writes first, then the cache, then the wrapped
+/// repository. In [`WriteMode::WriteBack`] mode `save` returns as soon as the
+/// entity is cached; writes to the same ID are coalesced until the next
+/// [`CachedRepository::flush`], which must also run on shutdown or the
+/// pending writes are lost.
+pub struct CachedRepository<R, T: CacheableEntity> {
+    inner: Arc<R>,
+    registry: CacheRegistry,
+    write_mode: WriteMode,
+    // Entities saved but not yet written, by cache key
+    pending: Mutex<HashMap<String, T>>,
+    // Held while writing to the database, so a delete cannot race a flush
+    write_lock: tokio::sync::Mutex<()>,
+}
+
+impl<R, T> CachedRepository<R, T>
+where
+    R: Repository<T, T::Id> + 'static,
+    T: CacheableEntity,
+{
+    /// Cache lookups of `inner` in `registry`
+    ///
+    /// The entity's resource type is registered in the registry if needed,
+    /// picking up any `cache.resources.<type>` settings.
+    pub fn new(inner: Arc<R>, registry: CacheRegistry, write_mode: WriteMode) -> Self {
+        if !registry.is_registered(T::resource_type())
+            && let Err(e) = register_resource_cache::<T>(&registry, T::resource_type())
+        {
+            warn!(
+                "⚠️ Failed to register {} repository cache: {}",
+                T::resource_type(),
+                e
+            );
+        }
+
+        Self {
+            inner,
+            registry,
+            write_mode,
+            pending: Mutex::new(HashMap::new()),
+            write_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// The wrapped repository, for queries the cache does not cover
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// How saved entities are written
+    pub fn write_mode(&self) -> WriteMode {
+        self.write_mode
+    }
+
+    /// Number of saved entities not yet written to the database
+    pub fn pending_writes(&self) -> usize {
+        self.pending
+            .lock()
+            .map(|pending| pending.len())
+            .unwrap_or(0)
+    }
+
+    /// Write every pending entity to the database
+    ///
+    /// Returns how many were written. Entities that fail to save are queued
+    /// again (unless saved again meanwhile) and the first error is returned.
+    pub async fn flush(&self) -> Result<usize, AppError> {
+        let _guard = self.write_lock.lock().await;
+        let batch: Vec<(String, T)> = match self.pending.lock() {
+            Ok(mut pending) => pending.drain().collect(),
+            Err(_) => return Err(lock_error()),
+        };
+        if batch.is_empty() {
+            return Ok(0);
+        }
+
+        let mut written = 0;
+        let mut first_error = None;
+        for (key, entity) in batch {
+            match self.inner.save(entity.clone()).await {
+                Ok(_) => {
+                    written += 1;
+                    record_write_back(T::resource_type(), "ok");
+                }
+                Err(e) => {
+                    record_write_back(T::resource_type(), "error");
+                    warn!(
+                        "⚠️ Failed to write back {} {}: {}",
+                        T::resource_type(),
+                        key,
+                        e
+                    );
+                    if let Ok(mut pending) = self.pending.lock() {
+                        pending.entry(key).or_insert(entity);
+                    }
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        debug!("💾 Wrote back {} {} entities", written, T::resource_type());
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(written),
+        }
+    }
+
+    /// Flush pending writes every `interval` in the background
+    ///
+    /// Returns `None` in write-through mode, where there is nothing to flush.
+    pub fn start_write_back(self: &Arc<Self>, interval: Duration) -> Option<JoinHandle<()>> {
+        if self.write_mode != WriteMode::WriteBack {
+            return None;
+        }
+
+        let repository = self.clone();
+        info!(
+            "💾 Writing back {} entities every {:?}",
+            T::resource_type(),
+            interval
+        );
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                // Failures are logged by `flush` and retried on the next tick
+                let _ = repository.flush().await;
+            }
+        }))
+    }
+
+    fn pending_entity(&self, key: &str) -> Option<T> {
+        self.pending.lock().ok()?.get(key).cloned()
+    }
+
+    async fn cache_entity(&self, key: String, entity: T) {
+        if let Some(cache) = get_resource_cache::<T>(&self.registry, T::resource_type()) {
+            cache.insert(key, entity).await;
+        }
+    }
+}
+
+#[async_trait]
+impl<R, T> Repository<T, T::Id> for CachedRepository<R, T>
+where
+    R: Repository<T, T::Id> + 'static,
+    T: CacheableEntity,
+{
+    async fn find_by_id(&self, id: T::Id) -> Result<Option<T>, AppError> {
+        let key = id.to_string();
+        if let Some(entity) = self.pending_entity(&key) {
+            return Ok(Some(entity));
+        }
+        if let Some(entity) = get_cached::<T>(&self.registry, T::resource_type(), &key).await {
+            return Ok(Some(entity));
+        }
+
+        counter!("cache_misses_total", "resource_type" => T::resource_type()).increment(1);
+        let entity = self.inner.find_by_id(id).await?;
+        if let Some(entity) = &entity {
+            self.cache_entity(key, entity.clone()).await;
+        }
+        Ok(entity)
+    }
+
+    async fn save(&self, entity: T) -> Result<T, AppError> {
+        let key = entity.entity_id().to_string();
+        match self.write_mode {
+            WriteMode::WriteThrough => {
+                let saved = self.inner.save(entity).await?;
+                self.cache_entity(key, saved.clone()).await;
+                Ok(saved)
+            }
+            WriteMode::WriteBack => {
+                self.pending
+                    .lock()
+                    .map_err(|_| lock_error())?
+                    .insert(key.clone(), entity.clone());
+                self.cache_entity(key, entity.clone()).await;
+                Ok(entity)
+            }
+        }
+    }
+
+    /// Conditional updates always reach the database, after any pending write
+    /// of the same entity so the check sees the copy callers were served
+    async fn update_if_unchanged(&self, entity: T, expected: &T) -> Result<Option<T>, AppError> {
+        let key = entity.entity_id().to_string();
+        let _guard = self.write_lock.lock().await;
+        let pending = self.pending.lock().map_err(|_| lock_error())?.remove(&key);
+        if let Some(pending) = pending
+            && let Err(e) = self.inner.save(pending.clone()).await
+        {
+            if let Ok(mut queued) = self.pending.lock() {
+                queued.entry(key).or_insert(pending);
+            }
+            return Err(e);
+        }
+
+        let updated = self.inner.update_if_unchanged(entity, expected).await?;
+        match &updated {
+            Some(saved) => self.cache_entity(key, saved.clone()).await,
+            None => {
+                self.registry.invalidate_key(T::resource_type(), &key).await;
+            }
+        }
+        Ok(updated)
+    }
+
+    async fn delete(&self, id: T::Id) -> Result<bool, AppError> {
+        let key = id.to_string();
+        let _guard = self.write_lock.lock().await;
+        let was_pending = self
+            .pending
+            .lock()
+            .map_err(|_| lock_error())?
+            .remove(&key)
+            .is_some();
+        self.registry.invalidate_key(T::resource_type(), &key).await;
+
+        let deleted = self.inner.delete(id).await?;
+        Ok(deleted || was_pending)
+    }
+
+    async fn find_all(&self) -> Result<Vec<T>, AppError> {
+        let mut entities = self.inner.find_all().await?;
+
+        // Overlay writes that have not reached the database yet
+        let mut pending = self.pending.lock().map_err(|_| lock_error())?.clone();
+        for entity in entities.iter_mut() {
+            if let Some(newer) = pending.remove(&entity.entity_id().to_string()) {
+                *entity = newer;
+            }
+        }
+        entities.extend(pending.into_values());
+        Ok(entities)
+    }
+}
+
+fn lock_error() -> AppError {
+    AppError::InternalError("Repository write-back queue lock poisoned".to_string())
+}
+
+fn record_write_back(resource_type: &'static str, status: &'static str) {
+    counter!("repository_write_back_total", "resource_type" => resource_type, "status" => status)
+        .increment(1);
+}
@@ -3,12 +3,14 @@
 //! This module provides interfaces and implementations for interacting with the database.
 //! It follows the repository pattern where each entity type has its own repository.
 
+pub mod cached;
 pub mod models;
 pub mod user;
 
 #[cfg(test)]
 mod tests;
 
+pub use cached::{CacheableEntity, CachedRepository};
 pub use models::User;
 pub use user::UserRepository;
 
@@ -31,6 +33,13 @@ where
     /// Save an entity (create or update)
     async fn save(&self, entity: T) -> Result<T, AppError>;
 
+    /// Save changes to an entity only if the stored copy is still `expected`
+    ///
+    /// The check and the write happen atomically, so two updates based on the
+    /// same copy cannot both succeed. Returns `None` if the stored copy has
+    /// changed or no longer exists.
+    async fn update_if_unchanged(&self, entity: T, expected: &T) -> Result<Option<T>, AppError>;
+
     /// Delete an entity by its ID
     async fn delete(&self, id: ID) -> Result<bool, AppError>;
 
@@ -7,6 +7,9 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::CacheableEntity;
+use crate::utils::api_resource::ApiResource;
+
 /// User entity
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
@@ -34,6 +37,10 @@ pub struct User {
 
     /// When the user was last updated
     pub updated_at: DateTime<Utc>,
+
+    /// Subject (`sub` claim) of the caller who created the user, who owns it
+    #[serde(default)]
+    pub subject: Option<String>,
 }
 
 impl User {
@@ -48,6 +55,7 @@ impl User {
             role,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
+            subject: None,
         }
     }
 
@@ -57,6 +65,25 @@ impl User {
     }
 }
 
+/// Users are cached as the `user` resource type, keyed by their ID
+impl ApiResource for User {
+    type Id = Uuid;
+
+    fn resource_type() -> &'static str {
+        "user"
+    }
+
+    fn api_name() -> &'static str {
+        "Database"
+    }
+}
+
+impl CacheableEntity for User {
+    fn entity_id(&self) -> Uuid {
+        self.id
+    }
+}
+
 /// User role
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum UserRole {
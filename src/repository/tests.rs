@@ -2,12 +2,18 @@
 //!
 //! This module provides tests for repository implementations.
 
+use async_trait::async_trait;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 use uuid::Uuid;
 
+use crate::config::WriteMode;
+use crate::core::cache::{CacheRegistry, init_cache_registry};
 use crate::core::config::app_config::DatabaseConfig;
 use crate::core::database::connection::MockDatabaseConnection;
-use crate::repository::{Repository, User, UserRepository, models::UserRole};
+use crate::core::error::AppError;
+use crate::repository::{CachedRepository, Repository, User, UserRepository, models::UserRole};
 
 /// Create a test database pool
 fn create_test_db_pool() -> Arc<Box<dyn crate::core::database::PgPool>> {
@@ -218,3 +224,182 @@ async fn test_user_touch() {
         "created_at should not change after touch"
     );
 }
+
+/// Counts the lookups that reach the database
+struct CountingRepository {
+    inner: UserRepository,
+    finds: AtomicUsize,
+}
+
+#[async_trait]
+impl Repository<User, Uuid> for CountingRepository {
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<User>, AppError> {
+        self.finds.fetch_add(1, Ordering::SeqCst);
+        self.inner.find_by_id(id).await
+    }
+
+    async fn save(&self, entity: User) -> Result<User, AppError> {
+        self.inner.save(entity).await
+    }
+
+    async fn update_if_unchanged(
+        &self,
+        entity: User,
+        expected: &User,
+    ) -> Result<Option<User>, AppError> {
+        self.inner.update_if_unchanged(entity, expected).await
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool, AppError> {
+        self.inner.delete(id).await
+    }
+
+    async fn find_all(&self) -> Result<Vec<User>, AppError> {
+        self.inner.find_all().await
+    }
+}
+
+fn cached_user_repository(
+    write_mode: WriteMode,
+) -> (
+    Arc<CountingRepository>,
+    CacheRegistry,
+    CachedRepository<CountingRepository, User>,
+) {
+    let database = Arc::new(CountingRepository {
+        inner: UserRepository::new(create_test_db_pool()),
+        finds: AtomicUsize::new(0),
+    });
+    let registry = init_cache_registry(true, 100, 60);
+    let cached = CachedRepository::new(database.clone(), registry.clone(), write_mode);
+    (database, registry, cached)
+}
+
+fn test_user(name: &str) -> User {
+    User::new(
+        name.to_string(),
+        format!("{}@example.com", name),
+        None,
+        UserRole::User,
+    )
+}
+
+#[tokio::test]
+async fn test_cached_repository_write_through() {
+    let (database, registry, repo) = cached_user_repository(WriteMode::WriteThrough);
+    let user = test_user("cached");
+
+    repo.save(user.clone()).await.unwrap();
+    assert!(database.inner.find_by_id(user.id).await.unwrap().is_some());
+
+    // The saved user is served from the cache
+    assert_eq!(repo.find_by_id(user.id).await.unwrap().unwrap().id, user.id);
+    assert_eq!(database.finds.load(Ordering::SeqCst), 0);
+
+    // Once evicted, the next lookup reaches the database and is cached again
+    registry.invalidate_key("user", &user.id.to_string()).await;
+    repo.find_by_id(user.id).await.unwrap();
+    repo.find_by_id(user.id).await.unwrap();
+    assert_eq!(database.finds.load(Ordering::SeqCst), 1);
+
+    assert!(repo.delete(user.id).await.unwrap());
+    assert!(repo.find_by_id(user.id).await.unwrap().is_none());
+    assert_eq!(repo.pending_writes(), 0);
+}
+
+#[tokio::test]
+async fn test_cached_repository_write_back_defers_writes_until_flush() {
+    let (database, registry, repo) = cached_user_repository(WriteMode::WriteBack);
+    let mut user = test_user("deferred");
+
+    repo.save(user.clone()).await.unwrap();
+    user.full_name = Some("Deferred User".to_string());
+    repo.save(user.clone()).await.unwrap();
+
+    // Nothing has reached the database, but reads see the pending write
+    assert!(database.inner.find_by_id(user.id).await.unwrap().is_none());
+    assert_eq!(repo.pending_writes(), 1);
+    registry.invalidate_key("user", &user.id.to_string()).await;
+    let found = repo.find_by_id(user.id).await.unwrap().unwrap();
+    assert_eq!(found.full_name, user.full_name);
+    assert_eq!(repo.find_all().await.unwrap().len(), 1);
+
+    // Both saves were coalesced into one write
+    assert_eq!(repo.flush().await.unwrap(), 1);
+    assert_eq!(repo.pending_writes(), 0);
+    let stored = database.inner.find_by_id(user.id).await.unwrap().unwrap();
+    assert_eq!(stored.full_name, user.full_name);
+}
+
+#[tokio::test]
+async fn test_cached_repository_conditional_update_writes_pending_copy_first() {
+    let (database, _, repo) = cached_user_repository(WriteMode::WriteBack);
+    let user = test_user("conditional");
+    repo.save(user.clone()).await.unwrap();
+
+    let mut renamed = user.clone();
+    renamed.full_name = Some("Renamed".to_string());
+    renamed.touch();
+    let saved = repo
+        .update_if_unchanged(renamed.clone(), &user)
+        .await
+        .unwrap();
+    assert_eq!(saved.unwrap().full_name, renamed.full_name);
+    assert_eq!(repo.pending_writes(), 0);
+
+    // A second update based on the same copy is rejected
+    let mut stale = user.clone();
+    stale.is_active = false;
+    stale.touch();
+    assert!(
+        repo.update_if_unchanged(stale, &user)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    let stored = database.inner.find_by_id(user.id).await.unwrap().unwrap();
+    assert_eq!(stored.full_name, renamed.full_name);
+    assert!(stored.is_active);
+}
+
+#[tokio::test]
+async fn test_cached_repository_delete_drops_pending_write() {
+    let (database, _, repo) = cached_user_repository(WriteMode::WriteBack);
+    let user = test_user("short-lived");
+
+    repo.save(user.clone()).await.unwrap();
+    assert!(repo.delete(user.id).await.unwrap());
+
+    assert_eq!(repo.flush().await.unwrap(), 0);
+    assert!(repo.find_by_id(user.id).await.unwrap().is_none());
+    assert!(database.inner.find_by_id(user.id).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_cached_repository_flushes_in_background() {
+    let (database, _, repo) = cached_user_repository(WriteMode::WriteBack);
+    let repo = Arc::new(repo);
+    let user = test_user("background");
+
+    let task = repo.start_write_back(Duration::from_millis(10)).unwrap();
+    repo.save(user.clone()).await.unwrap();
+
+    let mut stored = false;
+    for _ in 0..100 {
+        if database.inner.find_by_id(user.id).await.unwrap().is_some() {
+            stored = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    task.abort();
+    assert!(stored);
+
+    // Write-through repositories have nothing to flush
+    let (_, _, write_through) = cached_user_repository(WriteMode::WriteThrough);
+    assert!(
+        Arc::new(write_through)
+            .start_write_back(Duration::from_millis(10))
+            .is_none()
+    );
+}
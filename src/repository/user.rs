@@ -96,7 +96,7 @@ impl UserRepository {
         sqlx::query_as::<_, User>(
             r#"
             SELECT id, username, email, full_name, is_active,
-                   role, created_at, updated_at
+                   role, created_at, updated_at, subject
             FROM users
             WHERE username = $1
             "#,
@@ -143,7 +143,7 @@ impl UserRepository {
         sqlx::query_as::<_, User>(
             r#"
             SELECT id, username, email, full_name, is_active,
-                   role, created_at, updated_at
+                   role, created_at, updated_at, subject
             FROM users
             WHERE email = $1
             "#,
@@ -205,6 +205,7 @@ impl FromRow<'_, PgRow> for User {
         let role_str: String = row.try_get("role")?;
         let created_at: DateTime<Utc> = row.try_get("created_at")?;
         let updated_at: DateTime<Utc> = row.try_get("updated_at")?;
+        let subject: Option<String> = row.try_get("subject")?;
 
         // Convert role string to enum
         let role = match role_str.as_str() {
@@ -222,6 +223,7 @@ impl FromRow<'_, PgRow> for User {
             role,
             created_at,
             updated_at,
+            subject,
         })
     }
 }
@@ -234,7 +236,7 @@ impl Repository<User, Uuid> for UserRepository {
             return sqlx::query_as::<_, User>(
                 r#"
                 SELECT id, username, email, full_name, is_active,
-                       role, created_at, updated_at
+                       role, created_at, updated_at, subject
                 FROM users
                 WHERE id = $1
                 "#,
@@ -270,12 +272,12 @@ impl Repository<User, Uuid> for UserRepository {
 
             return sqlx::query_as::<_, User>(
                 r#"
-                INSERT INTO users (id, username, email, full_name, is_active, role, created_at, updated_at)
-                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                INSERT INTO users (id, username, email, full_name, is_active, role, created_at, updated_at, subject)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
                 ON CONFLICT (id) DO UPDATE
                 SET email = $3, full_name = $4, is_active = $5, role = $6, updated_at = $8
                 RETURNING id, username, email, full_name, is_active,
-                         role, created_at, updated_at
+                         role, created_at, updated_at, subject
                 "#
             )
             .bind(entity.id)
@@ -286,6 +288,7 @@ impl Repository<User, Uuid> for UserRepository {
             .bind(&role_str)
             .bind(entity.created_at)
             .bind(entity.updated_at)
+            .bind(&entity.subject)
             .fetch_one(pool)
             .await
             .map_err(|e| AppError::DatabaseError(format!("Failed to save user: {}", e)));
@@ -311,6 +314,57 @@ impl Repository<User, Uuid> for UserRepository {
         Ok(entity)
     }
 
+    /// Users are versioned by `updated_at`, which every update advances
+    async fn update_if_unchanged(
+        &self,
+        entity: User,
+        expected: &User,
+    ) -> Result<Option<User>, AppError> {
+        // Try to use the real database if available
+        if let Some(pool) = self.get_sqlx_pool() {
+            let role_str = entity.role.to_string();
+
+            return sqlx::query_as::<_, User>(
+                r#"
+                UPDATE users
+                SET email = $3, full_name = $4, is_active = $5, role = $6, updated_at = $7
+                WHERE id = $1 AND updated_at = $2
+                RETURNING id, username, email, full_name, is_active,
+                          role, created_at, updated_at, subject
+                "#,
+            )
+            .bind(entity.id)
+            .bind(expected.updated_at)
+            .bind(&entity.email)
+            .bind(&entity.full_name)
+            .bind(entity.is_active)
+            .bind(&role_str)
+            .bind(entity.updated_at)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to update user: {}", e)));
+        }
+
+        // For tests, use the mock connection
+        #[cfg(test)]
+        if let Some(conn) = self.get_mock_connection() {
+            return Ok(conn.update_user_if_unmodified(entity, expected.updated_at));
+        }
+
+        // Fallback to transaction-based implementation
+        let db_pool = self.base.db_pool();
+        let _tx = db_pool.begin().await?;
+
+        // In a real implementation, we would execute a SQL query
+        tracing::debug!(
+            "update_if_unchanged: Using non-SQLx implementation for user: {}",
+            entity.username
+        );
+
+        // Just return the entity as-is for compatibility
+        Ok(Some(entity))
+    }
+
     async fn delete(&self, id: Uuid) -> Result<bool, AppError> {
         // Try to use the real database if available
         if let Some(pool) = self.get_sqlx_pool() {
@@ -346,7 +400,7 @@ impl Repository<User, Uuid> for UserRepository {
             return sqlx::query_as::<_, User>(
                 r#"
                 SELECT id, username, email, full_name, is_active,
-                       role, created_at, updated_at
+                       role, created_at, updated_at, subject
                 FROM users
                 ORDER BY username
                 "#,
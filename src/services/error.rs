@@ -19,6 +19,9 @@ pub enum ServiceError {
     /// Email already exists
     EmailExists,
 
+    /// User was changed since the copy an update was based on
+    UserModified,
+
     /// Other generic error
     Other(String),
 }
@@ -31,6 +34,7 @@ impl fmt::Display for ServiceError {
             ServiceError::UserNotFound => write!(f, "User not found"),
             ServiceError::UsernameExists => write!(f, "Username already exists"),
             ServiceError::EmailExists => write!(f, "Email already exists"),
+            ServiceError::UserModified => write!(f, "User has been modified"),
             ServiceError::Other(msg) => write!(f, "Other error: {}", msg),
         }
     }
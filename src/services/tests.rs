@@ -5,10 +5,11 @@
 use std::sync::Arc;
 use uuid::Uuid;
 
+use crate::cache::InvalidationBus;
 use crate::core::config::app_config::DatabaseConfig;
 use crate::core::database::connection::MockDatabaseConnection;
 use crate::repository::{User, UserRepository, models::UserRole};
-use crate::services::user::{CreateUserDto, UpdateUserDto};
+use crate::services::user::{CreateUserDto, USER_RESOURCE_TYPE, UpdateUserDto};
 use crate::services::{ServiceError, UserService};
 
 /// Create a test user repository
@@ -34,6 +35,7 @@ async fn test_create_user() {
         email: "test@example.com".to_string(),
         full_name: Some("Test User".to_string()),
         role: None,
+        subject: None,
     };
 
     // Create user
@@ -63,6 +65,7 @@ async fn test_create_user_validation() {
         email: "test@example.com".to_string(),
         full_name: None,
         role: None,
+        subject: None,
     };
 
     let result = service.create_user(create_dto).await;
@@ -80,6 +83,7 @@ async fn test_create_user_validation() {
         email: "invalid-email".to_string(), // Missing @ and domain
         full_name: None,
         role: None,
+        subject: None,
     };
 
     let result = service.create_user(create_dto).await;
@@ -104,6 +108,7 @@ async fn test_duplicate_username() {
         email: "test1@example.com".to_string(),
         full_name: None,
         role: None,
+        subject: None,
     };
 
     service.create_user(create_dto).await.unwrap();
@@ -114,6 +119,7 @@ async fn test_duplicate_username() {
         email: "test2@example.com".to_string(), // Different email
         full_name: None,
         role: None,
+        subject: None,
     };
 
     let result = service.create_user(create_dto).await;
@@ -136,6 +142,7 @@ async fn test_duplicate_email() {
         email: "same@example.com".to_string(),
         full_name: None,
         role: None,
+        subject: None,
     };
 
     service.create_user(create_dto).await.unwrap();
@@ -146,6 +153,7 @@ async fn test_duplicate_email() {
         email: "same@example.com".to_string(), // Same email
         full_name: None,
         role: None,
+        subject: None,
     };
 
     let result = service.create_user(create_dto).await;
@@ -168,6 +176,7 @@ async fn test_get_user() {
         email: "get@example.com".to_string(),
         full_name: Some("Get User".to_string()),
         role: Some(UserRole::Admin),
+        subject: None,
     };
 
     let user = service.create_user(create_dto).await.unwrap();
@@ -205,6 +214,7 @@ async fn test_update_user() {
         email: "update@example.com".to_string(),
         full_name: Some("Original Name".to_string()),
         role: Some(UserRole::User),
+        subject: None,
     };
 
     let user = service.create_user(create_dto).await.unwrap();
@@ -236,6 +246,44 @@ async fn test_update_user() {
     assert_eq!(found.is_active, false);
 }
 
+#[tokio::test]
+async fn test_concurrent_conditional_updates() {
+    let repo = create_test_user_repository();
+    let service = UserService::new(repo);
+
+    let create_dto = CreateUserDto {
+        username: "raceuser".to_string(),
+        email: "race@example.com".to_string(),
+        full_name: None,
+        role: None,
+        subject: None,
+    };
+    let original = service.create_user(create_dto).await.unwrap();
+
+    let rename = |name: &str| UpdateUserDto {
+        email: None,
+        full_name: Some(name.to_string()),
+        is_active: None,
+        role: None,
+    };
+
+    // Both updates were based on the same copy; only the first one applies
+    let first = service
+        .update_user_if_unmodified(rename("First"), &original)
+        .await
+        .unwrap();
+    assert_eq!(first.full_name, Some("First".to_string()));
+    assert!(matches!(
+        service
+            .update_user_if_unmodified(rename("Second"), &original)
+            .await,
+        Err(ServiceError::UserModified)
+    ));
+
+    let found = service.get_user_by_id(original.id).await.unwrap();
+    assert_eq!(found.full_name, Some("First".to_string()));
+}
+
 #[tokio::test]
 async fn test_delete_user() {
     // Create dependencies
@@ -248,6 +296,7 @@ async fn test_delete_user() {
         email: "delete@example.com".to_string(),
         full_name: None,
         role: None,
+        subject: None,
     };
 
     let user = service.create_user(create_dto).await.unwrap();
@@ -269,6 +318,47 @@ async fn test_delete_user() {
     assert!(result.is_err());
 }
 
+#[tokio::test]
+async fn test_update_and_delete_broadcast_invalidations() {
+    use crate::cache::InvalidationMessage;
+    use crate::cache::invalidation::{InvalidationTransport, LocalInvalidationTransport};
+    use futures::StreamExt;
+
+    let transport = Arc::new(LocalInvalidationTransport::new());
+    let mut messages = transport.subscribe().await.unwrap();
+    let bus = Arc::new(InvalidationBus::new(transport, None));
+
+    let repo = create_test_user_repository();
+    let service = UserService::new(repo).with_invalidation(bus.clone());
+
+    let create_dto = CreateUserDto {
+        username: "cacheduser".to_string(),
+        email: "cached@example.com".to_string(),
+        full_name: None,
+        role: None,
+        subject: None,
+    };
+    let user = service.create_user(create_dto).await.unwrap();
+
+    let update_dto = UpdateUserDto {
+        email: None,
+        full_name: Some("Renamed".to_string()),
+        is_active: None,
+        role: None,
+    };
+    service.update_user(user.id, update_dto).await.unwrap();
+    service.delete_user(user.id).await.unwrap();
+
+    // Creating a user does not invalidate anything; update and delete do
+    for _ in 0..2 {
+        let payload = messages.next().await.unwrap().unwrap();
+        let message: InvalidationMessage = serde_json::from_str(&payload).unwrap();
+        assert_eq!(message.resource_type, USER_RESOURCE_TYPE);
+        assert_eq!(message.key, user.id.to_string());
+        assert_eq!(message.origin, bus.origin());
+    }
+}
+
 #[tokio::test]
 async fn test_get_all_users() {
     // Create dependencies
@@ -286,6 +376,7 @@ async fn test_get_all_users() {
             email: format!("user{}@example.com", i),
             full_name: None,
             role: None,
+            subject: None,
         };
 
         service.create_user(create_dto).await.unwrap();
@@ -4,9 +4,11 @@
 
 use async_trait::async_trait;
 use std::sync::Arc;
+use tracing::warn;
 use uuid::Uuid;
 
 use crate::{
+    cache::InvalidationBus,
     core::error::AppError,
     repository::{Repository, User, models::UserRole},
     services::error::{ServiceError, ServiceResult},
@@ -26,6 +28,9 @@ pub struct CreateUserDto {
 
     /// Role (defaults to User if not specified)
     pub role: Option<UserRole>,
+
+    /// Subject of the caller creating the user, who will own it
+    pub subject: Option<String>,
 }
 
 /// User update data transfer object
@@ -52,14 +57,23 @@ pub trait IUserService: Send + Sync {
     async fn find_by_email(&self, email: &str) -> Result<User, ServiceError>;
     async fn create_user(&self, user: CreateUserDto) -> Result<User, ServiceError>;
     async fn update_user(&self, id: Uuid, user: UpdateUserDto) -> Result<User, ServiceError>;
+    async fn update_user_if_unmodified(
+        &self,
+        user: UpdateUserDto,
+        expected: &User,
+    ) -> Result<User, ServiceError>;
     async fn delete_user(&self, id: Uuid) -> Result<(), ServiceError>;
 }
 
+/// Resource type under which users are cached and invalidated
+pub const USER_RESOURCE_TYPE: &str = "user";
+
 pub struct UserService<R>
 where
     R: Repository<User, Uuid> + Send + Sync + 'static,
 {
     repository: Arc<R>,
+    invalidation: Option<Arc<InvalidationBus>>,
 }
 
 impl<R> UserService<R>
@@ -68,7 +82,16 @@ where
 {
     /// Create a new user service
     pub fn new(repository: Arc<R>) -> Self {
-        Self { repository }
+        Self {
+            repository,
+            invalidation: None,
+        }
+    }
+
+    /// Evict updated and deleted users from every replica's cache through `bus`
+    pub fn with_invalidation(mut self, bus: Arc<InvalidationBus>) -> Self {
+        self.invalidation = Some(bus);
+        self
     }
 
     /// Get all users
@@ -146,15 +169,19 @@ where
             return Err(ServiceError::EmailExists);
         }
 
-        // Create new user
-        let user = User::new(
+        // Create new user, owned by its creator
+        let mut new_user = User::new(
             user.username,
             user.email,
             user.full_name,
             user.role.unwrap_or(UserRole::User),
         );
+        new_user.subject = user.subject;
 
-        self.repository.save(user).await.map_err(ServiceError::from)
+        self.repository
+            .save(new_user)
+            .await
+            .map_err(ServiceError::from)
     }
 
     /// Update an existing user
@@ -191,10 +218,59 @@ where
 
         existing.updated_at = chrono::Utc::now();
 
-        self.repository
+        let updated = self
+            .repository
             .save(existing)
             .await
-            .map_err(ServiceError::from)
+            .map_err(ServiceError::from)?;
+        self.invalidate_cached_user(id).await;
+        Ok(updated)
+    }
+
+    /// Update `expected`, failing with `UserModified` if the stored user has
+    /// changed since it was read
+    pub async fn update_user_if_unmodified(
+        &self,
+        user: UpdateUserDto,
+        expected: &User,
+    ) -> ServiceResult<User> {
+        let id = expected.id;
+        let mut updated = expected.clone();
+
+        if let Some(email) = &user.email {
+            let users = self
+                .repository
+                .find_all()
+                .await
+                .map_err(ServiceError::from)?;
+            if users.iter().any(|u| u.email == *email && u.id != id) {
+                return Err(ServiceError::EmailExists);
+            }
+            updated.email = email.clone();
+        }
+
+        if let Some(full_name) = user.full_name {
+            updated.full_name = Some(full_name);
+        }
+
+        if let Some(is_active) = user.is_active {
+            updated.is_active = is_active;
+        }
+
+        if let Some(role) = user.role {
+            updated.role = role;
+        }
+
+        updated.touch();
+
+        let updated = self
+            .repository
+            .update_if_unchanged(updated, expected)
+            .await
+            .map_err(ServiceError::from)?
+            .ok_or(ServiceError::UserModified)?;
+        self.invalidate_cached_user(id).await;
+        Ok(updated)
     }
 
     /// Delete a user by ID
@@ -205,6 +281,7 @@ where
                     .delete(id)
                     .await
                     .map_err(ServiceError::from)?;
+                self.invalidate_cached_user(id).await;
                 Ok(())
             }
             Ok(None) => Err(ServiceError::UserNotFound),
@@ -214,6 +291,20 @@ where
 
     // Helper methods
 
+    /// Evict the user from the caches of every replica
+    ///
+    /// The write has already succeeded, so a failed broadcast is only logged.
+    async fn invalidate_cached_user(&self, id: Uuid) {
+        if let Some(bus) = &self.invalidation
+            && let Err(e) = bus.publish(USER_RESOURCE_TYPE, &id.to_string()).await
+        {
+            warn!(
+                "⚠️ Failed to broadcast cache invalidation for user {}: {}",
+                id, e
+            );
+        }
+    }
+
     /// Validate username format
     fn validate_username(&self, username: &str) -> ServiceResult<()> {
         // Username must be at least 3 characters
@@ -309,15 +400,19 @@ where
             return Err(ServiceError::EmailExists);
         }
 
-        // Create new user
-        let user = User::new(
+        // Create new user, owned by its creator
+        let mut new_user = User::new(
             user.username,
             user.email,
             user.full_name,
             user.role.unwrap_or(UserRole::User),
         );
+        new_user.subject = user.subject;
 
-        self.repository.save(user).await.map_err(ServiceError::from)
+        self.repository
+            .save(new_user)
+            .await
+            .map_err(ServiceError::from)
     }
 
     async fn update_user(&self, id: Uuid, user: UpdateUserDto) -> Result<User, ServiceError> {
@@ -353,10 +448,21 @@ where
 
         existing.updated_at = chrono::Utc::now();
 
-        self.repository
+        let updated = self
+            .repository
             .save(existing)
             .await
-            .map_err(ServiceError::from)
+            .map_err(ServiceError::from)?;
+        self.invalidate_cached_user(id).await;
+        Ok(updated)
+    }
+
+    async fn update_user_if_unmodified(
+        &self,
+        user: UpdateUserDto,
+        expected: &User,
+    ) -> Result<User, ServiceError> {
+        UserService::update_user_if_unmodified(self, user, expected).await
     }
 
     async fn delete_user(&self, id: Uuid) -> Result<(), ServiceError> {
@@ -366,6 +472,7 @@ where
                     .delete(id)
                     .await
                     .map_err(ServiceError::from)?;
+                self.invalidate_cached_user(id).await;
                 Ok(())
             }
             Ok(None) => Err(ServiceError::UserNotFound),